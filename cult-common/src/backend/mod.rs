@@ -3,6 +3,7 @@ use chrono::{DateTime, Local};
 use ritelinked::LinkedHashMap;
 use serde::{Deserialize, Deserializer, Serialize};
 use serde_json::Map;
+use strum::Display;
 use tsify_next::Tsify;
 use utoipa::ToSchema;
 use std::collections::HashMap;
@@ -11,23 +12,98 @@ use std::sync::{Arc, Mutex};
 use std::vec;
 use wasm_bindgen::prelude::*;
 
-use crate::dto::board::{DtoCategory, DtoJeopardyBoard, DtoQuestion};
+use crate::dto::board::{DtoCategory, DtoFinalJeopardy, DtoFinalJeopardyEntry, DtoJeopardyBoard, DtoEstimation, DtoEstimationEntry, DtoJeopardyRound, DtoMultipleChoice, BoardProblem, DTOBoardProblem, DtoMultipleChoiceEntry, DtoQuestion, DtoWrittenAnswerEntry, DtoWrittenAnswers};
 use crate::wasm_lib::ids::lobby::LobbyId;
+use crate::wasm_lib::ids::team::TeamId;
 use crate::wasm_lib::ids::usersession::UserSessionId;
 use crate::wasm_lib::ids::websocketsession::{self, WebsocketSessionId};
-use crate::wasm_lib::websocket_events::MediaStatus;
-use crate::wasm_lib::{JeopardyMode, Media, MediaType, NumberScope, QuestionType, Vector2D, VideoType};
+use crate::wasm_lib::websocket_events::{GameStateEvent, MediaStatus};
+use crate::wasm_lib::{Estimation, ImageType, JeopardyMode, Media, MediaType, MultipleChoice, NumberScope, QuestionType, Vector2D, VideoType};
+
+#[derive(Tsify, Debug, Clone, Serialize, Deserialize, Eq, PartialEq, Display)]
+pub enum GameState{
+    Waiting,
+    Starting,
+    Playing,
+    Paused,
+    End
+}
+
+impl GameState{
+
+    pub fn open(&self) -> bool {
+        match self {
+            GameState::Waiting => true,
+            GameState::Starting => false,
+            GameState::Playing => false,
+            GameState::Paused => false,
+            GameState::End => true,
+        }
+    }
+
+    pub fn accepts_board_actions(&self) -> bool {
+        matches!(self, GameState::Playing)
+    }
+
+    /// Returns the state the host event leads to, `None` if it doesn't apply to the current state.
+    pub fn transition(&self, event: &GameStateEvent) -> Option<GameState> {
+        match (self, event) {
+            (GameState::Waiting, GameStateEvent::Start) => Some(GameState::Starting),
+            (GameState::Playing, GameStateEvent::Pause) => Some(GameState::Paused),
+            (GameState::Paused, GameStateEvent::Resume) => Some(GameState::Playing),
+            (GameState::Starting | GameState::Playing | GameState::Paused, GameStateEvent::End) => Some(GameState::End),
+            _ => None,
+        }
+    }
+}
+
+/// Body of a lobby creation, either a single board or an ordered list of rounds.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(untagged)]
+pub enum LobbyCreateRequest {
+    Rounds(Vec<JeopardyRound>),
+    Board(Box<JeopardyBoard>),
+}
+
+impl LobbyCreateRequest {
+
+    pub fn rounds(self) -> Vec<JeopardyRound> {
+        match self {
+            LobbyCreateRequest::Rounds(rounds) => rounds,
+            LobbyCreateRequest::Board(board) => vec![JeopardyRound::new("Jeopardy", *board)],
+        }
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq, ToSchema)]
 pub enum LobbyCreateResponse {
     Created(LobbyId),
     Error(String),
+    Invalid(Vec<DTOBoardProblem>),
 }
 
 #[derive(Tsify,Debug, Clone, Serialize, ToSchema)]
 pub struct JeopardyBoard {
     pub title: String,
     pub categories: Vec<Category>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[tsify(optional)]
+    pub final_jeopardy: Option<FinalJeopardy>,
+    #[serde(skip_serializing)]
+    #[tsify(optional)]
+    pub final_jeopardy_state: Option<FinalJeopardyState>,
+    #[serde(skip_serializing)]
+    #[tsify(optional)]
+    pub daily_double: Option<DailyDoubleState>,
+    #[serde(skip_serializing)]
+    #[tsify(optional)]
+    pub written_answers: Option<WrittenAnswerState>,
+    #[serde(skip_serializing)]
+    #[tsify(optional)]
+    pub multiple_choice: Option<MultipleChoiceState>,
+    #[serde(skip_serializing)]
+    #[tsify(optional)]
+    pub estimation: Option<EstimationState>,
     #[serde(skip_serializing)]
     #[tsify(optional)]
     pub current: Option<Vector2D>,
@@ -81,6 +157,8 @@ impl JeopardyBoard {
                     question: question_name,
                     question_type: question_type,
                     answer: answer_name,
+                    daily_double: question == 4 && category == 1,
+                    alternate_answers: vec![],
                     open: false,
                     won_user_id: None,
                 };
@@ -90,9 +168,20 @@ impl JeopardyBoard {
 
             categories.push(Category::new(category_name, questions))
         }
+        let final_jeopardy = FinalJeopardy {
+            category: "Final_Category".to_string(),
+            question: "final_question".to_string(),
+            answer: "final_answer".to_string(),
+        };
         JeopardyBoard {
             title: "Default JeopardyBoard".to_string(),
             categories,
+            final_jeopardy: Some(final_jeopardy),
+            final_jeopardy_state: None,
+            daily_double: None,
+            written_answers: None,
+            multiple_choice: None,
+            estimation: None,
             current: None,
             create: Local::now(),
             action_state: Arc::new(Mutex::new(ActionState::None)),
@@ -116,10 +205,7 @@ impl JeopardyBoard {
                             x: row_index,
                             y: col_index,
                         };
-                        match self.current {
-                        None => question.clone().dto(false, current),
-                        Some(vec) =>  question.clone().dto(vec.eq(&current), current)
-                        }
+                        self.question_dto(question, current)
                     })
                     .collect::<Vec<DtoQuestion>>();
 
@@ -130,22 +216,45 @@ impl JeopardyBoard {
             })
             .collect::<Vec<DtoCategory>>();
 
-        let current = match self.current {
-            None => None,
-            Some(vec) => {
-                let question = self.get_question(vec).unwrap();
-                Some(question.dto(true, vec))
-            }
-        };
+        let current = self.current_dto();
         DtoJeopardyBoard {
             creator,
             categories: cat,
+            final_jeopardy: self.final_jeopardy_dto(),
+            daily_double: self.daily_double.clone(),
+            written_answers: self.written_answers.as_ref().map(|written_answers| written_answers.dto()),
+            multiple_choice: self.multiple_choice_dto(),
+            estimation: self.estimation_dto(),
             current,
             action_state: self.action_state.lock().expect("Error while locking action state").clone(),
             buzzer_state: self.buzzer_state.lock().expect("Error while locking buzzer state").clone(),
         }
     }
 
+    /// The clue of a Daily Double stays hidden until the wager has been placed.
+    pub fn question_dto(&self, question: &Question, vector2d: Vector2D) -> DtoQuestion {
+        let is_current = self.current.is_some_and(|current| current.eq(&vector2d));
+        let mut dto = question.clone().dto(is_current, vector2d);
+        if is_current && self.daily_double.as_ref().is_some_and(|daily_double| daily_double.wager.is_none()) {
+            dto.question_text = None;
+        }
+        dto
+    }
+
+    pub fn current_dto(&self) -> Option<DtoQuestion> {
+        let current = self.current?;
+        let question = self.get_question(current)?;
+        Some(self.question_dto(&question, current))
+    }
+
+    pub fn max_value(&self) -> i32 {
+        self.categories.iter()
+            .flat_map(|category| category.questions.iter())
+            .map(|question| question.value)
+            .max()
+            .unwrap_or(0)
+    }
+
     pub fn get_question(&self, vector2d: Vector2D) -> Option<Question> {
         if let Some(categories) = self.categories.get(vector2d.x) {
             if let Some(question) = categories.questions.get(vector2d.y) {
@@ -180,21 +289,181 @@ impl JeopardyBoard {
 
     pub fn get_value_and_remove_current(&mut self, won_user_id: &UserSessionId) -> Option<i32> {
         let mut value : Option<i32> = None;
+        // the wager is only at stake for the player who selected the Daily Double
+        let wager = self.daily_double.take()
+            .filter(|daily_double| daily_double.user_session_id.as_ref() == Some(won_user_id))
+            .and_then(|daily_double| daily_double.wager);
+        self.written_answers = None;
+        self.multiple_choice = None;
+        self.estimation = None;
         if let Some(current) = self.current {
             if let Some(question) = self.get_mut_question(current) {
                 question.open = true;
                 question.won_user_id = Some(won_user_id.clone());
-                value = Some(question.value);
+                value = Some(wager.unwrap_or(question.value));
             }
         }
         self.current = None;
         value
     }
 
+    /// Closes the current question without a winner.
+    pub fn close_current(&mut self) {
+        if let Some(current) = self.current {
+            if let Some(question) = self.get_mut_question(current) {
+                question.open = true;
+            }
+        }
+        self.daily_double = None;
+        self.written_answers = None;
+        self.multiple_choice = None;
+        self.estimation = None;
+        self.current = None;
+    }
+
+    pub fn multiple_choice_dto(&self) -> Option<DtoMultipleChoice> {
+        let state = self.multiple_choice.as_ref()?;
+        let question = self.get_current()?;
+        let multiple_choice = question.question_type.get_multiple_choice()?;
+        Some(state.dto(multiple_choice))
+    }
 
+    pub fn estimation_dto(&self) -> Option<DtoEstimation> {
+        let state = self.estimation.as_ref()?;
+        let question = self.get_current()?;
+        let estimation = question.question_type.get_estimation()?;
+        Some(state.dto(estimation))
+    }
+
+    pub fn final_jeopardy_dto(&self) -> Option<DtoFinalJeopardy> {
+        let final_jeopardy = self.final_jeopardy.as_ref()?;
+        match &self.final_jeopardy_state {
+            None => Some(DtoFinalJeopardy {
+                category: final_jeopardy.category.clone(),
+                stage: FinalJeopardyStage::Pending,
+                question: None,
+                answer: None,
+                entries: vec![],
+            }),
+            Some(state) => Some(state.dto(final_jeopardy)),
+        }
+    }
+
+    pub fn validate(&self) -> Vec<DTOBoardProblem> {
+        let mut problems = Vec::new();
+        let expected_length = self.categories.first().map(|category| category.questions.len()).unwrap_or(0);
+        for (x, category) in self.categories.iter().enumerate() {
+            if category.questions.is_empty() {
+                problems.push(DTOBoardProblem::category(x, BoardProblem::EmptyCategory));
+            } else if category.questions.len() != expected_length {
+                problems.push(DTOBoardProblem::category(x, BoardProblem::UnequalCategoryLength(expected_length, category.questions.len())));
+            }
+            for (y, question) in category.questions.iter().enumerate() {
+                problems.extend(question.validate().into_iter().map(|problem| DTOBoardProblem::question(Vector2D { x, y }, problem)));
+            }
+        }
+        problems
+    }
+
+    /// Names of the media files used by the questions, with their position.
+    pub fn media_names(&self) -> Vec<(Vector2D, String)> {
+        let mut names = Vec::new();
+        for (x, category) in self.categories.iter().enumerate() {
+            for (y, question) in category.questions.iter().enumerate() {
+                for media in question.question_type.get_media() {
+                    names.push((Vector2D { x, y }, media.name));
+                }
+            }
+        }
+        names
+    }
+
+    /// Points every media entry named `from` to the file `to`.
+    pub fn rename_media(&mut self, from: &str, to: &str) {
+        for category in self.categories.iter_mut() {
+            for question in category.questions.iter_mut() {
+                if let QuestionType::Media(medias) = &mut question.question_type {
+                    for media in medias.iter_mut().filter(|media| media.name == from) {
+                        media.name = to.to_string();
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn progress(&self) -> BoardProgress {
+        let mut questions = Vec::new();
+        for (x, category) in self.categories.iter().enumerate() {
+            for (y, question) in category.questions.iter().enumerate() {
+                if question.open || question.won_user_id.is_some() {
+                    questions.push(QuestionProgress {
+                        vector2d: Vector2D { x, y },
+                        open: question.open,
+                        won_user_id: question.won_user_id.clone(),
+                    });
+                }
+            }
+        }
+        BoardProgress {
+            current: self.current,
+            questions,
+            final_jeopardy_state: self.final_jeopardy_state.clone(),
+            daily_double: self.daily_double.clone(),
+            written_answers: self.written_answers.clone(),
+            multiple_choice: self.multiple_choice.clone(),
+            estimation: self.estimation.clone(),
+        }
+    }
+
+    pub fn apply_progress(&mut self, progress: &BoardProgress) {
+        for question_progress in &progress.questions {
+            if let Some(question) = self.get_mut_question(question_progress.vector2d) {
+                question.open = question_progress.open;
+                question.won_user_id = question_progress.won_user_id.clone();
+            }
+        }
+        if self.final_jeopardy.is_some() {
+            self.final_jeopardy_state = progress.final_jeopardy_state.clone();
+        }
+        self.current = match progress.current {
+            Some(vector2d) if self.get_question(vector2d).is_some() => Some(vector2d),
+            _ => None,
+        };
+        if self.current.is_some() {
+            self.daily_double = progress.daily_double.clone();
+            self.written_answers = progress.written_answers.clone();
+            self.multiple_choice = progress.multiple_choice.clone();
+            self.estimation = progress.estimation.clone();
+        }
+    }
 
 }
 
+
+/// The parts of a running board that are skipped by `Serialize`, kept so a lobby can be restored.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct BoardProgress {
+    pub current: Option<Vector2D>,
+    pub questions: Vec<QuestionProgress>,
+    #[serde(default)]
+    pub final_jeopardy_state: Option<FinalJeopardyState>,
+    #[serde(default)]
+    pub daily_double: Option<DailyDoubleState>,
+    #[serde(default)]
+    pub written_answers: Option<WrittenAnswerState>,
+    #[serde(default)]
+    pub multiple_choice: Option<MultipleChoiceState>,
+    #[serde(default)]
+    pub estimation: Option<EstimationState>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuestionProgress {
+    pub vector2d: Vector2D,
+    pub open: bool,
+    pub won_user_id: Option<UserSessionId>,
+}
+
 impl<'de> Deserialize<'de> for JeopardyBoard {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -204,6 +473,8 @@ impl<'de> Deserialize<'de> for JeopardyBoard {
         struct PartialJeopardyBoard {
             title: String,
             categories: Vec<Category>,
+            #[serde(default)]
+            final_jeopardy: Option<FinalJeopardy>,
         }
 
         let partial_board = PartialJeopardyBoard::deserialize(deserializer)?;
@@ -226,6 +497,12 @@ impl<'de> Deserialize<'de> for JeopardyBoard {
         let board = JeopardyBoard {
             title: partial_board.title,
             categories: partial_board.categories,
+            final_jeopardy: partial_board.final_jeopardy,
+            final_jeopardy_state: None,
+            daily_double: None,
+            written_answers: None,
+            multiple_choice: None,
+            estimation: None,
             current: None,
             create: Local::now(),
             action_state:   Arc::new(Mutex::new(ActionState::None)),
@@ -245,6 +522,7 @@ impl<'de> Deserialize<'de> for JeopardyBoard {
 pub enum ActionState {
     None,
     MediaPlayer(MediaState),
+    ImageReveal(ImageRevealState),
 }
 
 
@@ -275,6 +553,14 @@ impl ActionState {
     }
 
 
+    pub fn get_mut_image_reveal(&mut self) -> Option<&mut ImageRevealState> {
+        match self {
+            ActionState::ImageReveal(image_reveal) => Some(image_reveal),
+            _ => None,
+        }
+    }
+
+
     pub fn get_media_status(&self) -> Option<&MediaStatus> {
         match self {
             ActionState::MediaPlayer(media) => Some(&media.status),
@@ -326,6 +612,66 @@ impl MediaState {
 
 
 
+/// Reveal progress of an image, at step 0 the image is fully modified and at `steps` it is shown unmodified.
+/// The tile order is shuffled once so every client reveals the same tiles.
+#[derive(Tsify,Debug, Clone, Serialize, Deserialize)]
+pub struct ImageRevealState {
+    pub image_types: Vec<ImageType>,
+    pub step: usize,
+    pub steps: usize,
+    pub tile_order: Vec<usize>,
+    pub started: i64,
+    pub last_updated: i64,
+}
+
+impl ImageRevealState {
+
+    pub fn new(image_types: &[ImageType]) -> Option<Self> {
+        let mut steps = 0;
+        for image_type in image_types {
+            steps = steps.max(image_type.steps()?);
+        }
+        if steps == 0 {
+            return None;
+        }
+        let tiles = image_types.iter().find_map(|image_type| match image_type {
+            ImageType::Tiles(_, _) => image_type.steps(),
+            _ => None,
+        }).unwrap_or(0);
+        let mut tile_order: Vec<usize> = (0..tiles).collect();
+        for index in (1..tile_order.len()).rev() {
+            tile_order.swap(index, rand::random::<usize>() % (index + 1));
+        }
+        let now = Local::now().timestamp_millis();
+        Some(ImageRevealState {
+            image_types: image_types.to_vec(),
+            step: 0,
+            steps,
+            tile_order,
+            started: now,
+            last_updated: now,
+        })
+    }
+
+    pub fn set_step(&mut self, step: usize) -> bool {
+        let step = step.min(self.steps);
+        if step == self.step {
+            return false;
+        }
+        self.step = step;
+        self.last_updated = Local::now().timestamp_millis();
+        true
+    }
+
+    /// Share of the image that has been revealed, from 0.0 to 1.0, usable for scoring by reveal stage.
+    pub fn progress(&self) -> f64 {
+        self.step as f64 / self.steps as f64
+    }
+
+}
+
+
+
 impl ActionState {
 
     pub fn update(&mut self, action: ActionState) {
@@ -344,6 +690,563 @@ impl ActionState {
 
 }
 
+/// One round of a lobby, question values are multiplied when the round starts.
+#[derive(Tsify, Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct JeopardyRound {
+    pub name: String,
+    #[serde(default = "JeopardyRound::default_multiplier")]
+    pub multiplier: i32,
+    pub board: JeopardyBoard,
+}
+
+impl JeopardyRound {
+
+    pub fn new(name: &str, board: JeopardyBoard) -> Self {
+        JeopardyRound {
+            name: name.to_string(),
+            multiplier: Self::default_multiplier(),
+            board,
+        }
+    }
+
+    fn default_multiplier() -> i32 {
+        1
+    }
+
+    /// Problems of the round that can be found without looking up the media files.
+    pub fn validate(&self, round: usize) -> Vec<DTOBoardProblem> {
+        let mut problems = self.board.validate();
+        for problem in problems.iter_mut() {
+            problem.round = Some(round);
+        }
+        if self.multiplier <= 0 {
+            problems.push(DTOBoardProblem::round(round, BoardProblem::InvalidMultiplier(self.multiplier)));
+        }
+        for (x, category) in self.board.categories.iter().enumerate() {
+            for (y, question) in category.questions.iter().enumerate() {
+                if question.value.checked_mul(self.multiplier).is_none() {
+                    let mut problem = DTOBoardProblem::question(Vector2D { x, y }, BoardProblem::ValueOverflow(question.value, self.multiplier));
+                    problem.round = Some(round);
+                    problems.push(problem);
+                }
+            }
+        }
+        if round == 0 && self.board.categories.is_empty() {
+            problems.push(DTOBoardProblem::round(round, BoardProblem::NoCategories));
+        } else if !self.is_playable() {
+            problems.push(DTOBoardProblem::round(round, BoardProblem::NotPlayable));
+        }
+        problems
+    }
+
+    /// A round needs questions on the board or a Final Jeopardy to be playable.
+    pub fn is_playable(&self) -> bool {
+        self.multiplier > 0 && (!self.board.categories.is_empty() || self.board.final_jeopardy.is_some())
+    }
+
+    pub fn start_board(&self) -> JeopardyBoard {
+        let mut board = self.board.clone();
+        for question in board.categories.iter_mut().flat_map(|category| category.questions.iter_mut()) {
+            question.value = question.value.saturating_mul(self.multiplier);
+        }
+        board.current = None;
+        board.daily_double = None;
+        board.final_jeopardy_state = None;
+        board.create = Local::now();
+        board.action_state = Arc::new(Mutex::new(ActionState::None));
+        board.buzzer_state = Arc::new(Mutex::new(BuzzerState::None));
+        board
+    }
+
+    pub fn dto(&self, index: usize, count: usize) -> DtoJeopardyRound {
+        DtoJeopardyRound {
+            name: self.name.clone(),
+            multiplier: self.multiplier,
+            index,
+            count,
+        }
+    }
+}
+
+#[derive(Tsify, Debug, Clone, Serialize, Deserialize, Eq, PartialEq, ToSchema)]
+pub struct FinalJeopardy {
+    pub category: String,
+    pub question: String,
+    pub answer: String,
+}
+
+
+#[derive(Tsify, Debug, Clone, Copy, Serialize, Deserialize, Eq, PartialEq)]
+#[tsify(namespace)]
+pub enum FinalJeopardyStage {
+    Pending,
+    Wagering,
+    Answering,
+    Revealing,
+    Finished,
+}
+
+
+#[derive(Tsify, Debug, Clone, Serialize, Deserialize)]
+pub struct FinalJeopardyEntry {
+    pub user_session_id: UserSessionId,
+    #[tsify(optional)]
+    pub wager: Option<i32>,
+    #[tsify(optional)]
+    pub answer: Option<String>,
+    pub revealed: bool,
+    #[tsify(optional)]
+    pub correct: Option<bool>,
+}
+
+
+/// Wagers and answers of the Final Jeopardy round, kept in reveal order (lowest score first).
+#[derive(Tsify, Debug, Clone, Serialize, Deserialize)]
+pub struct FinalJeopardyState {
+    pub stage: FinalJeopardyStage,
+    pub entries: Vec<FinalJeopardyEntry>,
+}
+
+impl FinalJeopardyState {
+
+    pub fn new(players: Vec<UserSessionId>) -> Self {
+        let entries = players.into_iter().map(|user_session_id| FinalJeopardyEntry {
+            user_session_id,
+            wager: None,
+            answer: None,
+            revealed: false,
+            correct: None,
+        }).collect();
+        FinalJeopardyState {
+            stage: FinalJeopardyStage::Wagering,
+            entries,
+        }
+    }
+
+    pub fn get_mut_entry(&mut self, user_session_id: &UserSessionId) -> Option<&mut FinalJeopardyEntry> {
+        self.entries.iter_mut().find(|entry| entry.user_session_id.eq(user_session_id))
+    }
+
+    pub fn next_unrevealed(&mut self) -> Option<&mut FinalJeopardyEntry> {
+        self.entries.iter_mut().find(|entry| !entry.revealed)
+    }
+
+    pub fn all_judged(&self) -> bool {
+        self.entries.iter().all(|entry| entry.correct.is_some())
+    }
+
+    pub fn dto(&self, final_jeopardy: &FinalJeopardy) -> DtoFinalJeopardy {
+        let question = match self.stage {
+            FinalJeopardyStage::Pending | FinalJeopardyStage::Wagering => None,
+            _ => Some(final_jeopardy.question.clone()),
+        };
+        let answer = match self.stage {
+            FinalJeopardyStage::Revealing | FinalJeopardyStage::Finished => Some(final_jeopardy.answer.clone()),
+            _ => None,
+        };
+        let entries = self.entries.iter().map(|entry| DtoFinalJeopardyEntry {
+            user_session_id: entry.user_session_id.clone(),
+            wagered: entry.wager.is_some(),
+            answered: entry.answer.is_some(),
+            wager: if entry.revealed { entry.wager } else { None },
+            answer: if entry.revealed { entry.answer.clone() } else { None },
+            correct: entry.correct,
+        }).collect();
+        DtoFinalJeopardy {
+            category: final_jeopardy.category.clone(),
+            stage: self.stage,
+            question,
+            answer,
+            entries,
+        }
+    }
+}
+
+
+
+/// YouTube video ids are 11 characters of letters, digits, `-` and `_`.
+pub fn is_youtube_id(id: &str) -> bool {
+    id.len() == 11 && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Lowercases the answer, drops punctuation, a leading question phrase like "what is" and articles.
+pub fn normalize_answer(answer: &str) -> String {
+    let cleaned: String = answer.to_lowercase().chars()
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect();
+    let mut words: Vec<&str> = cleaned.split_whitespace().collect();
+    if words.len() > 2 && matches!(words[0], "what" | "who" | "where" | "when") && matches!(words[1], "is" | "are" | "was" | "were") {
+        words.drain(..2);
+    }
+    words.retain(|word| !matches!(*word, "a" | "an" | "the"));
+    words.join(" ")
+}
+
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// After normalisation one typo per five characters of the expected answer is tolerated.
+pub fn answer_matches(given: &str, expected: &str) -> bool {
+    let given = normalize_answer(given);
+    let expected = normalize_answer(expected);
+    if given.is_empty() || expected.is_empty() {
+        return false;
+    }
+    edit_distance(&given, &expected) <= expected.chars().count() / 5
+}
+
+
+#[derive(Tsify, Debug, Clone, Serialize, Deserialize)]
+pub struct WrittenAnswerEntry {
+    pub user_session_id: UserSessionId,
+    #[tsify(optional)]
+    pub answer: Option<String>,
+    #[tsify(optional)]
+    pub suggested: Option<bool>,
+}
+
+/// Typed answers of a written question, graded once the host closes the answers.
+#[derive(Tsify, Debug, Clone, Serialize, Deserialize)]
+pub struct WrittenAnswerState {
+    pub closed: bool,
+    pub entries: Vec<WrittenAnswerEntry>,
+}
+
+impl WrittenAnswerState {
+
+    pub fn new(players: Vec<UserSessionId>) -> Self {
+        let entries = players.into_iter().map(|user_session_id| WrittenAnswerEntry {
+            user_session_id,
+            answer: None,
+            suggested: None,
+        }).collect();
+        WrittenAnswerState {
+            closed: false,
+            entries,
+        }
+    }
+
+    pub fn get_mut_entry(&mut self, user_session_id: &UserSessionId) -> Option<&mut WrittenAnswerEntry> {
+        self.entries.iter_mut().find(|entry| entry.user_session_id.eq(user_session_id))
+    }
+
+    pub fn close(&mut self, question: &Question) {
+        self.closed = true;
+        for entry in self.entries.iter_mut() {
+            entry.suggested = entry.answer.as_ref().map(|answer| question.accepts_answer(answer));
+        }
+    }
+
+    /// Answers stay hidden until the answers are closed.
+    pub fn dto(&self) -> DtoWrittenAnswers {
+        let entries = self.entries.iter().map(|entry| DtoWrittenAnswerEntry {
+            user_session_id: entry.user_session_id.clone(),
+            answered: entry.answer.is_some(),
+            answer: if self.closed { entry.answer.clone() } else { None },
+            suggested: entry.suggested,
+        }).collect();
+        DtoWrittenAnswers {
+            closed: self.closed,
+            entries,
+        }
+    }
+}
+
+
+
+#[derive(Tsify, Debug, Clone, Serialize, Deserialize)]
+pub struct MultipleChoiceEntry {
+    pub user_session_id: UserSessionId,
+    pub choice: usize,
+    pub elapsed_millis: i64,
+    #[tsify(optional)]
+    pub points: Option<i32>,
+}
+
+/// Locked in choices of a multiple choice question, the elapsed time is counted from `opened`.
+#[derive(Tsify, Debug, Clone, Serialize, Deserialize)]
+pub struct MultipleChoiceState {
+    pub opened: i64,
+    pub revealed: bool,
+    pub entries: Vec<MultipleChoiceEntry>,
+}
+
+impl MultipleChoiceState {
+
+    pub fn new(opened: i64) -> Self {
+        MultipleChoiceState {
+            opened,
+            revealed: false,
+            entries: vec![],
+        }
+    }
+
+    pub fn lock_in(&mut self, user_session_id: &UserSessionId, choice: usize, options: usize, now: i64) -> bool {
+        if self.revealed || choice >= options || self.entries.iter().any(|entry| entry.user_session_id.eq(user_session_id)) {
+            return false;
+        }
+        self.entries.push(MultipleChoiceEntry {
+            user_session_id: user_session_id.clone(),
+            choice,
+            elapsed_millis: now - self.opened,
+            points: None,
+        });
+        true
+    }
+
+    /// Awards the correct players, the fastest one is returned first.
+    pub fn reveal(&mut self, multiple_choice: &MultipleChoice, value: i32, now: i64) -> Vec<(UserSessionId, i32)> {
+        self.revealed = true;
+        let duration = (now - self.opened).max(1) as f64;
+        self.entries.sort_by_key(|entry| entry.elapsed_millis);
+        let mut awards = Vec::new();
+        for entry in self.entries.iter_mut() {
+            if !multiple_choice.correct.contains(&entry.choice) {
+                entry.points = Some(0);
+                continue;
+            }
+            let points = match multiple_choice.speed_bonus {
+                true => {
+                    let remaining = 1.0 - (entry.elapsed_millis as f64 / duration).clamp(0.0, 1.0);
+                    (value as f64 * (0.5 + remaining / 2.0)).round() as i32
+                }
+                false => value,
+            };
+            entry.points = Some(points);
+            awards.push((entry.user_session_id.clone(), points));
+        }
+        awards
+    }
+
+    pub fn dto(&self, multiple_choice: &MultipleChoice) -> DtoMultipleChoice {
+        let entries = self.entries.iter().map(|entry| DtoMultipleChoiceEntry {
+            user_session_id: entry.user_session_id.clone(),
+            choice: if self.revealed { Some(entry.choice) } else { None },
+            points: entry.points,
+        }).collect();
+        let distribution = self.revealed.then(|| {
+            let mut distribution = vec![0; multiple_choice.options.len()];
+            for entry in &self.entries {
+                if let Some(count) = distribution.get_mut(entry.choice) {
+                    *count += 1;
+                }
+            }
+            distribution
+        });
+        DtoMultipleChoice {
+            revealed: self.revealed,
+            entries,
+            distribution,
+            correct: self.revealed.then(|| multiple_choice.correct.clone()),
+        }
+    }
+}
+
+
+
+#[derive(Tsify, Debug, Clone, Serialize, Deserialize)]
+pub struct EstimationEntry {
+    pub user_session_id: UserSessionId,
+    pub guess: i64,
+    #[tsify(optional)]
+    pub rank: Option<usize>,
+    #[tsify(optional)]
+    pub points: Option<i32>,
+}
+
+/// Guesses of an estimation question, players may change their guess until the reveal.
+#[derive(Tsify, Debug, Clone, Serialize, Deserialize, Default)]
+pub struct EstimationState {
+    pub revealed: bool,
+    pub entries: Vec<EstimationEntry>,
+}
+
+impl EstimationState {
+
+    pub fn submit_guess(&mut self, user_session_id: &UserSessionId, guess: i64) -> bool {
+        if self.revealed {
+            return false;
+        }
+        match self.entries.iter_mut().find(|entry| entry.user_session_id.eq(user_session_id)) {
+            Some(entry) => entry.guess = guess,
+            None => self.entries.push(EstimationEntry {
+                user_session_id: user_session_id.clone(),
+                guess,
+                rank: None,
+                points: None,
+            }),
+        }
+        true
+    }
+
+    /// Ranks the guesses by distance, equal distances share a rank.
+    /// Every guess ranked within `winners` earns the value, the closest one is returned first.
+    pub fn reveal(&mut self, estimation: &Estimation, value: i32) -> Vec<(UserSessionId, i32)> {
+        self.revealed = true;
+        let answer = estimation.answer.unwrap_or(0);
+        self.entries.sort_by_key(|entry| entry.guess.abs_diff(answer));
+        let mut awards = Vec::new();
+        let mut previous_distance = None;
+        let mut rank = 0;
+        for (index, entry) in self.entries.iter_mut().enumerate() {
+            let distance = entry.guess.abs_diff(answer);
+            if previous_distance != Some(distance) {
+                rank = index + 1;
+                previous_distance = Some(distance);
+            }
+            entry.rank = Some(rank);
+            let points = if rank <= estimation.winners { value } else { 0 };
+            entry.points = Some(points);
+            if points > 0 {
+                awards.push((entry.user_session_id.clone(), points));
+            }
+        }
+        awards
+    }
+
+    pub fn dto(&self, estimation: &Estimation) -> DtoEstimation {
+        let answer = estimation.answer.unwrap_or(0);
+        let entries = self.entries.iter().map(|entry| DtoEstimationEntry {
+            user_session_id: entry.user_session_id.clone(),
+            guess: if self.revealed { Some(entry.guess) } else { None },
+            distance: if self.revealed { Some(entry.guess.abs_diff(answer)) } else { None },
+            rank: entry.rank,
+            points: entry.points,
+        }).collect();
+        DtoEstimation {
+            revealed: self.revealed,
+            answer: if self.revealed { estimation.answer } else { None },
+            entries,
+        }
+    }
+}
+
+
+
+#[derive(Tsify, Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct Team {
+    pub team_id: TeamId,
+    pub name: String,
+}
+
+impl Team {
+    pub fn new(name: &str) -> Self {
+        Team {
+            team_id: TeamId::random(),
+            name: name.trim().to_string(),
+        }
+    }
+}
+
+
+
+/// Timers of a lobby, a missing duration disables the timer.
+#[derive(Tsify, Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct LobbyTimers {
+    pub buzz_grace_millis: u64,
+    #[tsify(optional)]
+    pub buzz_window_secs: Option<u64>,
+    #[tsify(optional)]
+    pub answer_time_secs: Option<u64>,
+    pub auto_reveal: bool,
+}
+
+impl Default for LobbyTimers {
+    fn default() -> Self {
+        LobbyTimers {
+            buzz_grace_millis: 2000,
+            buzz_window_secs: None,
+            answer_time_secs: None,
+            auto_reveal: false,
+        }
+    }
+}
+
+/// How buzzes are ordered: by arrival on the server, by arrival minus half the
+/// measured round trip, or by the client timestamp mapped onto the server clock.
+#[derive(Tsify, Debug, Copy, Clone, Serialize, Deserialize, Eq, PartialEq, Default)]
+pub enum BuzzerMode {
+    #[default]
+    ServerTime,
+    PingCompensated,
+    ClientTimestamp,
+}
+
+/// The tolerance caps the latency compensation and how far a client timestamp
+/// may deviate from the ping compensated time before it is ignored.
+/// Players buzzing during a question before the buzzer opens are locked out
+/// for `early_buzz_lockout_millis`, if set.
+#[derive(Tsify, Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct BuzzerSettings {
+    pub mode: BuzzerMode,
+    pub tolerance_millis: u64,
+    #[serde(default)]
+    #[tsify(optional)]
+    pub early_buzz_lockout_millis: Option<u64>,
+}
+
+impl Default for BuzzerSettings {
+    fn default() -> Self {
+        BuzzerSettings {
+            mode: BuzzerMode::ServerTime,
+            tolerance_millis: 150,
+            early_buzz_lockout_millis: None,
+        }
+    }
+}
+
+#[derive(Tsify, Debug, Copy, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub enum CountdownKind {
+    Buzzer,
+    Answer,
+}
+
+
+
+/// Wager of the player answering the current Daily Double.
+#[derive(Tsify, Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct DailyDoubleState {
+    #[tsify(optional)]
+    pub user_session_id: Option<UserSessionId>,
+    #[tsify(optional)]
+    pub wager: Option<i32>,
+    pub min_wager: i32,
+    pub max_wager: i32,
+}
+
+impl DailyDoubleState {
+
+    pub const MIN_WAGER: i32 = 5;
+
+    /// Players may wager up to their score, or up to the highest value on the board if that is more.
+    pub fn new(user_session_id: Option<UserSessionId>, score: i32, max_value: i32) -> Self {
+        let max_wager = score.max(max_value);
+        DailyDoubleState {
+            user_session_id,
+            wager: None,
+            min_wager: Self::MIN_WAGER.min(max_wager),
+            max_wager,
+        }
+    }
+
+    pub fn is_valid_wager(&self, wager: i32) -> bool {
+        wager >= self.min_wager && wager <= self.max_wager
+    }
+}
+
+
+
 #[derive(Tsify,Debug, Clone, Serialize, Deserialize)]
 pub enum  BuzzerState {
     None,
@@ -412,6 +1315,10 @@ pub struct Question {
     pub question: String,
     pub value: i32,
     pub answer: String,
+    #[serde(default)]
+    pub daily_double: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub alternate_answers: Vec<String>,
     #[serde(skip_serializing)]
     pub open: bool,
     #[serde(skip_serializing)]
@@ -423,6 +1330,37 @@ pub struct Question {
 
 impl Question {
 
+    pub fn validate(&self) -> Vec<BoardProblem> {
+        let mut problems = Vec::new();
+        if self.value < 0 {
+            problems.push(BoardProblem::NegativeValue(self.value));
+        }
+        match &self.question_type {
+            QuestionType::MultipleChoice(multiple_choice) if !multiple_choice.is_valid() => problems.push(BoardProblem::InvalidMultipleChoice),
+            QuestionType::Estimation(estimation) if !estimation.is_valid() => problems.push(BoardProblem::InvalidEstimation),
+            QuestionType::MultipleChoice(_) | QuestionType::Estimation(_) => {}
+            _ if self.answer.trim().is_empty() => problems.push(BoardProblem::EmptyAnswer),
+            _ => {}
+        }
+        match &self.question_type {
+            QuestionType::Media(medias) if medias.is_empty() => problems.push(BoardProblem::EmptyMedia),
+            QuestionType::Media(medias) => {
+                for media in medias {
+                    problems.extend(media.media_type.validate());
+                }
+            }
+            QuestionType::Youtube(id) if !is_youtube_id(id) => problems.push(BoardProblem::InvalidYoutubeId(id.clone())),
+            _ => {}
+        }
+        problems
+    }
+
+    pub fn accepts_answer(&self, given: &str) -> bool {
+        std::iter::once(&self.answer)
+            .chain(self.alternate_answers.iter())
+            .any(|expected| answer_matches(given, expected))
+    }
+
     pub fn dto(self, current: bool, vector2d: Vector2D) -> DtoQuestion {
         let question_text = match current {
             true => Some(self.question),
@@ -432,8 +1370,14 @@ impl Question {
             true => Some(self.answer),
             false => None,
         };
+        let correct_options = match self.open {
+            true => self.question_type.get_multiple_choice().map(|multiple_choice| multiple_choice.correct.clone()),
+            false => None,
+        };
         DtoQuestion {
-            question_type: self.question_type,
+            daily_double: self.daily_double && (current || self.open),
+            question_type: self.question_type.without_solution(),
+            correct_options,
             value: self.value,
             question_text,
             answer,
@@ -454,6 +1398,10 @@ impl<'de> Deserialize<'de> for Question {
             question: String,
             value: i32,
             answer: String,
+            #[serde(default)]
+            daily_double: bool,
+            #[serde(default)]
+            alternate_answers: Vec<String>,
         }
         let partial_question = PartialQuestion::deserialize(deserializer)?;
         let question = Question {
@@ -461,10 +1409,206 @@ impl<'de> Deserialize<'de> for Question {
             question: partial_question.question,
             value: partial_question.value,
             answer: partial_question.answer,
+            daily_double: partial_question.daily_double,
+            alternate_answers: partial_question.alternate_answers,
             open: false,
             won_user_id: None,
         };
 
         Ok(question)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GAME_STATES: [GameState; 5] = [GameState::Waiting, GameState::Starting, GameState::Playing, GameState::Paused, GameState::End];
+    const GAME_STATE_EVENTS: [GameStateEvent; 4] = [GameStateEvent::Start, GameStateEvent::Pause, GameStateEvent::Resume, GameStateEvent::End];
+
+    #[test]
+    fn game_state_follows_the_host_events() {
+        assert_eq!(GameState::Waiting.transition(&GameStateEvent::Start), Some(GameState::Starting));
+        assert_eq!(GameState::Playing.transition(&GameStateEvent::Pause), Some(GameState::Paused));
+        assert_eq!(GameState::Paused.transition(&GameStateEvent::Resume), Some(GameState::Playing));
+        for game_state in [GameState::Starting, GameState::Playing, GameState::Paused] {
+            assert_eq!(game_state.transition(&GameStateEvent::End), Some(GameState::End));
+        }
+    }
+
+    #[test]
+    fn game_state_rejects_illegal_transitions() {
+        let legal = [
+            (GameState::Waiting, GameStateEvent::Start),
+            (GameState::Playing, GameStateEvent::Pause),
+            (GameState::Paused, GameStateEvent::Resume),
+            (GameState::Starting, GameStateEvent::End),
+            (GameState::Playing, GameStateEvent::End),
+            (GameState::Paused, GameStateEvent::End),
+        ];
+        for game_state in &GAME_STATES {
+            for event in &GAME_STATE_EVENTS {
+                if legal.iter().any(|(state, legal_event)| state == game_state && legal_event == event) {
+                    continue;
+                }
+                assert_eq!(game_state.transition(event), None, "{} accepted {:?}", game_state, event);
+            }
+        }
+    }
+
+    #[test]
+    fn normalize_answer_drops_question_phrase_articles_and_punctuation() {
+        assert_eq!(normalize_answer("What is the Eiffel-Tower?"), "eiffel tower");
+        assert_eq!(normalize_answer("  Who were  The Beatles "), "beatles");
+        assert_eq!(normalize_answer("what is"), "what is");
+        assert_eq!(normalize_answer("An apple a day"), "apple day");
+    }
+
+    #[test]
+    fn edit_distance_counts_insertions_deletions_and_substitutions() {
+        assert_eq!(edit_distance("", ""), 0);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("abc", ""), 3);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("paris", "paris"), 0);
+        assert_eq!(edit_distance("über", "uber"), 1);
+    }
+
+    #[test]
+    fn answer_matches_tolerates_one_typo_per_five_characters() {
+        // four characters allow no typo
+        assert!(answer_matches("rome", "Rome"));
+        assert!(!answer_matches("rone", "Rome"));
+        // five characters allow one
+        assert!(answer_matches("pariz", "Paris"));
+        assert!(!answer_matches("parzz", "Paris"));
+        // nine characters still allow one, ten allow two
+        assert!(answer_matches("what is the amsterdan", "Amsterdam!"));
+        assert!(!answer_matches("amstedan", "Amsterdam!"));
+        assert!(answer_matches("kopenhagan", "Copenhagen"));
+        assert!(!answer_matches("kopanhagan", "Copenhagen"));
+    }
+
+    #[test]
+    fn answer_matches_rejects_empty_answers() {
+        assert!(!answer_matches("", "Paris"));
+        assert!(!answer_matches("the", "Paris"));
+        assert!(!answer_matches("Paris", ""));
+    }
+
+    #[test]
+    fn board_progress_survives_a_stored_snapshot() {
+        let mut board = JeopardyBoard::default(JeopardyMode::NORMAL);
+        let winner = UserSessionId::of(7);
+        board.get_mut_question(Vector2D { x: 0, y: 1 }).expect("question missing").open = true;
+        board.get_mut_question(Vector2D { x: 2, y: 3 }).expect("question missing").won_user_id = Some(winner.clone());
+        board.current = Some(Vector2D { x: 1, y: 1 });
+        board.daily_double = Some(DailyDoubleState::new(Some(winner.clone()), 100, 500));
+
+        let stored = serde_json::to_string(&board.progress()).expect("progress not serializable");
+        let progress: BoardProgress = serde_json::from_str(&stored).expect("progress not deserializable");
+        let mut restored = JeopardyBoard::default(JeopardyMode::NORMAL);
+        restored.apply_progress(&progress);
+
+        assert!(restored.get_question(Vector2D { x: 0, y: 1 }).expect("question missing").open);
+        assert_eq!(restored.get_question(Vector2D { x: 2, y: 3 }).expect("question missing").won_user_id, Some(winner.clone()));
+        assert!(!restored.get_question(Vector2D { x: 0, y: 0 }).expect("question missing").open);
+        assert_eq!(restored.current, Some(Vector2D { x: 1, y: 1 }));
+        assert_eq!(restored.daily_double.and_then(|daily_double| daily_double.user_session_id), Some(winner));
+    }
+
+    #[test]
+    fn board_progress_outside_the_board_is_dropped() {
+        let progress = BoardProgress {
+            current: Some(Vector2D { x: 99, y: 0 }),
+            daily_double: Some(DailyDoubleState::new(None, 0, 500)),
+            questions: vec![QuestionProgress { vector2d: Vector2D { x: 0, y: 99 }, open: true, won_user_id: None }],
+            ..Default::default()
+        };
+
+        let mut board = JeopardyBoard::default(JeopardyMode::NORMAL);
+        board.apply_progress(&progress);
+        assert_eq!(board.current, None);
+        assert!(board.daily_double.is_none());
+        assert!(board.categories.iter().flat_map(|category| category.questions.iter()).all(|question| !question.open));
+    }
+
+    fn round_problems(round: &JeopardyRound) -> Vec<BoardProblem> {
+        round.validate(1).into_iter().map(|problem| problem.problem).collect()
+    }
+
+    #[test]
+    fn round_start_board_applies_the_multiplier() {
+        let mut round = JeopardyRound::new("Double Jeopardy", JeopardyBoard::default(JeopardyMode::NORMAL));
+        round.multiplier = 2;
+        let values: Vec<i32> = round.board.categories[0].questions.iter().map(|question| question.value * 2).collect();
+        let board = round.start_board();
+        assert_eq!(board.categories[0].questions.iter().map(|question| question.value).collect::<Vec<i32>>(), values);
+        assert!(!round_problems(&round).iter().any(|problem| matches!(problem, BoardProblem::ValueOverflow(_, _))));
+    }
+
+    #[test]
+    fn round_reports_values_that_overflow_with_the_multiplier() {
+        let mut round = JeopardyRound::new("Double Jeopardy", JeopardyBoard::default(JeopardyMode::NORMAL));
+        round.multiplier = 2;
+        round.board.categories[1].questions[2].value = i32::MAX;
+        let problems = round.validate(1);
+        let overflow = problems.iter().find(|problem| matches!(problem.problem, BoardProblem::ValueOverflow(_, _))).expect("overflow not reported");
+        assert_eq!(overflow, &DTOBoardProblem { round: Some(1), category: Some(1), question: Some(2), problem: BoardProblem::ValueOverflow(i32::MAX, 2) });
+        assert_eq!(round.start_board().categories[1].questions[2].value, i32::MAX);
+    }
+
+    #[test]
+    fn round_needs_a_positive_multiplier() {
+        let mut round = JeopardyRound::new("Jeopardy", JeopardyBoard::default(JeopardyMode::NORMAL));
+        round.multiplier = 0;
+        let problems = round_problems(&round);
+        assert!(problems.contains(&BoardProblem::InvalidMultiplier(0)));
+        assert!(problems.contains(&BoardProblem::NotPlayable));
+    }
+
+    fn estimation_state(guesses: &[(usize, i64)]) -> EstimationState {
+        let mut state = EstimationState::default();
+        for (id, guess) in guesses {
+            assert!(state.submit_guess(&UserSessionId::of(*id), *guess));
+        }
+        state
+    }
+
+    fn ranks(state: &EstimationState) -> Vec<(usize, Option<usize>, Option<i32>)> {
+        state.entries.iter().map(|entry| (entry.user_session_id.clone().id(), entry.rank, entry.points)).collect()
+    }
+
+    #[test]
+    fn estimation_ranks_by_distance_on_both_sides_of_the_answer() {
+        let mut state = estimation_state(&[(1, 130), (2, 95), (3, 100), (4, -500)]);
+        let awards = state.reveal(&Estimation { answer: Some(100), winners: 2 }, 300);
+        assert_eq!(awards, vec![(UserSessionId::of(3), 300), (UserSessionId::of(2), 300)]);
+        assert_eq!(ranks(&state), vec![(3, Some(1), Some(300)), (2, Some(2), Some(300)), (1, Some(3), Some(0)), (4, Some(4), Some(0))]);
+    }
+
+    #[test]
+    fn estimation_ties_share_a_rank_and_skip_the_next() {
+        let mut state = estimation_state(&[(1, 90), (2, 110), (3, 120)]);
+        let awards = state.reveal(&Estimation { answer: Some(100), winners: 1 }, 200);
+        assert_eq!(awards.len(), 2);
+        assert_eq!(ranks(&state), vec![(1, Some(1), Some(200)), (2, Some(1), Some(200)), (3, Some(3), Some(0))]);
+    }
+
+    #[test]
+    fn estimation_accepts_no_guesses_after_the_reveal() {
+        let mut state = estimation_state(&[(1, 5)]);
+        assert!(state.submit_guess(&UserSessionId::of(1), 7));
+        assert_eq!(state.entries.len(), 1);
+        state.reveal(&Estimation { answer: Some(7), winners: 1 }, 100);
+        assert!(!state.submit_guess(&UserSessionId::of(2), 7));
+        assert_eq!(state.entries.len(), 1);
+    }
+
+    #[test]
+    fn estimation_handles_extreme_guesses() {
+        let mut state = estimation_state(&[(1, i64::MIN), (2, i64::MAX)]);
+        let awards = state.reveal(&Estimation { answer: Some(0), winners: 1 }, 100);
+        assert_eq!(awards, vec![(UserSessionId::of(2), 100)]);
+    }
+}
@@ -4,20 +4,29 @@ use tsify_next::Tsify;
 use std::hash::Hash;
 use wasm_bindgen::prelude::*;
 
-use crate::backend::{ActionState, BuzzerState};
+use utoipa::ToSchema;
+
+use crate::backend::{ActionState, BuzzerState, CountdownKind, DailyDoubleState, FinalJeopardyStage};
+use crate::wasm_lib::hashs::validate::ValidateHash;
+use crate::wasm_lib::ids::board::BoardId;
+use crate::wasm_lib::ids::discord::DiscordID;
+use crate::wasm_lib::ids::team::TeamId;
 use crate::wasm_lib::ids::usersession::UserSessionId;
 use crate::wasm_lib::{DiscordUser, QuestionType, Vector2D};
 
 
 
 
-#[derive(Tsify,Default, Debug, Clone, PartialEq, Serialize, Deserialize, Eq, Hash)]
+#[derive(Tsify,Default, Debug, Clone, PartialEq, Serialize, Deserialize, Eq, Hash, ToSchema)]
 pub struct DTOSession {
     pub user_session_id: UserSessionId,
     pub score: i32,
     #[tsify(optional)]
     pub discord_user: Option<DiscordUser>,
     pub is_admin: bool,
+    #[serde(default)]
+    #[tsify(optional)]
+    pub team_id: Option<TeamId>,
 }
 
 impl DTOSession {
@@ -35,6 +44,16 @@ pub struct DtoJeopardyBoard {
     pub creator: UserSessionId,
     pub categories: Vec<DtoCategory>,
     #[tsify(optional)]
+    pub final_jeopardy: Option<DtoFinalJeopardy>,
+    #[tsify(optional)]
+    pub daily_double: Option<DailyDoubleState>,
+    #[tsify(optional)]
+    pub written_answers: Option<DtoWrittenAnswers>,
+    #[tsify(optional)]
+    pub multiple_choice: Option<DtoMultipleChoice>,
+    #[tsify(optional)]
+    pub estimation: Option<DtoEstimation>,
+    #[tsify(optional)]
     pub current: Option<DtoQuestion>,
     pub action_state: ActionState,
     pub buzzer_state: BuzzerState,
@@ -77,6 +96,118 @@ impl DtoJeopardyBoard {
 }
 
 
+/// Written answers as seen by the clients, the answers are shown once the host closes them.
+#[derive(Tsify,Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DtoWrittenAnswers {
+    pub closed: bool,
+    pub entries: Vec<DtoWrittenAnswerEntry>,
+}
+
+#[derive(Tsify,Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DtoWrittenAnswerEntry {
+    pub user_session_id: UserSessionId,
+    pub answered: bool,
+    #[tsify(optional)]
+    pub answer: Option<String>,
+    #[tsify(optional)]
+    pub suggested: Option<bool>,
+}
+
+/// Before the reveal only the players who locked in are shown,
+/// afterwards the choices, the distribution per option and the awarded points.
+#[derive(Tsify,Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DtoMultipleChoice {
+    pub revealed: bool,
+    pub entries: Vec<DtoMultipleChoiceEntry>,
+    #[tsify(optional)]
+    pub distribution: Option<Vec<usize>>,
+    #[tsify(optional)]
+    pub correct: Option<Vec<usize>>,
+}
+
+#[derive(Tsify,Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DtoMultipleChoiceEntry {
+    pub user_session_id: UserSessionId,
+    #[tsify(optional)]
+    pub choice: Option<usize>,
+    #[tsify(optional)]
+    pub points: Option<i32>,
+}
+
+/// Before the reveal only the players who guessed are shown, afterwards the guesses ranked by distance.
+#[derive(Tsify,Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DtoEstimation {
+    pub revealed: bool,
+    #[tsify(optional)]
+    pub answer: Option<i64>,
+    pub entries: Vec<DtoEstimationEntry>,
+}
+
+#[derive(Tsify,Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DtoEstimationEntry {
+    pub user_session_id: UserSessionId,
+    #[tsify(optional)]
+    pub guess: Option<i64>,
+    #[tsify(optional)]
+    pub distance: Option<u64>,
+    #[tsify(optional)]
+    pub rank: Option<usize>,
+    #[tsify(optional)]
+    pub points: Option<i32>,
+}
+
+/// Running countdown, the deadline is a unix timestamp in milliseconds.
+#[derive(Tsify,Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DtoCountdown {
+    pub kind: CountdownKind,
+    #[tsify(optional)]
+    pub user_session_id: Option<UserSessionId>,
+    pub deadline: i64,
+}
+
+/// A team with its members, the score is the sum of the member scores.
+#[derive(Tsify,Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DtoTeam {
+    pub team_id: TeamId,
+    pub name: String,
+    pub members: Vec<UserSessionId>,
+    pub score: i32,
+}
+
+#[derive(Tsify,Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DtoJeopardyRound {
+    pub name: String,
+    pub multiplier: i32,
+    pub index: usize,
+    pub count: usize,
+}
+
+/// Final Jeopardy as seen by the clients, wagers and answers stay hidden until the host reveals them.
+#[derive(Tsify,Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DtoFinalJeopardy {
+    pub category: String,
+    pub stage: FinalJeopardyStage,
+    #[tsify(optional)]
+    pub question: Option<String>,
+    #[tsify(optional)]
+    pub answer: Option<String>,
+    pub entries: Vec<DtoFinalJeopardyEntry>,
+}
+
+#[derive(Tsify,Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DtoFinalJeopardyEntry {
+    pub user_session_id: UserSessionId,
+    pub wagered: bool,
+    pub answered: bool,
+    #[tsify(optional)]
+    pub wager: Option<i32>,
+    #[tsify(optional)]
+    pub answer: Option<String>,
+    #[tsify(optional)]
+    pub correct: Option<bool>,
+}
+
+
 #[derive(Tsify,Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct DtoCategory {
     pub title: String,
@@ -102,4 +233,102 @@ pub struct DtoQuestion {
     #[tsify(optional)]
     pub won_user_id: Option<UserSessionId>,
     pub vector2d: Vector2D,
+    pub daily_double: bool,
+    #[serde(default)]
+    #[tsify(optional)]
+    pub correct_options: Option<Vec<usize>>,
+}
+
+
+
+#[derive(Tsify, Debug, Clone, Serialize, Deserialize, Eq, PartialEq, ToSchema)]
+pub struct DTOBoardData {
+    pub board_id: BoardId,
+    pub title: String,
+    pub owner: DiscordID,
+    pub revision: usize,
+    pub create_date: String,
+    pub update_date: String,
+}
+
+
+#[derive(Tsify, Debug, Clone, Serialize, Deserialize, Eq, PartialEq, ToSchema)]
+pub struct DTOBoardRevision {
+    pub board_id: BoardId,
+    pub title: String,
+    pub revision: usize,
+    pub update_date: String,
+}
+
+
+/// Manifest stored as `manifest.json` next to `board.json` in an exported board archive.
+#[derive(Tsify, Debug, Clone, Serialize, Deserialize, Eq, PartialEq, ToSchema)]
+pub struct DTOBoardArchiveManifest {
+    pub version: u32,
+    pub files: Vec<DTOBoardArchiveFile>,
+}
+
+/// A media file bundled in a board archive, stored under `path` inside the archive.
+#[derive(Tsify, Debug, Clone, Serialize, Deserialize, Eq, PartialEq, Hash, ToSchema)]
+pub struct DTOBoardArchiveFile {
+    pub name: String,
+    pub path: String,
+    pub file_type: String,
+    pub validate_hash: ValidateHash,
+}
+
+#[derive(Tsify, Debug, Clone, Serialize, Deserialize, Eq, PartialEq, ToSchema)]
+pub struct DTOBoardImport {
+    pub board: DTOBoardData,
+    pub imported: Vec<String>,
+    pub skipped: Vec<String>,
+}
+
+
+/// A problem found while validating a board, positioned by round, category and question where it applies.
+#[derive(Tsify, Debug, Clone, Serialize, Deserialize, Eq, PartialEq, Hash, ToSchema)]
+pub struct DTOBoardProblem {
+    #[tsify(optional)]
+    pub round: Option<usize>,
+    #[tsify(optional)]
+    pub category: Option<usize>,
+    #[tsify(optional)]
+    pub question: Option<usize>,
+    pub problem: BoardProblem,
+}
+
+impl DTOBoardProblem {
+    pub fn round(round: usize, problem: BoardProblem) -> Self {
+        DTOBoardProblem { round: Some(round), category: None, question: None, problem }
+    }
+
+    pub fn category(category: usize, problem: BoardProblem) -> Self {
+        DTOBoardProblem { round: None, category: Some(category), question: None, problem }
+    }
+
+    pub fn question(vector2d: Vector2D, problem: BoardProblem) -> Self {
+        DTOBoardProblem { round: None, category: Some(vector2d.x), question: Some(vector2d.y), problem }
+    }
+}
+
+#[derive(Tsify, Debug, Clone, Serialize, Deserialize, Eq, PartialEq, Hash, ToSchema)]
+#[tsify(namespace)]
+pub enum BoardProblem {
+    NoRounds,
+    NoCategories,
+    NotPlayable,
+    InvalidMultiplier(i32),
+    ValueOverflow(i32, i32),
+    EmptyCategory,
+    UnequalCategoryLength(usize, usize),
+    NegativeValue(i32),
+    EmptyAnswer,
+    EmptyMedia,
+    MissingMedia(String),
+    InvalidNumberScope(usize, usize),
+    InvalidYoutubeId(String),
+    InvalidImageReveal,
+    InvalidAudioSpeed(usize),
+    InvalidMultipleChoice,
+    InvalidEstimation,
 }
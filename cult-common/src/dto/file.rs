@@ -1,5 +1,6 @@
 
 use std::fs::File;
+use std::str::FromStr;
 
 use bytes::Bytes;
 use chrono::{DateTime, Local};
@@ -10,6 +11,7 @@ use wasm_bindgen::prelude::*;
 
 use crate::wasm_lib::hashs::validate::ValidateHash;
 use crate::wasm_lib::ids::discord::DiscordID;
+use crate::wasm_lib::ids::upload::UploadId;
 use crate::wasm_lib::{Media, NumberScope};
 
 
@@ -34,6 +36,30 @@ impl FileMultiPart {
 }
 
 
+/// Starts a chunked upload, or resumes the open one of the same file.
+#[derive(Tsify, Debug, Clone, Serialize, Deserialize, Eq, PartialEq, ToSchema)]
+pub struct DTOUploadRequest {
+    pub file_name: String,
+    pub file_type: String,
+    pub file_size: usize,
+    pub chunk_size: usize,
+    pub validate_hash: ValidateHash,
+}
+
+/// State of a chunked upload, `next_chunk` is the first chunk the server has not confirmed yet.
+#[derive(Tsify, Debug, Clone, Serialize, Deserialize, Eq, PartialEq, ToSchema)]
+pub struct DTOUploadSession {
+    pub upload_id: UploadId,
+    pub file_name: String,
+    pub file_size: usize,
+    pub chunk_size: usize,
+    pub chunk_count: usize,
+    pub received: Vec<usize>,
+    #[tsify(optional)]
+    pub next_chunk: Option<usize>,
+}
+
+
 #[derive(Tsify,Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
 pub struct DTOCFile{
     pub file_name: String,
@@ -61,4 +87,102 @@ pub struct DTOFileMetadata {
     pub file_type: String,
     pub validate_hash: ValidateHash,
     pub uploader: DiscordID,
+    pub tags: Vec<String>,
+    #[tsify(optional)]
+    pub folder: Option<String>,
+    #[tsify(optional)]
+    pub variant: Option<VariantKind>,
+    #[tsify(optional)]
+    pub processing: Option<MediaProcessing>,
+}
+
+
+/// Files the server derives from an uploaded file, stored next to it under their own name.
+#[derive(Tsify, Debug, Clone, Copy, Hash, Eq, PartialEq, Serialize, Deserialize, ToSchema)]
+pub enum VariantKind {
+    Thumbnail,
+    Poster,
+    Web,
+}
+
+impl VariantKind {
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            VariantKind::Thumbnail => "thumbnail",
+            VariantKind::Poster => "poster",
+            VariantKind::Web => "web",
+        }
+    }
+}
+
+impl FromStr for VariantKind {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<Self, ()> {
+        match input.to_lowercase().as_str() {
+            "thumbnail" => Ok(VariantKind::Thumbnail),
+            "poster" => Ok(VariantKind::Poster),
+            "web" => Ok(VariantKind::Web),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Tsify, Debug, Clone, Hash, Eq, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct MediaVariant {
+    pub kind: VariantKind,
+    pub file_name: String,
+    pub file_type: String,
+}
+
+/// What ffprobe found out about a file, fields it could not determine are left out.
+#[derive(Tsify, Debug, Clone, Hash, Eq, PartialEq, Serialize, Deserialize, Default, ToSchema)]
+pub struct MediaInfo {
+    #[tsify(optional)]
+    pub duration_ms: Option<u64>,
+    #[tsify(optional)]
+    pub width: Option<u32>,
+    #[tsify(optional)]
+    pub height: Option<u32>,
+    #[tsify(optional)]
+    pub format: Option<String>,
+    #[tsify(optional)]
+    pub video_codec: Option<String>,
+    #[tsify(optional)]
+    pub audio_codec: Option<String>,
+}
+
+/// Result of processing a file after its upload, `error` is set when ffmpeg failed.
+#[derive(Tsify, Debug, Clone, Hash, Eq, PartialEq, Serialize, Deserialize, Default, ToSchema)]
+pub struct MediaProcessing {
+    #[tsify(optional)]
+    pub info: Option<MediaInfo>,
+    pub variants: Vec<MediaVariant>,
+    #[tsify(optional)]
+    pub error: Option<String>,
+}
+
+impl MediaProcessing {
+
+    pub fn get_variant(&self, kind: VariantKind) -> Option<&MediaVariant> {
+        self.variants.iter().find(|variant| variant.kind == kind)
+    }
+}
+
+
+/// Gives a stored file a new name, boards using the old name are updated to the new one.
+#[derive(Tsify, Debug, Clone, Serialize, Deserialize, Eq, PartialEq, ToSchema)]
+pub struct DTOFileRename {
+    pub file_name: String,
+    pub new_name: String,
+}
+
+/// Replaces the tags and the folder of a stored file.
+#[derive(Tsify, Debug, Clone, Serialize, Deserialize, Eq, PartialEq, ToSchema)]
+pub struct DTOFileOrganize {
+    pub file_name: String,
+    pub tags: Vec<String>,
+    #[tsify(optional)]
+    pub folder: Option<String>,
 }
\ No newline at end of file
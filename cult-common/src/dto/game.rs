@@ -0,0 +1,54 @@
+use serde::{Deserialize, Serialize};
+use tsify_next::Tsify;
+use wasm_bindgen::prelude::*;
+
+use utoipa::ToSchema;
+
+use crate::dto::board::DTOSession;
+use crate::wasm_lib::ids::discord::DiscordID;
+use crate::wasm_lib::ids::game::GameId;
+use crate::wasm_lib::ids::lobby::LobbyId;
+use crate::wasm_lib::ids::usersession::UserSessionId;
+use crate::wasm_lib::Vector2D;
+
+
+/// Results of a finished game, players are ordered by their final score.
+#[derive(Tsify, Debug, Clone, Serialize, Deserialize, Eq, PartialEq, ToSchema)]
+pub struct DTOGameResult {
+    pub game_id: GameId,
+    pub lobby_id: LobbyId,
+    pub title: String,
+    pub players: Vec<DTOSession>,
+    pub questions: Vec<DTOQuestionResult>,
+    pub start_date: String,
+    pub end_date: String,
+}
+
+#[derive(Tsify, Debug, Clone, Serialize, Deserialize, Eq, PartialEq, ToSchema)]
+pub struct DTOQuestionResult {
+    pub round: usize,
+    pub category: String,
+    pub vector2d: Vector2D,
+    pub value: i32,
+    #[tsify(optional)]
+    pub won_user_id: Option<UserSessionId>,
+    pub buzzes: Vec<DTOBuzz>,
+}
+
+/// A buzz with its delay in milliseconds after the first buzz of the same buzzer round.
+#[derive(Tsify, Debug, Clone, Serialize, Deserialize, Eq, PartialEq, ToSchema)]
+pub struct DTOBuzz {
+    pub user_session_id: UserSessionId,
+    pub delay: i64,
+}
+
+#[derive(Tsify, Debug, Clone, Default, Serialize, Deserialize, Eq, PartialEq, ToSchema)]
+pub struct DTOPlayerStats {
+    pub discord_id: DiscordID,
+    pub games_played: usize,
+    pub games_won: usize,
+    pub total_score: i64,
+    pub best_score: i32,
+    pub questions_won: usize,
+    pub buzzes: usize,
+}
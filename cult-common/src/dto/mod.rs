@@ -4,4 +4,5 @@
 pub mod board;
 pub mod api;
 pub mod file;
+pub mod game;
 
@@ -11,6 +11,10 @@ pub struct FileDataHash {
 
 impl FileDataHash {
 
+    pub fn new(hash: String) -> Self {
+        FileDataHash { hash }
+    }
+
     pub fn get_hash(&self) -> String {
         self.hash.clone()
     }
@@ -0,0 +1,39 @@
+use std::fmt::{Display, Formatter};
+
+use rand::{distributions::Alphanumeric, Rng};
+use serde::{Deserialize, Serialize};
+use tsify_next::Tsify;
+use utoipa::ToSchema;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+
+#[derive(Tsify, Debug, Clone, Hash, Eq, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct BoardId {
+    pub id: String,
+}
+
+impl BoardId {
+
+    pub fn id(&self) -> String {
+        self.id.clone()
+    }
+
+    pub fn of(id: String) -> Self {
+        BoardId { id }
+    }
+
+    pub fn random() -> Self {
+        let id = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(16)
+            .map(char::from)
+            .collect();
+        BoardId { id }
+    }
+}
+
+impl Display for BoardId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.id)
+    }
+}
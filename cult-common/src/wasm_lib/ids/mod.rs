@@ -3,7 +3,11 @@
 pub mod usersession;
 pub mod discord;
 pub mod lobby;
+pub mod board;
+pub mod game;
+pub mod team;
 pub mod websocketsession;
+pub mod upload;
 
 
 
@@ -8,7 +8,8 @@ use std::{cmp::min, hash::Hash};
 use std::string::ToString;
 use wasm_bindgen::prelude::*;
 
-use crate::backend::{ActionState, MediaState};
+use crate::backend::{ActionState, ImageRevealState, MediaState};
+use crate::dto::board::BoardProblem;
 
 pub mod ids;
 pub mod websocket_events;
@@ -26,7 +27,7 @@ pub enum JeopardyMode {
     LONG,
 }
 
-#[derive(Tsify,Debug, Copy, Clone, Serialize, Deserialize, Eq, PartialEq, Default)]
+#[derive(Tsify,Debug, Copy, Clone, Serialize, Deserialize, Eq, PartialEq, Default, ToSchema)]
 pub struct Vector2D {
     pub x: usize,
     pub y: usize,
@@ -94,11 +95,53 @@ impl DiscordUser {
 pub enum QuestionType {
     Media(Vec<Media>),
     Youtube(String),
+    /// Every player types an answer, which is graded against the answer and the alternate answers.
+    Written,
+    MultipleChoice(MultipleChoice),
+    Estimation(Estimation),
     #[default]
     Question
 }
 
 
+/// Options of a multiple choice question, `correct` holds the indices of the correct options.
+/// With `speed_bonus` correct players earn between half and the full value depending on how fast they locked in.
+#[derive(Tsify,Debug, Clone, Serialize, Deserialize, Eq, PartialEq, Default,ToSchema)]
+pub struct MultipleChoice {
+    pub options: Vec<String>,
+    #[serde(default)]
+    pub correct: Vec<usize>,
+    #[serde(default)]
+    pub speed_bonus: bool,
+}
+
+/// A numeric answer, the `winners` closest guesses earn the value of the question.
+#[derive(Tsify,Debug, Clone, Serialize, Deserialize, Eq, PartialEq, Default,ToSchema)]
+pub struct Estimation {
+    #[serde(default)]
+    #[tsify(optional)]
+    pub answer: Option<i64>,
+    #[serde(default = "Estimation::default_winners")]
+    pub winners: usize,
+}
+
+impl Estimation {
+    fn default_winners() -> usize {
+        1
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.answer.is_some() && self.winners > 0
+    }
+}
+
+impl MultipleChoice {
+    pub fn is_valid(&self) -> bool {
+        self.options.len() >= 2 && !self.correct.is_empty() && self.correct.iter().all(|index| *index < self.options.len())
+    }
+}
+
+
 
 
 
@@ -108,9 +151,13 @@ impl QuestionType {
             QuestionType::Media(media) => {
                 let media = media.get(0).expect("Media is empty");
                 match media.media_type {
-                    MediaType::Video(_) => {
+                    MediaType::Video(_) | MediaType::Audio(_) => {
                         ActionState::MediaPlayer(MediaState::new(websocket_session_id))
                     }
+                    MediaType::Image(ref image_types) => match ImageRevealState::new(image_types) {
+                        Some(image_reveal) => ActionState::ImageReveal(image_reveal),
+                        None => ActionState::None,
+                    },
                     _ => ActionState::None,
                 }
             }
@@ -118,6 +165,35 @@ impl QuestionType {
         }
     }
 
+    /// The question type as sent to the clients, without the correct options.
+    pub fn without_solution(&self) -> QuestionType {
+        match self {
+            QuestionType::MultipleChoice(multiple_choice) => QuestionType::MultipleChoice(MultipleChoice {
+                correct: vec![],
+                ..multiple_choice.clone()
+            }),
+            QuestionType::Estimation(estimation) => QuestionType::Estimation(Estimation {
+                answer: None,
+                ..estimation.clone()
+            }),
+            question_type => question_type.clone(),
+        }
+    }
+
+    pub fn get_estimation(&self) -> Option<&Estimation> {
+        match self {
+            QuestionType::Estimation(estimation) => Some(estimation),
+            _ => None,
+        }
+    }
+
+    pub fn get_multiple_choice(&self) -> Option<&MultipleChoice> {
+        match self {
+            QuestionType::MultipleChoice(multiple_choice) => Some(multiple_choice),
+            _ => None,
+        }
+    }
+
     pub fn get_media(self: &QuestionType) -> Vec<Media> {
         match self {
             QuestionType::Media(media) => media.clone(),
@@ -196,21 +272,97 @@ impl MediaToken {
 
 
 
-#[derive(Tsify,Debug, Clone, Serialize, Deserialize, Eq, PartialEq, Default,ToSchema)]
+#[derive(Tsify,Debug, Clone, Serialize, Eq, PartialEq, Default,ToSchema)]
 #[tsify(namespace)]
 pub enum MediaType {
-    Image,
+    Image(Vec<ImageType>),
     Video(Vec<VideoType>),
-    Audio,
+    Audio(Vec<AudioType>),
     Text,
     Pdf,
     #[default]
     Unknown,
 }
 
+/// Boards created before image and audio options existed store them as plain `"Image"` and `"Audio"` variants.
+impl<'de> Deserialize<'de> for MediaType {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,{
+
+        #[derive(Deserialize)]
+        enum PartialMediaType {
+            Image(Vec<ImageType>),
+            Video(Vec<VideoType>),
+            Audio(Vec<AudioType>),
+            Text,
+            Pdf,
+            Unknown,
+        }
+
+        #[derive(Deserialize)]
+        enum LegacyMediaType {
+            Image,
+            Audio,
+        }
+
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum MediaTypeVisitor {
+            Current(PartialMediaType),
+            Legacy(LegacyMediaType),
+        }
+
+        let media_type = match MediaTypeVisitor::deserialize(deserializer)? {
+            MediaTypeVisitor::Current(PartialMediaType::Image(image_types)) => MediaType::Image(image_types),
+            MediaTypeVisitor::Current(PartialMediaType::Video(video_types)) => MediaType::Video(video_types),
+            MediaTypeVisitor::Current(PartialMediaType::Audio(audio_types)) => MediaType::Audio(audio_types),
+            MediaTypeVisitor::Current(PartialMediaType::Text) => MediaType::Text,
+            MediaTypeVisitor::Current(PartialMediaType::Pdf) => MediaType::Pdf,
+            MediaTypeVisitor::Current(PartialMediaType::Unknown) => MediaType::Unknown,
+            MediaTypeVisitor::Legacy(LegacyMediaType::Image) => MediaType::Image(vec![]),
+            MediaTypeVisitor::Legacy(LegacyMediaType::Audio) => MediaType::Audio(vec![]),
+        };
+        Ok(media_type)
+    }
+}
+
 
 impl MediaType {
 
+    pub fn validate(&self) -> Vec<BoardProblem> {
+        let mut scopes = Vec::new();
+        let mut problems = Vec::new();
+        match self {
+            MediaType::Video(video_types) => {
+                for video_type in video_types {
+                    if let VideoType::TimeSlots(time_slots) = video_type {
+                        scopes.extend(time_slots);
+                    }
+                }
+            }
+            MediaType::Audio(audio_types) => {
+                for audio_type in audio_types {
+                    match audio_type {
+                        AudioType::TimeSlots(time_slots) => scopes.extend(time_slots),
+                        AudioType::Speed(speed) if !AUDIO_SPEED_RANGE.contains(speed) => problems.push(BoardProblem::InvalidAudioSpeed(*speed)),
+                        _ => {}
+                    }
+                }
+            }
+            MediaType::Image(image_types) if image_types.iter().any(|image_type| *image_type != ImageType::None && matches!(image_type.steps(), None | Some(0))) => {
+                problems.push(BoardProblem::InvalidImageReveal);
+            }
+            _ => {}
+        }
+        for scope in scopes {
+            if scope.start >= scope.end {
+                problems.push(BoardProblem::InvalidNumberScope(scope.start, scope.end));
+            }
+        }
+        problems
+    }
+
     pub fn from_string(string_type: &str) -> MediaType {
 
         let type_vec: Vec<&str> = string_type.split("/").collect();
@@ -219,8 +371,8 @@ impl MediaType {
             _ => "unknown".to_owned(),
         };
         match media_type.as_str() {
-            "image" => MediaType::Image,
-            "audio" => MediaType::Audio,
+            "image" => MediaType::Image(vec![]),
+            "audio" => MediaType::Audio(vec![]),
             "text" => MediaType::Text,
             "pdf" => MediaType::Pdf,
             "video" => MediaType::Video(vec![]),
@@ -242,6 +394,64 @@ pub enum VideoType {
     
 }
 
+/// Reveal modes of an image, the numbers are the steps until the image is shown unmodified.
+/// `Tiles` takes the columns and rows, one tile is revealed per step.
+#[derive(Tsify,Debug, Clone, Serialize, Deserialize, Eq, PartialEq, Default,ToSchema)]
+#[tsify(namespace)]
+pub enum ImageType {
+    #[default]
+    None,
+    Pixelated(usize),
+    Zoomed(usize),
+    Tiles(usize, usize),
+}
+
+/// Most steps an image reveal may have, every tile is kept in the action state.
+pub const MAX_IMAGE_REVEAL_STEPS: usize = 256;
+
+impl ImageType {
+
+    /// `None` if the steps overflow or exceed `MAX_IMAGE_REVEAL_STEPS`.
+    pub fn steps(&self) -> Option<usize> {
+        let steps = match self {
+            ImageType::None => 0,
+            ImageType::Pixelated(steps) | ImageType::Zoomed(steps) => *steps,
+            ImageType::Tiles(columns, rows) => columns.checked_mul(*rows)?,
+        };
+        if steps > MAX_IMAGE_REVEAL_STEPS {
+            return None;
+        }
+        Some(steps)
+    }
+
+}
+
+/// Playback options of an audio clip, `Speed` is a percentage of the normal speed.
+#[derive(Tsify,Debug, Clone, Serialize, Deserialize, Eq, PartialEq, Default,ToSchema)]
+#[tsify(namespace)]
+pub enum AudioType {
+    #[default]
+    None,
+    TimeSlots(Vec<NumberScope>),
+    Speed(usize),
+    Reverse,
+}
+
+/// Playback speeds in percent that browsers can play back audibly.
+pub const AUDIO_SPEED_RANGE: std::ops::RangeInclusive<usize> = 25..=400;
+
+impl AudioType {
+
+    pub fn time_slots(vec: Vec<NumberScope>) -> AudioType {
+        if vec.is_empty() {
+            AudioType::None
+        } else {
+            AudioType::TimeSlots(vec)
+        }
+    }
+
+}
+
 impl VideoType {
     
     pub fn time_slots(vec: Vec<NumberScope>) -> VideoType {
@@ -6,10 +6,11 @@ use tsify_next::Tsify;
 use std::hash::{Hash};
 use std::string::ToString;
 
-use crate::backend::ActionState;
-use crate::dto::board::{DTOSession, DtoJeopardyBoard, DtoQuestion};
+use crate::backend::{ActionState, BuzzerSettings, DailyDoubleState, GameState, ImageRevealState, LobbyTimers};
+use crate::dto::board::{DTOSession, DtoFinalJeopardy, DtoCountdown, DtoJeopardyBoard, DtoJeopardyRound, DtoQuestion, DtoEstimation, DtoMultipleChoice, DtoTeam, DtoWrittenAnswers};
 
 use super::ids::lobby::LobbyId;
+use super::ids::team::TeamId;
 use super::ids::usersession::UserSessionId;
 use super::ids::websocketsession::{self, WebsocketSessionId};
 use super::Vector2D;
@@ -21,6 +22,7 @@ pub enum WebsocketServerEvents {
     Board(BoardEvent),
     Websocket(WebsocketEvent),
     Session(SessionEvent),
+    Game(GameEvent),
     Error(WebsocketError),
     ActionState(ActionStateEvent),
     Text(String),
@@ -33,6 +35,7 @@ impl WebsocketServerEvents {
             WebsocketServerEvents::Board(event) => event.to_string(),
             WebsocketServerEvents::Websocket(event) => event.to_string(),
             WebsocketServerEvents::Session(event) => event.to_string(),
+            WebsocketServerEvents::Game(event) => event.to_string(),
             WebsocketServerEvents::Error(event) => event.to_string(),
             WebsocketServerEvents::Text(event) => event.to_string(),
             WebsocketServerEvents::ActionState(event) => event.to_string().to_string(),
@@ -46,6 +49,7 @@ impl WebsocketServerEvents {
 #[tsify(namespace)] 
 pub enum ActionStateEvent {
     Media(ActionMediaEvent),
+    ImageReveal(ImageRevealState),
     SyncForward(f64),
     SyncBackward(i64),
 
@@ -101,6 +105,25 @@ pub enum BoardEvent {
     BuzzeringStarting,
     BuzzeringClosed(Vec<UserSessionId>),
     BuzzeringReset,
+    FinalJeopardy(DtoFinalJeopardy),
+    WrittenAnswers(DtoWrittenAnswers),
+    MultipleChoice(DtoMultipleChoice),
+    Estimation(DtoEstimation),
+    DailyDouble(DailyDoubleState),
+    CurrentRound(DtoJeopardyRound),
+    CurrentTeams(Vec<DtoTeam>),
+    Timers(LobbyTimers),
+    Countdown(Option<DtoCountdown>),
+    BuzzerSettings(BuzzerSettings),
+    /// The player is locked out of buzzing until the unix timestamp in milliseconds.
+    BuzzerLockout(UserSessionId, i64),
+}
+
+#[derive(Tsify,Debug, Clone, Serialize, Deserialize, Display)]
+#[tsify(namespace)] 
+pub enum GameEvent {
+    StateChanged(GameState),
+    FinalStandings(Vec<DTOSession>),
 }
 
 #[derive(Tsify,Debug, Clone, Serialize, Deserialize, Display)]
@@ -139,8 +162,21 @@ pub enum WebsocketSessionEvent {
     ChooseQuestion(Vector2D),
     Back,
     AddUserSessionScore(UserSessionId, Vector2D),
+    WrongUserSessionAnswer(UserSessionId, Vector2D),
+    AdjustUserSessionScore(UserSessionId, i32),
+    NextRound,
+    GameStateEvent(GameStateEvent),
+    AllowSpectators(bool),
+    TeamEvent(TeamEvent),
+    UpdateTimers(LobbyTimers),
+    UpdateBuzzerSettings(BuzzerSettings),
     MediaEvent(MediaEvent),
     BuzzoringEvent(BuzzorEvent),
+    FinalJeopardyEvent(FinalJeopardyEvent),
+    DailyDoubleEvent(DailyDoubleEvent),
+    WrittenAnswerEvent(WrittenAnswerEvent),
+    MultipleChoiceEvent(MultipleChoiceEvent),
+    EstimationEvent(EstimationEvent),
 }
 
 
@@ -150,6 +186,7 @@ pub enum WebsocketSessionEvent {
 #[tsify(namespace)]
 pub enum BuzzorEvent {
     BuzzorClick,
+    BuzzorClickAt(f64),
     BuzzorStarting,
     BuzzorStop,
     BuzzorReset,
@@ -162,10 +199,98 @@ pub enum BuzzorEvent {
 
 
 
+#[derive(Tsify,Debug, Clone, Serialize, Deserialize, Display)]
+#[tsify(namespace)]
+pub enum TeamEvent {
+    Create(String),
+    Remove(TeamId),
+    Join(TeamId),
+    Leave,
+    Assign(UserSessionId, Option<TeamId>),
+}
+
+
+
+
+#[derive(Tsify,Debug, Clone, Serialize, Deserialize, Display, Eq, PartialEq)]
+#[tsify(namespace)]
+pub enum GameStateEvent {
+    Start,
+    Pause,
+    Resume,
+    End,
+}
+
+
+
+
+#[derive(Tsify,Debug, Clone, Serialize, Deserialize, Display)]
+#[tsify(namespace)]
+pub enum DailyDoubleEvent {
+    SetPlayer(UserSessionId),
+    SubmitWager(i32),
+    Judge(bool),
+}
+
+
+
+
+/// Players submit answers, the host closes them and confirms the grades,
+/// grades missing in `Confirm` fall back to the suggested ones.
+#[derive(Tsify,Debug, Clone, Serialize, Deserialize, Display)]
+#[tsify(namespace)]
+pub enum WrittenAnswerEvent {
+    Submit(String),
+    Close,
+    Confirm(Vec<(UserSessionId, bool)>),
+}
+
+
+/// Players lock in the index of an option, the host reveals the distribution.
+#[derive(Tsify,Debug, Clone, Serialize, Deserialize, Display)]
+#[tsify(namespace)]
+pub enum MultipleChoiceEvent {
+    LockIn(usize),
+    Reveal,
+}
+
+
+#[derive(Tsify,Debug, Clone, Serialize, Deserialize, Display)]
+#[tsify(namespace)]
+pub enum ImageRevealEvent {
+    Next,
+    Previous,
+    RevealAll,
+}
+
+
+#[derive(Tsify,Debug, Clone, Serialize, Deserialize, Display)]
+#[tsify(namespace)]
+pub enum EstimationEvent {
+    SubmitGuess(i64),
+    Reveal,
+}
+
+
+#[derive(Tsify,Debug, Clone, Serialize, Deserialize, Display)]
+#[tsify(namespace)]
+pub enum FinalJeopardyEvent {
+    Start,
+    SubmitWager(i32),
+    OpenQuestion,
+    SubmitAnswer(String),
+    RevealNext,
+    Judge(UserSessionId, bool),
+}
+
+
+
+
 #[derive(Tsify,Debug, Clone, Serialize, Deserialize, Display)]
 #[tsify(namespace)] 
 pub enum MediaEvent {
     VideoEvent(VideoEvent),
+    ImageRevealEvent(ImageRevealEvent),
     SyncBackwardRequest,
     SyncForwardRequest(f64)
 }
@@ -194,6 +319,8 @@ pub enum WebsocketError {
     SessionNotFound(UserSessionId),
     GameStarted(LobbyId),
     NotAuthorized,
+    InvalidWager(i32),
+    InvalidGameState(GameState),
     WebsocketCrashed,
     UNKNOWN(String),
 }
@@ -1,6 +1,6 @@
 use bson::{oid::ObjectId, DateTime};
 use bytes::Bytes;
-use cult_common::{dto::file::DTOFileData, wasm_lib::{ids::usersession::UserSessionId, Media, MediaType, NumberScope}};
+use cult_common::{backend::JeopardyBoard, dto::{board::{DTOBoardData, DTOBoardRevision, DTOSession}, file::{DTOFileData, DTOUploadRequest, DTOUploadSession}, game::{DTOGameResult, DTOPlayerStats, DTOQuestionResult}}, wasm_lib::{hashs::validate::ValidateHash, ids::{board::BoardId, discord::DiscordID, game::GameId, lobby::LobbyId, upload::UploadId, usersession::UserSessionId}, Media, MediaType, NumberScope}};
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
@@ -76,6 +76,145 @@ impl FileData {
 
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BoardData {
+    pub board_id: BoardId,
+    pub owner: DiscordID,
+    pub revision: usize,
+    #[serde(rename = "createDate")]
+    pub create_date: DateTime,
+    #[serde(rename = "updateDate")]
+    pub update_date: DateTime,
+    pub board: JeopardyBoard,
+}
+
+impl BoardData {
+
+  pub fn new(owner: &DiscordID, board: JeopardyBoard) -> Self {
+    let now = DateTime::now();
+    BoardData {
+      board_id: BoardId::random(),
+      owner: owner.clone(),
+      revision: 0,
+      create_date: now,
+      update_date: now,
+      board,
+    }
+  }
+
+  pub fn to_revision(&self) -> BoardRevisionData {
+    BoardRevisionData {
+      board_id: self.board_id.clone(),
+      revision: self.revision,
+      update_date: self.update_date,
+      board: self.board.clone(),
+    }
+  }
+
+  pub fn to_dto(&self) -> DTOBoardData {
+    DTOBoardData {
+      board_id: self.board_id.clone(),
+      title: self.board.title.clone(),
+      owner: self.owner.clone(),
+      revision: self.revision,
+      create_date: self.create_date.to_string(),
+      update_date: self.update_date.to_string(),
+    }
+  }
+}
+
+/// Where a media file is used, a file without any reference can be deleted.
+#[derive(Debug, Clone, Default)]
+pub struct MediaReferences {
+    pub boards: Vec<BoardId>,
+    pub revisions: Vec<(BoardId, usize)>,
+    pub lobbies: Vec<LobbyId>,
+}
+
+/// Older version of a `BoardData`, written to `BoardRevisions` whenever the board is updated.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BoardRevisionData {
+    pub board_id: BoardId,
+    pub revision: usize,
+    #[serde(rename = "updateDate")]
+    pub update_date: DateTime,
+    pub board: JeopardyBoard,
+}
+
+impl BoardRevisionData {
+
+  pub fn to_dto(&self) -> DTOBoardRevision {
+    DTOBoardRevision {
+      board_id: self.board_id.clone(),
+      title: self.board.title.clone(),
+      revision: self.revision,
+      update_date: self.update_date.to_string(),
+    }
+  }
+}
+
+/// Results of a finished game, written to `Games` when the host ends the game.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GameResultData {
+    pub game_id: GameId,
+    pub lobby_id: LobbyId,
+    pub title: String,
+    pub players: Vec<DTOSession>,
+    pub questions: Vec<DTOQuestionResult>,
+    #[serde(rename = "startDate")]
+    pub start_date: DateTime,
+    #[serde(rename = "endDate")]
+    pub end_date: DateTime,
+}
+
+impl GameResultData {
+
+  pub fn to_dto(&self) -> DTOGameResult {
+    DTOGameResult {
+      game_id: self.game_id.clone(),
+      lobby_id: self.lobby_id.clone(),
+      title: self.title.clone(),
+      players: self.players.clone(),
+      questions: self.questions.clone(),
+      start_date: self.start_date.to_string(),
+      end_date: self.end_date.to_string(),
+    }
+  }
+
+  fn get_player(&self, discord_id: &DiscordID) -> Option<&DTOSession> {
+    self.players.iter().find(|player| player.discord_user.as_ref().is_some_and(|user| user.discord_id.eq(discord_id)))
+  }
+
+  /// Lifetime stats of a player over the given games, a game counts as won on the highest score.
+  pub fn player_stats(results: &[GameResultData], discord_id: &DiscordID) -> DTOPlayerStats {
+    let mut stats = DTOPlayerStats {
+      discord_id: discord_id.clone(),
+      ..DTOPlayerStats::default()
+    };
+    for result in results {
+      let player = match result.get_player(discord_id) {
+        None => continue,
+        Some(player) => player,
+      };
+      let best_score = result.players.iter().map(|player| player.score).max().unwrap_or(0);
+      if stats.games_played == 0 || player.score > stats.best_score {
+        stats.best_score = player.score;
+      }
+      stats.games_played += 1;
+      if player.score == best_score {
+        stats.games_won += 1;
+      }
+      stats.total_score += player.score as i64;
+      stats.questions_won += result.questions.iter().filter(|question| question.won_user_id.as_ref() == Some(&player.user_session_id)).count();
+      stats.buzzes += result.questions.iter()
+        .flat_map(|question| question.buzzes.iter())
+        .filter(|buzz| buzz.user_session_id.eq(&player.user_session_id))
+        .count();
+    }
+    stats
+  }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
 pub struct FileChunk {
     pub files_id:Option<ObjectId>,
@@ -84,6 +223,92 @@ pub struct FileChunk {
 }
 
 
+/// Unfinished chunked upload, written to `UploadSessions` until its chunks are assembled into the `FileBucket`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UploadSessionData {
+    pub upload_id: UploadId,
+    pub uploader: DiscordID,
+    pub file_name: String,
+    pub file_type: String,
+    pub file_size: usize,
+    pub chunk_size: usize,
+    pub validate_hash: ValidateHash,
+    pub received: Vec<usize>,
+    #[serde(rename = "createDate")]
+    pub create_date: DateTime,
+    #[serde(rename = "updateDate")]
+    pub update_date: DateTime,
+}
+
+impl UploadSessionData {
+
+  pub fn new(uploader: &DiscordID, request: DTOUploadRequest) -> Self {
+    let now = DateTime::now();
+    UploadSessionData {
+      upload_id: UploadId::random(),
+      uploader: uploader.clone(),
+      file_name: request.file_name,
+      file_type: request.file_type,
+      file_size: request.file_size,
+      chunk_size: request.chunk_size,
+      validate_hash: request.validate_hash,
+      received: Vec::new(),
+      create_date: now,
+      update_date: now,
+    }
+  }
+
+  pub fn chunk_count(&self) -> usize {
+    self.file_size.div_ceil(self.chunk_size)
+  }
+
+  /// Length the chunk at `index` must have, the last chunk holds the rest of the file.
+  pub fn chunk_length(&self, index: usize) -> Option<usize> {
+    if index >= self.chunk_count() {
+      return None;
+    }
+    Some(self.chunk_size.min(self.file_size - index * self.chunk_size))
+  }
+
+  pub fn next_chunk(&self) -> Option<usize> {
+    (0..self.chunk_count()).find(|index| !self.received.contains(index))
+  }
+
+  pub fn matches(&self, request: &DTOUploadRequest) -> bool {
+    self.file_type == request.file_type
+      && self.file_size == request.file_size
+      && self.chunk_size == request.chunk_size
+      && self.validate_hash == request.validate_hash
+  }
+
+  pub fn to_metadata(&self) -> FileMetadata {
+    FileMetadata::new(self.file_type.clone(), self.validate_hash.clone(), self.uploader.clone())
+  }
+
+  pub fn to_dto(&self) -> DTOUploadSession {
+    let mut received = self.received.clone();
+    received.sort();
+    DTOUploadSession {
+      upload_id: self.upload_id.clone(),
+      file_name: self.file_name.clone(),
+      file_size: self.file_size,
+      chunk_size: self.chunk_size,
+      chunk_count: self.chunk_count(),
+      received,
+      next_chunk: self.next_chunk(),
+    }
+  }
+}
+
+/// One received chunk of an `UploadSessionData`, written to `UploadChunks`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct UploadChunkData {
+    pub upload_id: UploadId,
+    pub index: usize,
+    pub data: Bytes,
+}
+
+
 #[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq, Default, ToSchema)]
 pub struct SessionRequest {
     pub user_session_id: UserSessionId,
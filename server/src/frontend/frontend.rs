@@ -7,7 +7,7 @@ use std::path::PathBuf;
 use std::sync::Arc;
 use cult_common::wasm_lib::ids::lobby::LobbyId;
 use crate::rest::api::session_error;
-use crate::rest::data::{get_session_with_token_update_or_create_new, remove_cookie, set_cookie, set_session_token_cookie};
+use crate::rest::data::{get_session_with_token_update_or_create_new, get_spectator_from_value, remove_cookie, set_cookie, set_session_token_cookie};
 use crate::authentication::discord::{is_admin, to_main_page};
 use crate::services::authentication::{AuthenticationServer, CheckAdminAccessToken};
 use crate::services::db::MongoServer;
@@ -61,7 +61,7 @@ async fn find_game(
         None => return Ok(session_error(&settings, &user_session, "No Lobby Found")),
     };
 
-    let can_join = lobby_addr.send(CanJoinLobby { user_session_id: user_session.user_session_id.clone()}).await.expect("No Lobby found!");
+    let can_join = lobby_addr.send(CanJoinLobby { user_session_id: user_session.user_session_id.clone(), spectator: get_spectator_from_value(&req) }).await.expect("No Lobby found!");
 
    
     println!("HasLobby?{}", can_join);
@@ -18,19 +18,23 @@ use actix_web::{get, web, App, HttpRequest, HttpResponse, HttpServer};
 use anyhow::Result;
 
 use rest::api::{game_info, session_data_request, DiscordSessionResponse, UserSessionWithAdmin};
-use rest::data::{extract_header_string, get_range_from_header, get_session, set_session_token_cookie};
-use rest::error::{ApiError, ApiGameError, ApiRequestError, ApiSessionError, ApiFileError };
+use rest::data::{extract_header_string, get_session, set_session_token_cookie};
+use rest::error::{ApiError, ApiGameError, ApiRequestError, ApiSessionError, ApiFileError, ApiBoardError };
 use attohttpc::Session;
 use authentication::discord::is_admin;
-use backend::{Category, JeopardyBoard, LobbyCreateResponse, Question};
+use backend::{Category, JeopardyBoard, JeopardyRound, LobbyCreateRequest, LobbyCreateResponse, Question};
 use bson::doc;
 use bytes::Bytes;
 use data::{BasicTokenResponse, SessionRequest};
 use dto::api::ApiResponse;
-use dto::file::FileMultiPart;
+use dto::board::{BoardProblem, DTOBoardArchiveFile, DTOBoardArchiveManifest, DTOBoardData, DTOBoardImport, DTOBoardProblem, DTOBoardRevision, DTOSession};
+use dto::file::{DTOFileOrganize, DTOFileRename, DTOUploadRequest, DTOUploadSession, FileMultiPart};
+use dto::game::{DTOBuzz, DTOGameResult, DTOPlayerStats, DTOQuestionResult};
 use futures::stream::once;
 use futures::AsyncReadExt;
-use rest::file::{get_file_from_name, get_file_list, get_file_size, upload_file_part};
+use rest::file::{cancel_upload, delete_file, finish_upload, get_file_from_name, get_file_list, get_file_size, get_unused_files, get_upload, organize_file, rename_file, start_upload, upload_chunk, upload_file_part};
+use rest::board::{validate_board, create_board, delete_board, get_board_list, get_board_revisions, get_stored_board, update_board, export_board, import_board};
+use rest::game::{get_game_list, get_game_result, get_player_stats};
 use services::db::MongoServer;
 use services::game::{DiscordData, SessionToken, UserSession};
 use settings::Settings;
@@ -40,10 +44,13 @@ use cult_common::*;
 use utoipa::{openapi, OpenApi};
 use utoipa_swagger_ui::SwaggerUi;
 use wasm_lib::hashs::validate::ValidateHash;
+use wasm_lib::ids::board::BoardId;
 use wasm_lib::ids::discord::DiscordID;
+use wasm_lib::ids::game::GameId;
 use wasm_lib::ids::lobby::LobbyId;
+use wasm_lib::ids::upload::UploadId;
 use wasm_lib::ids::usersession::UserSessionId;
-use wasm_lib::{DiscordUser, Media, MediaToken, MediaType, NumberScope, QuestionType, VideoType};
+use wasm_lib::{DiscordUser, Estimation, Media, MediaToken, MediaType, MultipleChoice, NumberScope, QuestionType, Vector2D, VideoType, AudioType, ImageType};
 use crate::authentication::discord;
 use crate::frontend::frontend::{assets, find_game, grant_admin_access, index};
 use crate::services::input::InputServer;
@@ -86,6 +93,27 @@ async fn main() -> Result<()> {
             rest::api::join_game,
             rest::file::get_file_size,
             rest::file::get_file_list,
+            rest::file::start_upload,
+            rest::file::get_upload,
+            rest::file::upload_chunk,
+            rest::file::finish_upload,
+            rest::file::cancel_upload,
+            rest::file::delete_file,
+            rest::file::rename_file,
+            rest::file::organize_file,
+            rest::file::get_unused_files,
+            rest::board::validate_board,
+            rest::board::create_board,
+            rest::board::update_board,
+            rest::board::get_board_list,
+            rest::board::get_stored_board,
+            rest::board::get_board_revisions,
+            rest::board::delete_board,
+            rest::board::export_board,
+            rest::board::import_board,
+            rest::game::get_game_list,
+            rest::game::get_game_result,
+            rest::game::get_player_stats,
         ),
         components(
             schemas(
@@ -102,6 +130,7 @@ async fn main() -> Result<()> {
                 ApiRequestError,
                 ApiGameError,
                 ApiFileError,
+                ApiBoardError,
                 FileMultiPart,
                 ValidateHash,
                 SessionRequest,
@@ -111,14 +140,39 @@ async fn main() -> Result<()> {
                 Category,
                 Question,
                 QuestionType,
+                MultipleChoice,
+                Estimation,
+                JeopardyRound,
+                LobbyCreateRequest,
                 LobbyCreateResponse,
+                DTOBoardProblem,
+                BoardProblem,
                 LobbyId,
                 Media,
                 MediaType,
                 VideoType,
+                AudioType,
+                ImageType,
                 NumberScope,
                 MediaToken,
-
+                BoardId,
+                DTOBoardData,
+                DTOBoardRevision,
+                DTOBoardImport,
+                DTOBoardArchiveManifest,
+                DTOBoardArchiveFile,
+                DTOUploadRequest,
+                DTOUploadSession,
+                DTOFileRename,
+                DTOFileOrganize,
+                UploadId,
+                GameId,
+                DTOSession,
+                Vector2D,
+                DTOGameResult,
+                DTOQuestionResult,
+                DTOBuzz,
+                DTOPlayerStats,
             ))
     )]
     struct ApiDoc;
@@ -126,7 +180,7 @@ async fn main() -> Result<()> {
 
     let api_doc = ApiDoc::openapi();
 
-    let input_server =  InputServer::init(services.authentication_server.clone());
+    let input_server =  InputServer::init(services.authentication_server.clone(), services.mongo_server.clone(), settings.clone());
     let rt = Runtime::new().expect("Somethings wrong with the Runtime");
 
     rt.spawn(input_server.read_input());
@@ -137,6 +191,8 @@ async fn main() -> Result<()> {
     HttpServer::new(move || {
         App::new()
             .app_data(web::Data::new(services.mongo_server.clone()))
+            .app_data(web::Data::new(services.media_storage.clone()))
+            .app_data(web::Data::new(services.media_processor.clone()))
             .app_data(web::Data::new(services.grant_client.clone()))
             .app_data(web::Data::new(services.login_client.clone()))
             .app_data(web::Data::new(services.game_server.clone()))
@@ -183,6 +239,27 @@ async fn main() -> Result<()> {
             .service(upload_file_part)
             .service(get_file_list)
             .service(get_file_size)
+            .service(start_upload)
+            .service(get_upload)
+            .service(upload_chunk)
+            .service(finish_upload)
+            .service(cancel_upload)
+            .service(delete_file)
+            .service(rename_file)
+            .service(organize_file)
+            .service(get_unused_files)
+            .service(validate_board)
+            .service(create_board)
+            .service(update_board)
+            .service(get_board_list)
+            .service(get_stored_board)
+            .service(get_board_revisions)
+            .service(delete_board)
+            .service(export_board)
+            .service(import_board)
+            .service(get_game_list)
+            .service(get_game_result)
+            .service(get_player_stats)
             .default_service(
                 web::route().to(not_found)
             )
@@ -203,110 +280,3 @@ async fn not_found(settings:web::Data<Arc<Settings>>) -> std::result::Result<Htt
         .finish();
     Ok(response)
 }
-
-
-
-
-
-
-
-
-
-
-/* Maybe using sometimes streamd with range
-#[get("/api/file/download2")]
-async fn get_file_from_name2(req: HttpRequest,  db: web::Data<Arc<MongoServer>>, settings:web::Data<Arc<Settings>>) -> Result<HttpResponse, actix_web::Error> {
-    let user_session = match get_session(&req, &db).await {
-        Some(data) => data,
-        None => return Ok(HttpResponse::Unauthorized().json("You are not authorized to access this file")),
-    };
-
-    let file_name = match extract_header_string(&req, "file-name") {
-        Ok(data) => data,
-        Err(e) => return Ok(e)
-    };
-
-    let byte_range = match get_range_from_header(&req) {
-        Ok(data) => data,
-        Err(e) => return Ok(e),
-    };
-
-
-        
-    let file_data: data::FileData = match db.collections.file_bucket_files.find_one(doc!{"filename":Some(file_name.clone())}).await {
-        Ok(data) => {
-            if let Some(data) = data {
-                  data
-            } else {
-                return Ok(HttpResponse::NotFound().json("File not found"));
-            }
-            
-        }
-        Err(err) => {
-            println!("Error while downloading file: {:#?}", err);
-            return Ok(HttpResponse::InternalServerError().json("Error while downloading file1"));
-        }
-    };
-
-    let file_chunk_range = file_data.get_chunk_range(byte_range.clone());
-
-    let id = match file_data.id {
-        Some(data) => data,
-        None => return Ok(HttpResponse::InternalServerError().json("Error while downloading file2")),
-    };
-
-
-
-    let file_meta = match file_data.metadata{
-        Some(data) => data,
-        None => return Ok(HttpResponse::InternalServerError().json("Error while downloading file2")),
-    };
-
-
-
-    let file_chunk = match db.get_file_chunks_in_range(id, file_chunk_range).await {
-        None => return Ok(HttpResponse::InternalServerError().json("Error while downloading file3")),
-        Some(data) => data,
-    };
-
-    let chunk_size = file_data.chunk_size;
-    let mut buf = Vec::new();
-    for chunk in file_chunk {
-        let chunk_start = chunk.n * chunk_size;
-        let chunk_end = chunk_start + chunk.data.len();
-        let chunk_range = Range::new(chunk_start, chunk_end);
-
-        if let Some(intersection) = byte_range.intersection(&chunk_range) {
-            let start_offset = (intersection.start - chunk_start) as usize;
-            let end_offset = (intersection.end - chunk_start) as usize;
-
-            if start_offset < chunk.data.len() && end_offset <= chunk.data.len() {
-                buf.extend_from_slice(&chunk.data[start_offset..end_offset]);
-            }
-        }
-    }
-
-    let start = byte_range.start;
-    let end = byte_range.end;
-
-    println!("Getting bytes: {:?}-{:?} from and the buf {:?}", start, end, buf.len());
-
-    let mut response =   HttpResponse::PartialContent()
-                                        .insert_header(("Content-Range", format!("bytes {}-{}/{}", start, end, file_data.length)))
-                                        .insert_header(("file-name", file_name))
-                                        .insert_header(("file-type", file_meta.file_type))
-                                        .insert_header(("file-size", file_data.length))
-                                        .insert_header(("Accept-Ranges", "bytes"))
-                                        .insert_header(("Content-Length", buf.len() as u64))
-                                        .insert_header(("file-upload-date", file_data.upload_date.to_string()))
-                                        .insert_header(("uploader-id", file_meta.uploader.id))
-                                        .insert_header(("validate-hash", file_meta.validate_hash.get_hash()))
-                                        .content_type("application/octet-stream")
-                                        .streaming(once(async move {
-                                            Ok::<_, actix_web::Error>(Bytes::from(buf))
-                                        }));
-
-    set_session_token_cookie(&mut response, &settings,&user_session);
-    Ok(response)
-}
- */
\ No newline at end of file
@@ -15,20 +15,18 @@ use cult_common::dto::api::{ApiResponse};
 use cult_common::dto::file::{FileMultiPart};
 use cult_common::wasm_lib::hashs::validate::ValidateHash;
 use cult_common::wasm_lib::{DiscordUser, JeopardyMode};
-use futures::{AsyncWriteExt, StreamExt};
-use mongodb::gridfs::GridFsUploadStream;
+use crate::services::storage::MediaWriter;
 use serde::Serialize;
 use serde_json::json;
-use cult_common::backend::JeopardyBoard;
+use cult_common::backend::{JeopardyBoard, LobbyCreateRequest, LobbyCreateResponse};
 use cult_common::wasm_lib::ids::lobby::LobbyId;
 use utoipa::ToSchema;
-use crate::rest::data::{extract_header_string, extract_value, get_internal_server_error_json, get_lobby_id_from_header, get_session, get_session_with_token_update_or_create_new, set_session_token_cookie};
+use crate::rest::data::{extract_header_string, extract_value, get_board_id_from_header, get_internal_server_error_json, get_lobby_id_from_header, get_session, get_session_with_token_update_or_create_new, get_spectator_from_value, set_session_token_cookie};
 use crate::authentication::discord::is_admin;
 use crate::services::game;
 use crate::services::game::UserSession;
 
 use super::data;
-use super::error::ApiFileError;
 
 #[utoipa::path(
     get,
@@ -111,12 +109,9 @@ async fn api_session_request(req: HttpRequest ,db:web::Data<Arc<MongoServer>>, s
 
 
 
-pub async fn file_part_error(stream: Option<GridFsUploadStream>,api_error:ApiError) -> HttpResponse {
-    if let Some(mut stream) = stream {
-        match stream.close().await {
-            Ok(data) => data,
-            Err(error) => return ApiError::File(ApiFileError::FileError(error.to_string())).to_response(),
-        };
+pub async fn file_part_error(writer: Option<Box<dyn MediaWriter>>,api_error:ApiError) -> HttpResponse {
+    if let Some(writer) = writer {
+        writer.abort().await;
     }
     return api_error.to_response();
 }
@@ -249,8 +244,9 @@ async fn discord_session(req: HttpRequest, db: web::Data<Arc<MongoServer>>, sett
     params(
         ("user_session_id" = Option<String>, Query, description = "User session ID"),
         ("user_session_token" = Option<String>, Query, description = "User session token"),
+        ("board-id" = Option<String>, Header, description = "Stored board to use instead of the request body"),
     ),
-    request_body(content = JeopardyBoard, description = "Upload JeopardyBoard and create a lobby", content_type = "application/json"),
+    request_body(content = LobbyCreateRequest, description = "Upload a JeopardyBoard or a list of rounds and create a lobby", content_type = "application/json"),
     responses(
         // 200
         (status = 200, description = "JeopardyBoard uploaded successfully", body = LobbyCreateResponse),
@@ -262,6 +258,7 @@ async fn discord_session(req: HttpRequest, db: web::Data<Arc<MongoServer>>, sett
         (status = 403, description = "No Discord Data", body = ApiError),
 
         //GAME
+        (status = 400, description = "Board invalid", body = ApiError),
         (status = 500, description = "Game error", body = ApiError),
     ),
     security(
@@ -269,7 +266,7 @@ async fn discord_session(req: HttpRequest, db: web::Data<Arc<MongoServer>>, sett
     )
 )]
 #[post("/api/create")]
-async fn create_game_lobby(req: HttpRequest,json: web::Json<Option<JeopardyBoard>>, srv: web::Data<Addr<game::GameServer>>, db: web::Data<Arc<MongoServer>>, settings: web::Data<Arc<Settings>>) -> HttpResponse {
+async fn create_game_lobby(req: HttpRequest,json: web::Json<Option<LobbyCreateRequest>>, srv: web::Data<Addr<game::GameServer>>, db: web::Data<Arc<MongoServer>>, settings: web::Data<Arc<Settings>>) -> HttpResponse {
     let user_session = match get_session(&req, &db).await {
         Some(data) => data,
         None => return ApiSessionError::NotFound.to_api_error().to_response(),
@@ -283,7 +280,9 @@ async fn create_game_lobby(req: HttpRequest,json: web::Json<Option<JeopardyBoard
         None => return ApiSessionError::NoDiscordData.to_api_error().to_response(),
     };
 
-    let data = match  srv.send(CreateLobby { user_session_id: user_session.user_session_id.clone(), discord_id, jeopardy_board: json.into_inner() }).await {
+    let board_id = get_board_id_from_header(&req);
+    let data = match  srv.send(CreateLobby { user_session_id: user_session.user_session_id.clone(), discord_id, request: json.into_inner(), board_id }).await {
+        Ok(LobbyCreateResponse::Invalid(problems)) => return ApiGameError::BoardInvalid(problems).to_response(),
         Ok(data) => data,
         Err(_) => return ApiGameError::GameError("No JeopardyBoard found".to_string()).to_response(),
     };
@@ -303,6 +302,7 @@ async fn create_game_lobby(req: HttpRequest,json: web::Json<Option<JeopardyBoard
         ("user_session_id" = Option<String>, Query, description = "User session ID"),
         ("user_session_token" = Option<String>, Query, description = "User session token"),
         ("lobby-id" = String, Header, description = "Lobby id"),
+        ("spectator" = Option<bool>, Query, description = "Join as a spectator"),
     ),
     responses(
         // 200
@@ -339,7 +339,7 @@ async fn join_game(req: HttpRequest, srv: web::Data<Addr<game::GameServer>>, db:
         Some(data) => data,
         None => return Ok(ApiGameError::LobbyNotFound(lobby_id.clone().id).to_response()),
     };
-    let can_join = match lobby_adrr.send(CanJoinLobby { user_session_id: user_session.user_session_id.clone()}).await {
+    let can_join = match lobby_adrr.send(CanJoinLobby { user_session_id: user_session.user_session_id.clone(), spectator: get_spectator_from_value(&req) }).await {
         Ok(data) => data,
         Err(_) => return Ok(ApiGameError::GameError("No Lobby found!".to_string()).to_response()),
     };
@@ -0,0 +1,551 @@
+use std::sync::Arc;
+
+use actix::Addr;
+use actix_web::{delete, get, post, put, web, HttpRequest, HttpResponse};
+use cult_common::backend::{JeopardyBoard, LobbyCreateRequest};
+use cult_common::dto::api::ApiResponse;
+use cult_common::dto::board::{DTOBoardData, DTOBoardImport, DTOBoardRevision};
+use cult_common::wasm_lib::ids::discord::DiscordID;
+use serde_json::json;
+
+use crate::authentication::discord::is_admin;
+use crate::data::BoardData;
+use crate::rest::data::{extract_header_string, get_board_id_from_header, get_revision_from_header, get_session, set_session_token_cookie};
+use crate::rest::error::{ApiBoardError, ApiFileError, ApiGameError, ApiSessionError, ToApiError, ToResponse};
+use crate::services::archive::{BoardArchive, BoardArchiveError};
+use crate::services::db::MongoServer;
+use crate::services::game::{validate_rounds, FileMetadata, UserSession};
+use crate::services::media::{MediaProcessor, ProcessMedia};
+use crate::services::storage::MediaStorage;
+use crate::settings::Settings;
+
+
+async fn get_board_owner(req: &HttpRequest, db: &web::Data<Arc<MongoServer>>) -> Result<(UserSession, DiscordID), HttpResponse> {
+    let user_session = match get_session(req, db).await {
+        Some(data) => data,
+        None => return Err(ApiSessionError::NotFound.to_api_error().to_response()),
+    };
+    if !is_admin(&user_session, db).await {
+        return Err(ApiSessionError::NotAdmin.to_api_error().to_response());
+    }
+    let discord_id = match user_session.get_discord_id() {
+        Some(data) => data,
+        None => return Err(ApiSessionError::NoDiscordData.to_api_error().to_response()),
+    };
+    Ok((user_session, discord_id))
+}
+
+async fn get_owned_board(req: &HttpRequest, db: &web::Data<Arc<MongoServer>>, owner: &DiscordID) -> Result<BoardData, HttpResponse> {
+    let board_id = match get_board_id_from_header(req) {
+        Some(data) => data,
+        None => return Err(ApiBoardError::BoardInvalid("No board id found".to_string()).to_api_error().to_response()),
+    };
+    let board_data = match db.find_board(&board_id).await {
+        Some(data) => data,
+        None => return Err(ApiBoardError::BoardNotFound(board_id.id).to_api_error().to_response()),
+    };
+    if !board_data.owner.eq(owner) {
+        return Err(ApiBoardError::NotOwner.to_api_error().to_response());
+    }
+    Ok(board_data)
+}
+
+
+
+
+#[utoipa::path(
+    post,
+    path = "/api/board/validate",
+    params(
+        ("user_session_id" = Option<String>, Query, description = "User session ID"),
+        ("user_session_token" = Option<String>, Query, description = "User session token"),
+    ),
+    request_body(content = LobbyCreateRequest, description = "JeopardyBoard or list of rounds to validate", content_type = "application/json"),
+    responses(
+        // 200
+        (status = 200, description = "Board is valid", body = ApiResponse),
+
+        // Session
+        (status = 404, description = "No User Session", body = ApiError),
+        (status = 401, description = "Not a admin Session", body = ApiError),
+        (status = 403, description = "No Discord Data", body = ApiError),
+
+        //GAME
+        (status = 400, description = "Board invalid, with the list of problems", body = ApiError),
+    ),
+    security(
+        ("cookie" = ["user_session_id", "user_session_token"])
+    )
+)]
+#[post("/api/board/validate")]
+async fn validate_board(req: HttpRequest, json: web::Json<LobbyCreateRequest>, db: web::Data<Arc<MongoServer>>, storage: web::Data<Arc<dyn MediaStorage>>, settings: web::Data<Arc<Settings>>) -> HttpResponse {
+    let (user_session, _) = match get_board_owner(&req, &db).await {
+        Ok(data) => data,
+        Err(response) => return response,
+    };
+
+    let problems = validate_rounds(&json.into_inner().rounds(), storage.get_ref().as_ref()).await;
+    if !problems.is_empty() {
+        return ApiGameError::BoardInvalid(problems).to_api_error().to_response();
+    }
+
+    let mut response = HttpResponse::Ok().json(ApiResponse::of(true));
+    set_session_token_cookie(&mut response, &settings, &user_session);
+    response
+}
+
+
+
+#[utoipa::path(
+    post,
+    path = "/api/boards",
+    params(
+        ("user_session_id" = Option<String>, Query, description = "User session ID"),
+        ("user_session_token" = Option<String>, Query, description = "User session token"),
+    ),
+    request_body(content = JeopardyBoard, description = "Store a new JeopardyBoard in the board library", content_type = "application/json"),
+    responses(
+        // 200
+        (status = 200, description = "JeopardyBoard stored successfully", body = DTOBoardData),
+
+        // Session
+        (status = 404, description = "No User Session", body = ApiError),
+        (status = 401, description = "Not a admin Session", body = ApiError),
+        (status = 403, description = "No Discord Data", body = ApiError),
+
+        //BOARD
+        (status = 500, description = "Board error", body = ApiError),
+    ),
+    security(
+        ("cookie" = ["user_session_id", "user_session_token"])
+    )
+)]
+#[post("/api/boards")]
+async fn create_board(req: HttpRequest, json: web::Json<JeopardyBoard>, db: web::Data<Arc<MongoServer>>, settings: web::Data<Arc<Settings>>) -> HttpResponse {
+    let (user_session, owner) = match get_board_owner(&req, &db).await {
+        Ok(data) => data,
+        Err(response) => return response,
+    };
+
+    let mut board_data = BoardData::new(&owner, json.into_inner());
+    while db.find_board(&board_data.board_id).await.is_some() {
+        board_data = BoardData::new(&owner, board_data.board);
+    }
+    if !db.insert_board(&board_data).await {
+        return ApiBoardError::BoardError("Can´t store board".to_string()).to_api_error().to_response();
+    }
+
+    let mut response = HttpResponse::Ok().json(board_data.to_dto());
+    set_session_token_cookie(&mut response, &settings, &user_session);
+    response
+}
+
+
+
+#[utoipa::path(
+    put,
+    path = "/api/boards",
+    params(
+        ("user_session_id" = Option<String>, Query, description = "User session ID"),
+        ("user_session_token" = Option<String>, Query, description = "User session token"),
+        ("board-id" = String, Header, description = "Board ID"),
+        ("revision" = Option<usize>, Header, description = "Revision the update is based on"),
+    ),
+    request_body(content = JeopardyBoard, description = "Store a new revision of the JeopardyBoard", content_type = "application/json"),
+    responses(
+        // 200
+        (status = 200, description = "JeopardyBoard updated successfully", body = DTOBoardData),
+
+        // Session
+        (status = 404, description = "No User Session", body = ApiError),
+        (status = 401, description = "Not a admin Session", body = ApiError),
+        (status = 403, description = "No Discord Data", body = ApiError),
+
+        //BOARD
+        (status = 400, description = "Board invalid", body = ApiError),
+        (status = 403, description = "Not the board owner", body = ApiError),
+        (status = 404, description = "Board not found", body = ApiError),
+        (status = 409, description = "Board was updated in the meantime", body = ApiError),
+    ),
+    security(
+        ("cookie" = ["user_session_id", "user_session_token"])
+    )
+)]
+#[put("/api/boards")]
+async fn update_board(req: HttpRequest, json: web::Json<JeopardyBoard>, db: web::Data<Arc<MongoServer>>, settings: web::Data<Arc<Settings>>) -> HttpResponse {
+    let (user_session, owner) = match get_board_owner(&req, &db).await {
+        Ok(data) => data,
+        Err(response) => return response,
+    };
+    let board_data = match get_owned_board(&req, &db, &owner).await {
+        Ok(data) => data,
+        Err(response) => return response,
+    };
+    if let Some(revision) = get_revision_from_header(&req) {
+        if revision != board_data.revision {
+            return ApiBoardError::RevisionConflict(board_data.board_id.id).to_api_error().to_response();
+        }
+    }
+
+    let updated = match db.update_board(&board_data, json.into_inner()).await {
+        Some(data) => data,
+        None => return ApiBoardError::RevisionConflict(board_data.board_id.id).to_api_error().to_response(),
+    };
+
+    let mut response = HttpResponse::Ok().json(updated.to_dto());
+    set_session_token_cookie(&mut response, &settings, &user_session);
+    response
+}
+
+
+
+#[utoipa::path(
+    get,
+    path = "/api/boards",
+    params(
+        ("user_session_id" = Option<String>, Query, description = "User session ID"),
+        ("user_session_token" = Option<String>, Query, description = "User session token"),
+        ("page_size" = Option<u64>, Header, description = "Boards per page"),
+        ("page" = Option<u64>, Header, description = "page"),
+    ),
+    responses(
+        // 200
+        (status = 200, description = "Boards of the current user, newest first"),
+
+        // Session
+        (status = 404, description = "No User Session", body = ApiError),
+        (status = 401, description = "Not a admin Session", body = ApiError),
+        (status = 403, description = "No Discord Data", body = ApiError),
+    ),
+    security(
+        ("cookie" = ["user_session_id", "user_session_token"])
+    )
+)]
+#[get("/api/boards")]
+async fn get_board_list(req: HttpRequest, db: web::Data<Arc<MongoServer>>, settings: web::Data<Arc<Settings>>) -> HttpResponse {
+    let (user_session, owner) = match get_board_owner(&req, &db).await {
+        Ok(data) => data,
+        Err(response) => return response,
+    };
+
+    let page = match extract_header_string(&req, "page") {
+        Ok(data) => data.parse::<u64>().unwrap_or(0),
+        Err(_) => 0,
+    };
+    let page_size = match extract_header_string(&req, "page_size") {
+        Ok(data) => data.parse::<u64>().unwrap_or(10),
+        Err(_) => 10,
+    };
+    if page_size == 0 {
+        return ApiBoardError::BoardInvalid("Page size cannot be zero".to_string()).to_api_error().to_response();
+    }
+
+    let board_count = db.count_boards(&owner).await;
+    let boards: Vec<DTOBoardData> = db.get_boards(&owner, page * page_size, page_size as i64).await
+        .iter()
+        .map(|board_data| board_data.to_dto())
+        .collect();
+
+    let mut response = HttpResponse::Ok().json(json!({
+        "boards": boards,
+        "board_count": board_count,
+        "page": page,
+        "page_size": page_size,
+    }));
+    set_session_token_cookie(&mut response, &settings, &user_session);
+    response
+}
+
+
+
+#[utoipa::path(
+    get,
+    path = "/api/boards/board",
+    params(
+        ("user_session_id" = Option<String>, Query, description = "User session ID"),
+        ("user_session_token" = Option<String>, Query, description = "User session token"),
+        ("board-id" = String, Header, description = "Board ID"),
+        ("revision" = Option<usize>, Header, description = "Older revision to load, the latest one if missing"),
+    ),
+    responses(
+        // 200
+        (status = 200, description = "Stored JeopardyBoard", body = JeopardyBoard),
+
+        // Session
+        (status = 404, description = "No User Session", body = ApiError),
+        (status = 401, description = "Not a admin Session", body = ApiError),
+        (status = 403, description = "No Discord Data", body = ApiError),
+
+        //BOARD
+        (status = 403, description = "Not the board owner", body = ApiError),
+        (status = 404, description = "Board or revision not found", body = ApiError),
+    ),
+    security(
+        ("cookie" = ["user_session_id", "user_session_token"])
+    )
+)]
+#[get("/api/boards/board")]
+async fn get_stored_board(req: HttpRequest, db: web::Data<Arc<MongoServer>>, settings: web::Data<Arc<Settings>>) -> HttpResponse {
+    let (user_session, owner) = match get_board_owner(&req, &db).await {
+        Ok(data) => data,
+        Err(response) => return response,
+    };
+    let board_data = match get_owned_board(&req, &db, &owner).await {
+        Ok(data) => data,
+        Err(response) => return response,
+    };
+
+    let board = match get_revision_from_header(&req) {
+        Some(revision) if revision != board_data.revision => {
+            match db.find_board_revision(&board_data.board_id, revision).await {
+                Some(data) => data.board,
+                None => return ApiBoardError::RevisionNotFound(board_data.board_id.id, revision).to_api_error().to_response(),
+            }
+        }
+        _ => board_data.board,
+    };
+
+    let mut response = HttpResponse::Ok().json(board);
+    set_session_token_cookie(&mut response, &settings, &user_session);
+    response
+}
+
+
+
+#[utoipa::path(
+    get,
+    path = "/api/boards/revisions",
+    params(
+        ("user_session_id" = Option<String>, Query, description = "User session ID"),
+        ("user_session_token" = Option<String>, Query, description = "User session token"),
+        ("board-id" = String, Header, description = "Board ID"),
+    ),
+    responses(
+        // 200
+        (status = 200, description = "Older revisions of the board, newest first", body = Vec<DTOBoardRevision>),
+
+        // Session
+        (status = 404, description = "No User Session", body = ApiError),
+        (status = 401, description = "Not a admin Session", body = ApiError),
+        (status = 403, description = "No Discord Data", body = ApiError),
+
+        //BOARD
+        (status = 403, description = "Not the board owner", body = ApiError),
+        (status = 404, description = "Board not found", body = ApiError),
+    ),
+    security(
+        ("cookie" = ["user_session_id", "user_session_token"])
+    )
+)]
+#[get("/api/boards/revisions")]
+async fn get_board_revisions(req: HttpRequest, db: web::Data<Arc<MongoServer>>, settings: web::Data<Arc<Settings>>) -> HttpResponse {
+    let (user_session, owner) = match get_board_owner(&req, &db).await {
+        Ok(data) => data,
+        Err(response) => return response,
+    };
+    let board_data = match get_owned_board(&req, &db, &owner).await {
+        Ok(data) => data,
+        Err(response) => return response,
+    };
+
+    let revisions: Vec<DTOBoardRevision> = db.get_board_revisions(&board_data.board_id).await
+        .iter()
+        .map(|revision| revision.to_dto())
+        .collect();
+
+    let mut response = HttpResponse::Ok().json(revisions);
+    set_session_token_cookie(&mut response, &settings, &user_session);
+    response
+}
+
+
+
+#[utoipa::path(
+    delete,
+    path = "/api/boards",
+    params(
+        ("user_session_id" = Option<String>, Query, description = "User session ID"),
+        ("user_session_token" = Option<String>, Query, description = "User session token"),
+        ("board-id" = String, Header, description = "Board ID"),
+    ),
+    responses(
+        // 200
+        (status = 200, description = "Board and its revisions deleted", body = ApiResponse),
+
+        // Session
+        (status = 404, description = "No User Session", body = ApiError),
+        (status = 401, description = "Not a admin Session", body = ApiError),
+        (status = 403, description = "No Discord Data", body = ApiError),
+
+        //BOARD
+        (status = 403, description = "Not the board owner", body = ApiError),
+        (status = 404, description = "Board not found", body = ApiError),
+    ),
+    security(
+        ("cookie" = ["user_session_id", "user_session_token"])
+    )
+)]
+#[delete("/api/boards")]
+async fn delete_board(req: HttpRequest, db: web::Data<Arc<MongoServer>>, settings: web::Data<Arc<Settings>>) -> HttpResponse {
+    let (user_session, owner) = match get_board_owner(&req, &db).await {
+        Ok(data) => data,
+        Err(response) => return response,
+    };
+    let board_data = match get_owned_board(&req, &db, &owner).await {
+        Ok(data) => data,
+        Err(response) => return response,
+    };
+
+    let deleted = db.delete_board(&board_data.board_id).await;
+    let mut response = HttpResponse::Ok().json(ApiResponse::new(deleted));
+    set_session_token_cookie(&mut response, &settings, &user_session);
+    response
+}
+
+
+
+#[utoipa::path(
+    get,
+    path = "/api/boards/export",
+    params(
+        ("user_session_id" = Option<String>, Query, description = "User session ID"),
+        ("user_session_token" = Option<String>, Query, description = "User session token"),
+        ("board-id" = String, Header, description = "Board ID"),
+    ),
+    responses(
+        // 200
+        (status = 200, description = "Zip archive with board.json, manifest.json and the media of the board", content_type = "application/zip"),
+
+        // Session
+        (status = 404, description = "No User Session", body = ApiError),
+        (status = 401, description = "Not a admin Session", body = ApiError),
+        (status = 403, description = "No Discord Data", body = ApiError),
+
+        //BOARD
+        (status = 403, description = "Not the board owner", body = ApiError),
+        (status = 404, description = "Board not found", body = ApiError),
+
+        //FILE
+        (status = 400, description = "Media of the board is too large to be imported again", body = ApiError),
+        (status = 404, description = "Media of the board not found", body = ApiError),
+        (status = 500, description = "Archive error", body = ApiError),
+    ),
+    security(
+        ("cookie" = ["user_session_id", "user_session_token"])
+    )
+)]
+#[get("/api/boards/export")]
+async fn export_board(req: HttpRequest, db: web::Data<Arc<MongoServer>>, storage: web::Data<Arc<dyn MediaStorage>>, settings: web::Data<Arc<Settings>>) -> HttpResponse {
+    let (user_session, owner) = match get_board_owner(&req, &db).await {
+        Ok(data) => data,
+        Err(response) => return response,
+    };
+    let board_data = match get_owned_board(&req, &db, &owner).await {
+        Ok(data) => data,
+        Err(response) => return response,
+    };
+
+    let archive = match BoardArchive::collect(board_data.board, storage.get_ref().as_ref()).await {
+        Ok(data) => data,
+        Err(BoardArchiveError::FileNotFound(name)) => return ApiFileError::FileNotFound(name).to_api_error().to_response(),
+        Err(err @ BoardArchiveError::TooLarge(_)) => return ApiFileError::FileInvalid(err.to_string()).to_api_error().to_response(),
+        Err(err) => return ApiFileError::FileError(err.to_string()).to_api_error().to_response(),
+    };
+    let data = match archive.to_bytes() {
+        Ok(data) => data,
+        Err(err) => return ApiFileError::FileError(err.to_string()).to_api_error().to_response(),
+    };
+
+    let mut response = HttpResponse::Ok()
+        .content_type("application/zip")
+        .insert_header(("Content-Disposition", format!("attachment; filename=\"{}.zip\"", board_data.board_id.id)))
+        .body(data);
+    set_session_token_cookie(&mut response, &settings, &user_session);
+    response
+}
+
+
+
+#[utoipa::path(
+    post,
+    path = "/api/boards/import",
+    params(
+        ("user_session_id" = Option<String>, Query, description = "User session ID"),
+        ("user_session_token" = Option<String>, Query, description = "User session token"),
+    ),
+    request_body(content = Vec<u8>, description = "Board archive created by /api/boards/export", content_type = "application/zip"),
+    responses(
+        // 200
+        (status = 200, description = "Board stored, with the imported and the already present media files", body = DTOBoardImport),
+
+        // Session
+        (status = 404, description = "No User Session", body = ApiError),
+        (status = 401, description = "Not a admin Session", body = ApiError),
+        (status = 403, description = "No Discord Data", body = ApiError),
+
+        //FILE
+        (status = 400, description = "Archive invalid", body = ApiError),
+        (status = 409, description = "A different file with the same name exists", body = ApiError),
+        (status = 500, description = "File error", body = ApiError),
+
+        //BOARD
+        (status = 500, description = "Board error", body = ApiError),
+    ),
+    security(
+        ("cookie" = ["user_session_id", "user_session_token"])
+    )
+)]
+#[post("/api/boards/import")]
+async fn import_board(req: HttpRequest, body: web::Bytes, db: web::Data<Arc<MongoServer>>, storage: web::Data<Arc<dyn MediaStorage>>, processor: web::Data<Addr<MediaProcessor>>, settings: web::Data<Arc<Settings>>) -> HttpResponse {
+    let (user_session, owner) = match get_board_owner(&req, &db).await {
+        Ok(data) => data,
+        Err(response) => return response,
+    };
+
+    let mut archive = match BoardArchive::from_bytes(&body) {
+        Ok(data) => data,
+        Err(err) => return ApiFileError::FileInvalid(err.to_string()).to_api_error().to_response(),
+    };
+
+    // decide for every file before anything is written, so a conflict leaves the storage untouched
+    let mut missing = Vec::new();
+    let mut skipped = Vec::new();
+    for file in &archive.manifest.files {
+        if let Some(existing) = storage.find_file_by_hash(&file.validate_hash).await {
+            if existing.file_name != file.name {
+                archive.board.rename_media(&file.name, &existing.file_name);
+            }
+            skipped.push(existing.file_name);
+        } else if storage.file_exists(&file.name).await {
+            return ApiFileError::FileExists.to_api_error().to_response();
+        } else {
+            missing.push(file.clone());
+        }
+    }
+
+    let mut imported = Vec::new();
+    for file in missing {
+        let data = match archive.files.get(&file.path) {
+            Some(data) => data,
+            None => return ApiFileError::FileInvalid(format!("Archive entry {} is missing", file.path)).to_api_error().to_response(),
+        };
+        let metadata = FileMetadata::new(file.file_type, file.validate_hash, owner.clone());
+        if !storage.store_file(&file.name, data, metadata).await {
+            return ApiFileError::FileError(format!("Can´t store file {}", file.name)).to_api_error().to_response();
+        }
+        processor.do_send(ProcessMedia { file_name: file.name.clone() });
+        imported.push(file.name);
+    }
+
+    let mut board_data = BoardData::new(&owner, archive.board);
+    while db.find_board(&board_data.board_id).await.is_some() {
+        board_data = BoardData::new(&owner, board_data.board);
+    }
+    if !db.insert_board(&board_data).await {
+        return ApiBoardError::BoardError("Can´t store board".to_string()).to_api_error().to_response();
+    }
+
+    let mut response = HttpResponse::Ok().json(DTOBoardImport { board: board_data.to_dto(), imported, skipped });
+    set_session_token_cookie(&mut response, &settings, &user_session);
+    response
+}
@@ -9,13 +9,17 @@ use actix_web::cookie::Cookie;
 use actix_web::{HttpRequest, HttpResponse, web};
 use chrono::Local;
 use cult_common::wasm_lib::hashs::validate::ValidateHash;
-use cult_common::wasm_lib::{MediaToken, NumberScope};
+use cult_common::wasm_lib::MediaToken;
 use oauth2::http::header::COOKIE;
 use oauth2::http::HeaderValue;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use cult_common::wasm_lib::ids::board::BoardId;
+use cult_common::wasm_lib::ids::discord::DiscordID;
+use cult_common::wasm_lib::ids::game::GameId;
 use cult_common::wasm_lib::ids::lobby::LobbyId;
 use cult_common::wasm_lib::ids::usersession::UserSessionId;
+use cult_common::wasm_lib::ids::upload::UploadId;
 use crate::services::game::UserSession;
 
 use super::error::{ApiRequestError, ErrorType, ToApiError, ToResponse};
@@ -68,31 +72,46 @@ pub fn extract_header_string(req: &HttpRequest, header_name: &str) -> Result<Str
 
 
 
-pub fn get_range_from_header(req: &HttpRequest) -> Result<NumberScope, HttpResponse> {
-    match extract_header_string(req, "range") {
-        Ok(range) => {
-            let mut parts = range.split('=');
-            if let Some(_) = parts.next() {
-                if let Some(range) = parts.next() {
-                    let mut range_parts = range.split('-');
-                    if let Some(start) = range_parts.next() {
-                        if let Some(end) = range_parts.next() {
-                            if let Ok(start) = start.parse::<usize>() {
-                                if let Ok(end) = end.parse::<usize>() {
-                                    return Ok(NumberScope {
-                                        start,
-                                        end,
-                                    });
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
-        Err(err) => return Err(err),
+pub fn get_board_id_from_header(req: &HttpRequest) -> Option<BoardId> {
+    match extract_header_string(req, "board-id") {
+        Ok(board_id) => Some(BoardId::of(board_id)),
+        Err(_) => None,
+    }
+}
+
+pub fn get_game_id_from_header(req: &HttpRequest) -> Option<GameId> {
+    match extract_header_string(req, "game-id") {
+        Ok(game_id) => Some(GameId::of(game_id)),
+        Err(_) => None,
+    }
+}
+
+pub fn get_discord_id_from_header(req: &HttpRequest) -> Option<DiscordID> {
+    match extract_header_string(req, "discord-id") {
+        Ok(id) => Some(DiscordID { id }),
+        Err(_) => None,
+    }
+}
+
+pub fn get_upload_id_from_header(req: &HttpRequest) -> Option<UploadId> {
+    match extract_header_string(req, "upload-id") {
+        Ok(upload_id) => Some(UploadId::of(upload_id)),
+        Err(_) => None,
+    }
+}
+
+pub fn get_chunk_index_from_header(req: &HttpRequest) -> Option<usize> {
+    match extract_header_string(req, "chunk-index") {
+        Ok(index) => index.parse::<usize>().ok(),
+        Err(_) => None,
+    }
+}
+
+pub fn get_revision_from_header(req: &HttpRequest) -> Option<usize> {
+    match extract_header_string(req, "revision") {
+        Ok(revision) => revision.parse::<usize>().ok(),
+        Err(_) => None,
     }
-    Err(ApiRequestError::Error("range".to_string(), ErrorType::StringConversion).to_api_error().to_response())
 }
 
 pub fn get_lobby_id_from_header(req: &HttpRequest) -> Option<LobbyId> {
@@ -151,6 +170,15 @@ pub fn get_lobby_id_from_value(req: &HttpRequest) -> Option<LobbyId> {
 
 
 
+pub fn get_spectator_from_value(req: &HttpRequest) -> bool {
+    match extract_value(req, "spectator") {
+        Ok(value) => value.parse::<bool>().unwrap_or(false),
+        Err(_) => false,
+    }
+}
+
+
+
 pub fn get_file_name_from_value(req: &HttpRequest) -> Option<String> {
     if let Ok(cookie) = extract_value(&req,"file-name"){
         return Some(cookie);
@@ -1,5 +1,6 @@
 use actix_web::{http, HttpResponse};
 use serde::{Deserialize, Serialize};
+use cult_common::dto::board::DTOBoardProblem;
 use utoipa::{openapi::{security::Http, RefOr, Response, ResponseBuilder}, ToSchema};
 
 
@@ -10,7 +11,7 @@ pub enum ApiError {
     Request(ApiRequestError),
     Internal(String),
     File(ApiFileError),
-
+    Board(ApiBoardError),
 }
 
 pub trait ToResponse {
@@ -24,6 +25,10 @@ pub enum ApiFileError {
     FileInvalid(String),
     FileNotFound(String),
     FileExists,
+    UploadNotFound(String),
+    HashMismatch(String),
+    FileInUse(String, Vec<String>),
+    FileInLobby(String, Vec<String>),
 }
 
 impl ToApiError for ApiFileError {
@@ -44,6 +49,45 @@ impl ToResponse for ApiFileError {
             ApiFileError::FileInvalid(_) => http::StatusCode::BAD_REQUEST, // 400
             ApiFileError::FileNotFound(_) => http::StatusCode::NOT_FOUND, // 404
             ApiFileError::FileExists => http::StatusCode::CONFLICT, // 409
+            ApiFileError::UploadNotFound(_) => http::StatusCode::NOT_FOUND, // 404
+            ApiFileError::HashMismatch(_) => http::StatusCode::UNPROCESSABLE_ENTITY, // 422
+            ApiFileError::FileInUse(_, _) => http::StatusCode::CONFLICT, // 409
+            ApiFileError::FileInLobby(_, _) => http::StatusCode::CONFLICT, // 409
+        }
+    }
+}
+
+
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+pub enum ApiBoardError {
+    BoardError(String),
+    BoardInvalid(String),
+    BoardNotFound(String),
+    RevisionNotFound(String, usize),
+    RevisionConflict(String),
+    NotOwner,
+}
+
+impl ToApiError for ApiBoardError {
+    fn to_api_error(&self) -> ApiError {
+        ApiError::Board(self.clone())
+    }
+}
+
+impl ToResponse for ApiBoardError {
+    fn to_response(&self) -> HttpResponse {
+        HttpResponse::build(self.to_status_code()).json(self)
+    }
+
+    fn to_status_code(&self) -> http::StatusCode {
+        match self.clone(){
+            ApiBoardError::BoardError(_) => http::StatusCode::INTERNAL_SERVER_ERROR, // 500
+            ApiBoardError::BoardInvalid(_) => http::StatusCode::BAD_REQUEST, // 400
+            ApiBoardError::BoardNotFound(_) => http::StatusCode::NOT_FOUND, // 404
+            ApiBoardError::RevisionNotFound(_, _) => http::StatusCode::NOT_FOUND, // 404
+            ApiBoardError::RevisionConflict(_) => http::StatusCode::CONFLICT, // 409
+            ApiBoardError::NotOwner => http::StatusCode::FORBIDDEN, // 403
         }
     }
 }
@@ -62,6 +106,7 @@ impl ToResponse for ApiError {
             ApiError::Request(error) =>  error.to_response(),
             ApiError::Game(error) => error.to_response(),
             ApiError::File(error) => error.to_response(),
+            ApiError::Board(error) => error.to_response(),
             ApiError::Internal(_) =>  HttpResponse::InternalServerError().json(self), // 500
         }
     }
@@ -72,6 +117,7 @@ impl ToResponse for ApiError {
             ApiError::Request(error) =>  error.to_status_code(),
             ApiError::Game(error) => error.to_status_code(),
             ApiError::File(error) => error.to_status_code(),
+            ApiError::Board(error) => error.to_status_code(),
             ApiError::Internal(_) => http::StatusCode::INTERNAL_SERVER_ERROR, // 500
         }
     }
@@ -85,6 +131,9 @@ pub enum ApiGameError {
     GameError(String),
     LobbyInvalid(String),
     LobbyNotFound(String),
+    GameInvalid(String),
+    GameNotFound(String),
+    BoardInvalid(Vec<DTOBoardProblem>),
 }
 
 impl ToApiError for ApiGameError {
@@ -105,6 +154,9 @@ impl ToResponse for ApiGameError {
             ApiGameError::GameError(_) => http::StatusCode::INTERNAL_SERVER_ERROR, // 500
             ApiGameError::LobbyInvalid(_) => http::StatusCode::BAD_REQUEST, // 400
             ApiGameError::LobbyNotFound(_) => http::StatusCode::NOT_FOUND, // 404
+            ApiGameError::GameInvalid(_) => http::StatusCode::BAD_REQUEST, // 400
+            ApiGameError::GameNotFound(_) => http::StatusCode::NOT_FOUND, // 404
+            ApiGameError::BoardInvalid(_) => http::StatusCode::BAD_REQUEST, // 400
         }
     }
     
@@ -1,28 +1,33 @@
+use std::hash::Hasher;
 use std::sync::Arc;
 use crate::rest::api::file_part_error;
 use crate::rest::error::{ApiError, ApiGameError, ApiRequestError, ApiSessionError, ToApiError, ToResponse};
-use crate::data::{SessionRequest};
+use crate::data::{SessionRequest, UploadSessionData};
 use crate::services::db::MongoServer;
 use crate::services::game::{CreateLobby, FileMetadata, GameServer, GetLobbyMediaToken};
 use crate::services::lobby::CanJoinLobby;
+use crate::services::media::{MediaProcessor, ProcessMedia};
+use crate::services::storage::{assemble_upload, MediaStorage};
 use crate::settings::Settings;
 use actix::Addr;
 
 use actix_multipart::Multipart;
-use actix_web::{get, HttpRequest, HttpResponse, post, web};
-use bson::{bson, doc, Bson};
+use actix_web::{delete, get, put, HttpRequest, HttpResponse, post, web};
+use bson::{bson, doc, Bson, Document};
 use bytes::Bytes;
 use chrono::Local;
 use cult_common::dto::api::{ApiResponse};
-use cult_common::dto::file::{FileMultiPart};
+use cult_common::dto::file::{DTOFileOrganize, DTOFileRename, DTOUploadRequest, FileMultiPart, VariantKind};
+use cult_common::wasm_lib::hashs::filedata::FileDataHash;
 use cult_common::wasm_lib::hashs::validate::ValidateHash;
-use cult_common::wasm_lib::{DiscordUser, JeopardyMode};
+use cult_common::wasm_lib::ids::board::BoardId;
+use cult_common::wasm_lib::ids::discord::DiscordID;
+use cult_common::wasm_lib::{DiscordUser, JeopardyMode, NumberScope};
 use futures::stream::once;
-use futures::{AsyncReadExt, AsyncWriteExt, StreamExt};
-use mongodb::gridfs::GridFsUploadStream;
-use mongodb::options::FindOptions;
+use futures::{AsyncReadExt, StreamExt};
 use serde::Serialize;
 use serde_json::json;
+use twox_hash::XxHash64;
 use cult_common::backend::JeopardyBoard;
 use cult_common::wasm_lib::ids::lobby::LobbyId;
 use utoipa::ToSchema;
@@ -31,7 +36,8 @@ use crate::authentication::discord::is_admin;
 use crate::services::game;
 use crate::services::game::UserSession;
 
-use super::data::{self, get_media_token_from_header};
+use super::range::{etag_matches, file_etag, get_byte_ranges_from_header, stream_range, ByteRanges, MultipartRanges};
+use super::data::{self, get_chunk_index_from_header, get_discord_id_from_header, get_media_token_from_header, get_upload_id_from_header};
 use super::error::ApiFileError;
 
 #[utoipa::path(
@@ -67,7 +73,7 @@ use super::error::ApiFileError;
     )
 )]
 #[post("/api/file/upload")]
-async fn upload_file_part(req: HttpRequest,db: web::Data<Arc<MongoServer>>,mut payload: Multipart) -> Result<HttpResponse, actix_web::Error> {
+async fn upload_file_part(req: HttpRequest,db: web::Data<Arc<MongoServer>>,storage: web::Data<Arc<dyn MediaStorage>>,processor: web::Data<Addr<MediaProcessor>>,mut payload: Multipart) -> Result<HttpResponse, actix_web::Error> {
     let user_session = match get_session(&req, &db).await {
         Some(data) => data,
         None => return Ok(ApiSessionError::NotFound.to_api_error().to_response()),
@@ -91,13 +97,12 @@ async fn upload_file_part(req: HttpRequest,db: web::Data<Arc<MongoServer>>,mut p
     let mut file_multi = FileMultiPart::default();
 
     let mut upload_stream = None;
+    let mut hasher = XxHash64::with_seed(0);
 
     file_multi.file_name = Some(file_name.clone());
 
-    if let Ok(data) = db.collections.file_bucket_files.find_one(bson::doc!{"filename":Some(file_name.clone())}).await {
-        if let Some(_) = data {
-            return Ok(file_part_error(None, ApiFileError::FileExists.to_api_error()).await);
-        }
+    if storage.file_exists(&file_name).await {
+        return Ok(file_part_error(None, ApiFileError::FileExists.to_api_error()).await);
     }
 
 
@@ -129,15 +134,24 @@ async fn upload_file_part(req: HttpRequest,db: web::Data<Arc<MongoServer>>,mut p
                                     None => return Ok(file_part_error(None, ApiFileError::FileInvalid("Can´t get file type".to_string()).to_api_error()).await),
                                 };
                                 file_multi.file_type = Some(file_type.to_string()); 
-                                let mut stream = db.collections.file_bucket.open_upload_stream(file_name.to_string()).await.expect("Can´t open upload stream");   
-                                stream.write_all(&data).await.expect("Can´t write to upload stream");
+                                let mut stream = match storage.open_writer(file_name).await {
+                                    Some(data) => data,
+                                    None => return Ok(file_part_error(None, ApiFileError::FileError("Can´t open upload stream".to_string()).to_api_error()).await),
+                                };
+                                hasher.write(&data);
+                                if stream.write(&data).await.is_err() {
+                                    return Ok(file_part_error(Some(stream), ApiFileError::FileError("Can´t write to upload stream".to_string()).to_api_error()).await);
+                                }
                                 upload_stream = Some(stream);
                             } else {
                                 let stream = match &mut upload_stream {
                                     Some(data) => data,
                                     None => return Ok(file_part_error(None, ApiFileError::FileInvalid("Can´t get upload stream".to_string()).to_api_error()).await),                       
                                  };
-                                stream.write_all(&data).await.expect("Can´t write to upload stream");
+                                hasher.write(&data);
+                                if stream.write(&data).await.is_err() {
+                                    return Ok(file_part_error(upload_stream, ApiFileError::FileError("Can´t write to upload stream".to_string()).to_api_error()).await);
+                                }
                             }                        
                         },
                         "validate_hash" => {
@@ -162,31 +176,22 @@ async fn upload_file_part(req: HttpRequest,db: web::Data<Arc<MongoServer>>,mut p
         return Ok(file_part_error(upload_stream, ApiFileError::FileInvalid("File is not valid".to_string()).to_api_error()).await);
     }
 
-    if let Some(mut stream) = upload_stream {
-        let id = stream.id().clone();
-
-        if let Ok(data) = db.collections.file_bucket_files.find_one(bson::doc!{"filename":Some(file_name.clone())}).await {
-            if let Some(_) = data {
-                return Ok(file_part_error(Some(stream), ApiFileError::FileExists.to_api_error()).await);
-            }
-        }
-        if let Err(_) = stream.flush().await {
-            return Ok(file_part_error(Some(stream), ApiFileError::FileError("Can´t flush upload stream".to_string()).to_api_error()).await);
+    if let Some(stream) = upload_stream {
+        if storage.file_exists(&file_name).await {
+            return Ok(file_part_error(Some(stream), ApiFileError::FileExists.to_api_error()).await);
         }
-        if let Err(_) = stream.close().await {
-            return Ok(file_part_error(Some(stream), ApiFileError::FileError("Can´t close upload stream".to_string()).to_api_error()).await);
+        let hash = FileDataHash::new(hasher.finish().to_string());
+        if !file_multi.validate_hash.as_ref().is_some_and(|validate_hash| validate_hash.validate_file_chunk(&hash)) {
+            return Ok(file_part_error(Some(stream), ApiFileError::HashMismatch(hash.get_hash()).to_api_error()).await);
         }
 
-        let file_meta = FileMetadata{
-            file_type: file_multi.file_type.unwrap(),
-            validate_hash: file_multi.validate_hash.unwrap(),
-            uploader: file_multi.uploader_id.unwrap(),
-        };
+        let file_meta = FileMetadata::new(file_multi.file_type.unwrap(), file_multi.validate_hash.unwrap(), file_multi.uploader_id.unwrap());
         println!("{:#?}", file_meta);
 
-        if let Err(_) = db.collections.file_bucket_files.update_one(bson::doc! { "_id":id}, bson::doc! {"$set": {"metadata": file_meta}}).await {
-            return Ok(file_part_error(Some(stream), ApiFileError::FileError("Can´t update file metadata".to_string()).to_api_error()).await);
+        if !stream.finish(file_meta).await {
+            return Ok(file_part_error(None, ApiFileError::FileError("Can´t store file".to_string()).to_api_error()).await);
         }
+        processor.do_send(ProcessMedia { file_name });
     }
 
     return Ok(HttpResponse::Ok().finish())
@@ -197,7 +202,7 @@ async fn upload_file_part(req: HttpRequest,db: web::Data<Arc<MongoServer>>,mut p
 
 impl From<FileMetadata> for Bson {
     fn from(data: FileMetadata) -> Self {
-        let doc = bson::doc! {
+        let mut doc = bson::doc! {
             "file_type": data.file_type,
             "validate_hash": {
                 "hash": data.validate_hash.get_hash(),
@@ -205,7 +210,18 @@ impl From<FileMetadata> for Bson {
             "uploader": {
                 "id": data.uploader.id,
             },
+            "tags": data.tags,
+            "folder": data.folder,
         };
+        if let Some(variant) = data.variant {
+            doc.insert("variant", bson::to_bson(&variant).unwrap_or(Bson::Null));
+        }
+        if let Some(source) = data.source {
+            doc.insert("source", source);
+        }
+        if let Some(processing) = data.processing {
+            doc.insert("processing", bson::to_bson(&processing).unwrap_or(Bson::Null));
+        }
         Bson::Document(doc)
     }
 }
@@ -213,7 +229,7 @@ impl From<FileMetadata> for Bson {
 
 
 #[get("/api/file/download")]
-async fn get_file_from_name(req: HttpRequest,  db: web::Data<Arc<MongoServer>>, settings:web::Data<Arc<Settings>>, game_server:web::Data<Addr<GameServer>>) -> Result<HttpResponse, actix_web::Error> {
+async fn get_file_from_name(req: HttpRequest,  db: web::Data<Arc<MongoServer>>, storage: web::Data<Arc<dyn MediaStorage>>, settings:web::Data<Arc<Settings>>, game_server:web::Data<Addr<GameServer>>) -> Result<HttpResponse, actix_web::Error> {
     let user_session = match get_session(&req, &db).await {
         Some(data) => data,
         None => return Ok(file_part_error(None, ApiSessionError::NotFound.to_api_error()).await),
@@ -260,50 +276,95 @@ async fn get_file_from_name(req: HttpRequest,  db: web::Data<Arc<MongoServer>>,
     }
 
         
-    let file_data = match db.collections.file_bucket_files.find_one(doc!{"filename":Some(file_name.clone())}).await {
-        Ok(data) => {
-            if let Some(data) = data {
-                  data
-            } else {
-                return Ok(file_part_error(None, ApiFileError::FileNotFound(file_name).to_api_error()).await);
+    let file_data = match storage.find_file(&file_name).await {
+        Some(data) => data,
+        None => return Ok(file_part_error(None, ApiFileError::FileNotFound(file_name).to_api_error()).await),
+    };
+
+    // a requested variant is served in place of the file, without a web variant the original is playable already
+    let variant = extract_header_string(&req, "media-variant").ok().and_then(|data| data.parse::<VariantKind>().ok());
+    let file_data = match variant {
+        None => file_data,
+        Some(kind) => {
+            let variant_file = match file_data.metadata.as_ref().and_then(|data| data.processing.as_ref()).and_then(|processing| processing.get_variant(kind)) {
+                Some(variant) => storage.find_file(&variant.file_name).await,
+                None => None,
+            };
+            match variant_file {
+                Some(data) => data,
+                None if kind == VariantKind::Web => file_data,
+                None => return Ok(file_part_error(None, ApiFileError::FileNotFound(format!("{} {}", file_name, kind.name())).to_api_error()).await),
             }
-            
-        }
-        Err(err) => {
-            println!("Error while downloading file: {:#?}", err);
-            return Ok(file_part_error(None, ApiFileError::FileError("Error while downloading file".to_string()).to_api_error()).await);
         }
     };
 
-    let mut test = match db.collections.file_bucket.open_download_stream_by_name(file_name.clone()).await {
-        Ok(data) => data,
-        Err(_) => return Ok(file_part_error(None, ApiFileError::FileError("Error while downloading file1".to_string()).to_api_error()).await),
-    };
     println!("Downloading file: {}", file_name);
 
-    let file_meta = match file_data.metadata{
+    let file_meta = match file_data.metadata.clone() {
         Some(data) => data,
         None => return Ok(file_part_error(None, ApiFileError::FileError("Error while downloading file2".to_string()).to_api_error()).await),
     };
 
+    let etag = file_etag(&file_meta.validate_hash.get_hash());
+    if etag_matches(&req, "if-none-match", &etag) {
+        let mut response = HttpResponse::NotModified()
+                                        .insert_header(("ETag", etag))
+                                        .insert_header(("Accept-Ranges", "bytes"))
+                                        .finish();
+        set_session_token_cookie(&mut response, &settings,&user_session);
+        return Ok(response);
+    }
 
+    // a stale If-Range means the client's partial copy is outdated, so it gets the whole file again
+    let ranges = if req.headers().contains_key("if-range") && !etag_matches(&req, "if-range", &etag) {
+        ByteRanges::Full
+    } else {
+        get_byte_ranges_from_header(&req, file_data.length)
+    };
 
-    let mut buf = Vec::new();
-    if let Err(_) = test.read_to_end(&mut buf).await {
-        return Ok(file_part_error(None, ApiFileError::FileError("Error while downloading file3".to_string()).to_api_error()).await);
-    }
-    
-    let mut response =  HttpResponse::Ok()
-                                        .insert_header(("file-name", file_name))
-                                        .insert_header(("file-type", file_meta.file_type))
-                                        .insert_header(("file-size", file_data.length))
-                                        .insert_header(("file-upload-date", file_data.upload_date.to_string()))
-                                        .insert_header(("uploader-id", file_meta.uploader.id))
-                                        .insert_header(("validate-hash", file_meta.validate_hash.get_hash()))
-                                        .content_type("application/octet-stream")
-                                        .streaming(once(async move {
-                                            Ok::<_, actix_web::Error>(Bytes::from(buf))
-                                        }));
+    let mut builder = match &ranges {
+        ByteRanges::Full => HttpResponse::Ok(),
+        ByteRanges::Partial(_) => HttpResponse::PartialContent(),
+        ByteRanges::Unsatisfiable => HttpResponse::RangeNotSatisfiable(),
+    };
+    builder.insert_header(("file-name", file_name))
+        .insert_header(("file-type", file_meta.file_type))
+        .insert_header(("file-size", file_data.length))
+        .insert_header(("file-upload-date", file_data.upload_date.to_string()))
+        .insert_header(("uploader-id", file_meta.uploader.id))
+        .insert_header(("validate-hash", file_meta.validate_hash.get_hash()))
+        .insert_header(("ETag", etag))
+        .insert_header(("Accept-Ranges", "bytes"));
+
+    let storage = storage.get_ref().clone();
+    let length = file_data.length;
+    let mut response = match ranges {
+        ByteRanges::Unsatisfiable => builder
+            .insert_header(("Content-Range", format!("bytes */{}", length)))
+            .finish(),
+        ByteRanges::Full if length == 0 => builder
+            .content_type("application/octet-stream")
+            .finish(),
+        ByteRanges::Full => builder
+            .content_type("application/octet-stream")
+            .no_chunking(length as u64)
+            .streaming(stream_range(storage, file_data, NumberScope::new(0, length - 1))),
+        ByteRanges::Partial(ranges) if ranges.len() == 1 => {
+            let range = ranges[0].clone();
+            builder
+                .insert_header(("Content-Range", format!("bytes {}-{}/{}", range.start, range.end, length)))
+                .content_type("application/octet-stream")
+                .no_chunking((range.end - range.start + 1) as u64)
+                .streaming(stream_range(storage, file_data, range))
+        }
+        ByteRanges::Partial(ranges) => {
+            let multipart = MultipartRanges::new(storage, file_data, ranges, "application/octet-stream");
+            builder
+                .content_type(format!("multipart/byteranges; boundary={}", multipart.boundary))
+                .no_chunking(multipart.length)
+                .streaming(multipart.body)
+        }
+    };
 
     set_session_token_cookie(&mut response, &settings,&user_session);
     Ok(response)
@@ -327,7 +388,7 @@ async fn get_file_from_name(req: HttpRequest,  db: web::Data<Arc<MongoServer>>,
     )
 )]
 #[get("/api/files")]
-async fn get_file_size(req: HttpRequest,  db: web::Data<Arc<MongoServer>>, settings:web::Data<Arc<Settings>>) -> Result<HttpResponse, actix_web::Error> {
+async fn get_file_size(req: HttpRequest,  db: web::Data<Arc<MongoServer>>, storage: web::Data<Arc<dyn MediaStorage>>, settings:web::Data<Arc<Settings>>) -> Result<HttpResponse, actix_web::Error> {
     let user_session = match get_session(&req, &db).await {
         Some(data) => data,
         None => return Ok(file_part_error(None, ApiSessionError::NotFound.to_api_error()).await),
@@ -337,10 +398,7 @@ async fn get_file_size(req: HttpRequest,  db: web::Data<Arc<MongoServer>>, setti
         return Ok(file_part_error(None, ApiSessionError::NotAdmin.to_api_error()).await);
     }
 
-    let file_count = match db.collections.file_bucket_files.count_documents(doc!{}).await {
-        Ok(data) => data,
-        Err(_) => 0,
-    };
+    let file_count = storage.count_files(doc! {"metadata.variant": null}).await;
 
 
     let mut response = HttpResponse::Ok().json(json!({
@@ -362,6 +420,11 @@ async fn get_file_size(req: HttpRequest,  db: web::Data<Arc<MongoServer>>, setti
         ("user_session_token" = Option<String>, Query, description = "User session token"),
         ("page_size" = u64, Header, description = "Token do access media file"),
         ("page" = u64, Header, description = "page"),
+        ("search" = Option<String>, Header, description = "Part of the file name, ignoring case"),
+        ("file-type" = Option<String>, Header, description = "Start of the file type, like image or video/mp4"),
+        ("discord-id" = Option<String>, Header, description = "Only files of this uploader"),
+        ("tag" = Option<String>, Header, description = "Only files with this tag"),
+        ("folder" = Option<String>, Header, description = "Only files in this folder"),
     ),
     responses(
         // 200
@@ -372,7 +435,7 @@ async fn get_file_size(req: HttpRequest,  db: web::Data<Arc<MongoServer>>, setti
     )
 )]
 #[get("/api/file/list")]
-async fn get_file_list(req: HttpRequest,  db: web::Data<Arc<MongoServer>>, settings:web::Data<Arc<Settings>>) -> Result<HttpResponse, actix_web::Error> {
+async fn get_file_list(req: HttpRequest,  db: web::Data<Arc<MongoServer>>, storage: web::Data<Arc<dyn MediaStorage>>, settings:web::Data<Arc<Settings>>) -> Result<HttpResponse, actix_web::Error> {
     let user_session = match get_session(&req, &db).await {
         Some(data) => data,
         None => return Ok(file_part_error(None, ApiSessionError::NotFound.to_api_error()).await),
@@ -391,11 +454,9 @@ async fn get_file_list(req: HttpRequest,  db: web::Data<Arc<MongoServer>>, setti
         Err(e) => return Ok(e),
     };
 
-    // Count total files
-    let file_count = match db.collections.file_bucket_files.count_documents(doc! {}).await {
-        Ok(count) => count,
-        Err(_) => return Ok(file_part_error(None, ApiFileError::FileError("Error counting files".to_string()).to_api_error()).await),
-    };
+    // Count matching files
+    let filter = get_file_filter(&req);
+    let file_count = storage.count_files(filter.clone()).await;
 
     // Validate pagination
     if page_size == 0 {
@@ -406,38 +467,24 @@ async fn get_file_list(req: HttpRequest,  db: web::Data<Arc<MongoServer>>, setti
         return Ok(file_part_error(None, ApiFileError::FileError("Page out of range".to_string()).to_api_error()).await);
     }
 
-    // Prepare MongoDB find options
+    // Sorted by filename in ascending order
     let skip = page * page_size as u64;
-    let find_options = FindOptions::builder()
-        .sort(doc! { "filename": 1 })  // Sort by filename in ascending order
-        .skip(skip)
-        .limit(page_size as i64)
-        .build();
-
-
-
-    let mut cursor  = match db.collections.file_bucket_files.find(doc!{}).with_options(find_options).await {
-        Ok(data) => data,
-        Err(_) => return Ok(file_part_error(None, ApiFileError::FileError("Error getting files".to_string()).to_api_error()).await),
+    let files = match storage.list_files(filter, skip, page_size as i64).await {
+        Some(data) => data,
+        None => return Ok(file_part_error(None, ApiFileError::FileError("Error getting files".to_string()).to_api_error()).await),
     };
 
     let mut dto_file_data = Vec::new();
-
-    while let Some(result) = cursor.next().await {
-        match result {
-            Ok(data) => {
-                match data.to_dto() {
-                    Some(data) => dto_file_data.push(data),
-                    None => return Ok(file_part_error(None, ApiFileError::FileError("Error converting file data".to_string()).to_api_error()).await),
-                }
-            },
-            Err(_) => return Ok(file_part_error(None, ApiFileError::FileError("Error getting file data".to_string()).to_api_error()).await),
+    for data in files {
+        match data.to_dto() {
+            Some(data) => dto_file_data.push(data),
+            None => return Ok(file_part_error(None, ApiFileError::FileError("Error converting file data".to_string()).to_api_error()).await),
         }
-        
     }
 
     let mut response = HttpResponse::Ok().json(json!({
         "files": dto_file_data,
+        "file_count": file_count,
         "page": page,
         "page_size": page_size,
     }));
@@ -448,3 +495,670 @@ async fn get_file_list(req: HttpRequest,  db: web::Data<Arc<MongoServer>>, setti
     set_session_token_cookie(&mut response, &settings,&user_session);
     Ok(response)
 }
+
+
+/// Filter of the file list from the optional `search`, `file-type`, `discord-id`, `tag` and `folder` headers.
+/// Generated variants are left out, they belong to the file they were made from.
+fn get_file_filter(req: &HttpRequest) -> Document {
+    let mut filter = doc! {"metadata.variant": null};
+    if let Ok(search) = extract_header_string(req, "search") {
+        filter.insert("filename", doc! {"$regex": escape_regex(&search), "$options": "i"});
+    }
+    if let Ok(file_type) = extract_header_string(req, "file-type") {
+        filter.insert("metadata.file_type", doc! {"$regex": format!("^{}", escape_regex(&file_type)), "$options": "i"});
+    }
+    if let Some(uploader) = get_discord_id_from_header(req) {
+        filter.insert("metadata.uploader.id", uploader.id);
+    }
+    if let Ok(tag) = extract_header_string(req, "tag") {
+        filter.insert("metadata.tags", tag);
+    }
+    if let Ok(folder) = extract_header_string(req, "folder") {
+        filter.insert("metadata.folder", folder);
+    }
+    filter
+}
+
+fn escape_regex(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if "\\.+*?()|[]{}^$".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+
+
+/// Chunks are stored as single documents, so they have to stay well below the 16MB document limit.
+const MAX_UPLOAD_CHUNK_SIZE: usize = 8 * 1024 * 1024;
+
+/// Largest file a chunked upload may announce, the chunks are kept in mongodb until the upload completes.
+const MAX_UPLOAD_FILE_SIZE: usize = 1024 * 1024 * 1024;
+
+async fn get_uploader(req: &HttpRequest, db: &web::Data<Arc<MongoServer>>) -> Result<(UserSession, DiscordID), HttpResponse> {
+    let user_session = match get_session(req, db).await {
+        Some(data) => data,
+        None => return Err(ApiSessionError::NotFound.to_api_error().to_response()),
+    };
+    if !is_admin(&user_session, db).await {
+        return Err(ApiSessionError::NotAdmin.to_api_error().to_response());
+    }
+    let discord_id = match user_session.get_discord_id() {
+        Some(data) => data,
+        None => return Err(ApiSessionError::NoDiscordData.to_api_error().to_response()),
+    };
+    Ok((user_session, discord_id))
+}
+
+async fn get_own_upload(req: &HttpRequest, db: &web::Data<Arc<MongoServer>>, uploader: &DiscordID) -> Result<UploadSessionData, HttpResponse> {
+    let upload_id = match get_upload_id_from_header(req) {
+        Some(data) => data,
+        None => return Err(ApiFileError::FileInvalid("No upload id found".to_string()).to_api_error().to_response()),
+    };
+    match db.find_upload_session(&upload_id).await {
+        Some(upload) if upload.uploader.eq(uploader) => Ok(upload),
+        _ => Err(ApiFileError::UploadNotFound(upload_id.id).to_api_error().to_response()),
+    }
+}
+
+
+
+#[utoipa::path(
+    post,
+    path = "/api/file/upload/session",
+    params(
+        ("user_session_id" = Option<String>, Query, description = "User session ID"),
+        ("user_session_token" = Option<String>, Query, description = "User session token"),
+    ),
+    request_body(content = DTOUploadRequest, description = "File to upload in chunks", content_type = "application/json"),
+    responses(
+        // 200
+        (status = 200, description = "New upload, or the open upload of the same file to resume", body = DTOUploadSession),
+
+        // Session
+        (status = 404, description = "No User Session", body = ApiError),
+        (status = 401, description = "Not a admin Session", body = ApiError),
+        (status = 403, description = "No Discord Data", body = ApiError),
+
+        //FILE
+        (status = 400, description = "File Invalid", body = ApiError),
+        (status = 409, description = "File Exists", body = ApiError),
+        (status = 500, description = "File error", body = ApiError),
+    ),
+    security(
+        ("cookie" = ["user_session_id", "user_session_token"])
+    )
+)]
+#[post("/api/file/upload/session")]
+async fn start_upload(req: HttpRequest, json: web::Json<DTOUploadRequest>, db: web::Data<Arc<MongoServer>>, storage: web::Data<Arc<dyn MediaStorage>>, settings: web::Data<Arc<Settings>>) -> HttpResponse {
+    let (user_session, uploader) = match get_uploader(&req, &db).await {
+        Ok(data) => data,
+        Err(response) => return response,
+    };
+    let request = json.into_inner();
+    if request.file_name.is_empty() || request.file_size == 0 {
+        return ApiFileError::FileInvalid("File is empty".to_string()).to_api_error().to_response();
+    }
+    if request.file_size > MAX_UPLOAD_FILE_SIZE {
+        return ApiFileError::FileInvalid(format!("File size must not exceed {} bytes", MAX_UPLOAD_FILE_SIZE)).to_api_error().to_response();
+    }
+    if request.chunk_size == 0 || request.chunk_size > MAX_UPLOAD_CHUNK_SIZE {
+        return ApiFileError::FileInvalid(format!("Chunk size must be between 1 and {} bytes", MAX_UPLOAD_CHUNK_SIZE)).to_api_error().to_response();
+    }
+    if storage.file_exists(&request.file_name).await {
+        return ApiFileError::FileExists.to_api_error().to_response();
+    }
+
+    let upload = match db.find_open_upload(&uploader, &request.file_name).await {
+        Some(upload) if upload.matches(&request) => upload,
+        open => {
+            if let Some(upload) = open {
+                db.delete_upload_session(&upload.upload_id).await;
+            }
+            let upload = UploadSessionData::new(&uploader, request);
+            if !db.insert_upload_session(&upload).await {
+                return ApiFileError::FileError("Can´t start upload".to_string()).to_api_error().to_response();
+            }
+            upload
+        }
+    };
+
+    let mut response = HttpResponse::Ok().json(upload.to_dto());
+    set_session_token_cookie(&mut response, &settings, &user_session);
+    response
+}
+
+
+
+#[utoipa::path(
+    get,
+    path = "/api/file/upload/session",
+    params(
+        ("user_session_id" = Option<String>, Query, description = "User session ID"),
+        ("user_session_token" = Option<String>, Query, description = "User session token"),
+        ("upload-id" = String, Header, description = "Upload ID"),
+    ),
+    responses(
+        // 200
+        (status = 200, description = "Confirmed chunks of the upload", body = DTOUploadSession),
+
+        // Session
+        (status = 404, description = "No User Session", body = ApiError),
+        (status = 401, description = "Not a admin Session", body = ApiError),
+        (status = 403, description = "No Discord Data", body = ApiError),
+
+        //FILE
+        (status = 404, description = "Upload not found", body = ApiError),
+    ),
+    security(
+        ("cookie" = ["user_session_id", "user_session_token"])
+    )
+)]
+#[get("/api/file/upload/session")]
+async fn get_upload(req: HttpRequest, db: web::Data<Arc<MongoServer>>, settings: web::Data<Arc<Settings>>) -> HttpResponse {
+    let (user_session, uploader) = match get_uploader(&req, &db).await {
+        Ok(data) => data,
+        Err(response) => return response,
+    };
+    let upload = match get_own_upload(&req, &db, &uploader).await {
+        Ok(data) => data,
+        Err(response) => return response,
+    };
+
+    let mut response = HttpResponse::Ok().json(upload.to_dto());
+    set_session_token_cookie(&mut response, &settings, &user_session);
+    response
+}
+
+
+
+#[utoipa::path(
+    put,
+    path = "/api/file/upload/chunk",
+    params(
+        ("user_session_id" = Option<String>, Query, description = "User session ID"),
+        ("user_session_token" = Option<String>, Query, description = "User session token"),
+        ("upload-id" = String, Header, description = "Upload ID"),
+        ("chunk-index" = usize, Header, description = "Index of the chunk, starting at 0"),
+    ),
+    request_body(content = Vec<u8>, description = "Chunk data, chunk_size bytes except for the last chunk", content_type = "application/octet-stream"),
+    responses(
+        // 200
+        (status = 200, description = "Chunk stored", body = DTOUploadSession),
+
+        // Session
+        (status = 404, description = "No User Session", body = ApiError),
+        (status = 401, description = "Not a admin Session", body = ApiError),
+        (status = 403, description = "No Discord Data", body = ApiError),
+
+        //FILE
+        (status = 400, description = "Chunk invalid", body = ApiError),
+        (status = 404, description = "Upload not found", body = ApiError),
+        (status = 500, description = "File error", body = ApiError),
+    ),
+    security(
+        ("cookie" = ["user_session_id", "user_session_token"])
+    )
+)]
+#[put("/api/file/upload/chunk")]
+async fn upload_chunk(req: HttpRequest, body: web::Bytes, db: web::Data<Arc<MongoServer>>, settings: web::Data<Arc<Settings>>) -> HttpResponse {
+    let (user_session, uploader) = match get_uploader(&req, &db).await {
+        Ok(data) => data,
+        Err(response) => return response,
+    };
+    let upload = match get_own_upload(&req, &db, &uploader).await {
+        Ok(data) => data,
+        Err(response) => return response,
+    };
+    let index = match get_chunk_index_from_header(&req) {
+        Some(data) => data,
+        None => return ApiFileError::FileInvalid("No chunk index found".to_string()).to_api_error().to_response(),
+    };
+    match upload.chunk_length(index) {
+        Some(length) if length == body.len() => {},
+        Some(length) => return ApiFileError::FileInvalid(format!("Chunk {} must be {} bytes, got {}", index, length, body.len())).to_api_error().to_response(),
+        None => return ApiFileError::FileInvalid(format!("Chunk {} is out of range", index)).to_api_error().to_response(),
+    }
+
+    let upload = match db.store_upload_chunk(&upload, index, body).await {
+        Some(data) => data,
+        None => return ApiFileError::FileError(format!("Can´t store chunk {}", index)).to_api_error().to_response(),
+    };
+
+    let mut response = HttpResponse::Ok().json(upload.to_dto());
+    set_session_token_cookie(&mut response, &settings, &user_session);
+    response
+}
+
+
+
+#[utoipa::path(
+    post,
+    path = "/api/file/upload/finish",
+    params(
+        ("user_session_id" = Option<String>, Query, description = "User session ID"),
+        ("user_session_token" = Option<String>, Query, description = "User session token"),
+        ("upload-id" = String, Header, description = "Upload ID"),
+    ),
+    responses(
+        // 200
+        (status = 200, description = "File stored after the hash was verified", body = DTOFileData),
+
+        // Session
+        (status = 404, description = "No User Session", body = ApiError),
+        (status = 401, description = "Not a admin Session", body = ApiError),
+        (status = 403, description = "No Discord Data", body = ApiError),
+
+        //FILE
+        (status = 400, description = "Chunks missing", body = ApiError),
+        (status = 404, description = "Upload not found", body = ApiError),
+        (status = 409, description = "File Exists", body = ApiError),
+        (status = 422, description = "Hash mismatch, the upload is discarded", body = ApiError),
+        (status = 500, description = "File error", body = ApiError),
+    ),
+    security(
+        ("cookie" = ["user_session_id", "user_session_token"])
+    )
+)]
+#[post("/api/file/upload/finish")]
+async fn finish_upload(req: HttpRequest, db: web::Data<Arc<MongoServer>>, storage: web::Data<Arc<dyn MediaStorage>>, processor: web::Data<Addr<MediaProcessor>>, settings: web::Data<Arc<Settings>>) -> HttpResponse {
+    let (user_session, uploader) = match get_uploader(&req, &db).await {
+        Ok(data) => data,
+        Err(response) => return response,
+    };
+    let upload = match get_own_upload(&req, &db, &uploader).await {
+        Ok(data) => data,
+        Err(response) => return response,
+    };
+    if let Some(index) = upload.next_chunk() {
+        return ApiFileError::FileInvalid(format!("Chunk {} is missing", index)).to_api_error().to_response();
+    }
+    if storage.file_exists(&upload.file_name).await {
+        return ApiFileError::FileExists.to_api_error().to_response();
+    }
+
+    let hash = match assemble_upload(storage.get_ref().as_ref(), &db, &upload).await {
+        Some(data) => data,
+        None => return ApiFileError::FileError("Can´t assemble upload".to_string()).to_api_error().to_response(),
+    };
+    // a mismatch means the stored chunks are corrupt, so the upload has to start over
+    db.delete_upload_session(&upload.upload_id).await;
+    if !upload.validate_hash.validate_file_chunk(&hash) {
+        return ApiFileError::HashMismatch(hash.get_hash()).to_api_error().to_response();
+    }
+    processor.do_send(ProcessMedia { file_name: upload.file_name.clone() });
+
+    let file_data = match storage.find_file(&upload.file_name).await.and_then(|file_data| file_data.to_dto()) {
+        Some(data) => data,
+        None => return ApiFileError::FileNotFound(upload.file_name).to_api_error().to_response(),
+    };
+    let mut response = HttpResponse::Ok().json(file_data);
+    set_session_token_cookie(&mut response, &settings, &user_session);
+    response
+}
+
+
+
+#[utoipa::path(
+    delete,
+    path = "/api/file/upload/session",
+    params(
+        ("user_session_id" = Option<String>, Query, description = "User session ID"),
+        ("user_session_token" = Option<String>, Query, description = "User session token"),
+        ("upload-id" = String, Header, description = "Upload ID"),
+    ),
+    responses(
+        // 200
+        (status = 200, description = "Upload and its chunks deleted", body = ApiResponse),
+
+        // Session
+        (status = 404, description = "No User Session", body = ApiError),
+        (status = 401, description = "Not a admin Session", body = ApiError),
+        (status = 403, description = "No Discord Data", body = ApiError),
+
+        //FILE
+        (status = 404, description = "Upload not found", body = ApiError),
+    ),
+    security(
+        ("cookie" = ["user_session_id", "user_session_token"])
+    )
+)]
+#[delete("/api/file/upload/session")]
+async fn cancel_upload(req: HttpRequest, db: web::Data<Arc<MongoServer>>, settings: web::Data<Arc<Settings>>) -> HttpResponse {
+    let (user_session, uploader) = match get_uploader(&req, &db).await {
+        Ok(data) => data,
+        Err(response) => return response,
+    };
+    let upload = match get_own_upload(&req, &db, &uploader).await {
+        Ok(data) => data,
+        Err(response) => return response,
+    };
+
+    let deleted = db.delete_upload_session(&upload.upload_id).await;
+    let mut response = HttpResponse::Ok().json(ApiResponse::new(deleted));
+    set_session_token_cookie(&mut response, &settings, &user_session);
+    response
+}
+
+
+
+/// Points the media of a stored board to the renamed file, retried when the board changed in between.
+async fn rename_board_media(db: &MongoServer, board_id: &BoardId, from: &str, to: &str) -> bool {
+    for _ in 0..3 {
+        let board_data = match db.find_board(board_id).await {
+            Some(data) => data,
+            None => return false,
+        };
+        let mut board = board_data.board.clone();
+        board.rename_media(from, to);
+        if db.update_board(&board_data, board).await.is_some() {
+            return true;
+        }
+    }
+    false
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/file",
+    params(
+        ("user_session_id" = Option<String>, Query, description = "User session ID"),
+        ("user_session_token" = Option<String>, Query, description = "User session token"),
+        ("file-name" = String, Header, description = "File name"),
+    ),
+    responses(
+        // 200
+        (status = 200, description = "File and its generated variants deleted", body = ApiResponse),
+
+        // Session
+        (status = 404, description = "No User Session", body = ApiError),
+        (status = 401, description = "Not a admin Session", body = ApiError),
+        (status = 403, description = "No Discord Data", body = ApiError),
+
+        //FILE
+        (status = 400, description = "No file name found", body = ApiError),
+        (status = 404, description = "File not found", body = ApiError),
+        (status = 409, description = "File is used by stored boards or their revisions, or by a lobby that has not ended, with their ids", body = ApiError),
+    ),
+    security(
+        ("cookie" = ["user_session_id", "user_session_token"])
+    )
+)]
+#[delete("/api/file")]
+async fn delete_file(req: HttpRequest, db: web::Data<Arc<MongoServer>>, storage: web::Data<Arc<dyn MediaStorage>>, settings: web::Data<Arc<Settings>>) -> HttpResponse {
+    let (user_session, _) = match get_uploader(&req, &db).await {
+        Ok(data) => data,
+        Err(response) => return response,
+    };
+    let file_name = match extract_header_string(&req, "file-name") {
+        Ok(data) => data,
+        Err(_) => return ApiFileError::FileInvalid("No file name found".to_string()).to_api_error().to_response(),
+    };
+    let file_data = match storage.find_file(&file_name).await {
+        Some(data) => data,
+        None => return ApiFileError::FileNotFound(file_name).to_api_error().to_response(),
+    };
+    if let Some(references) = db.get_media_references().await.remove(&file_name) {
+        if !references.lobbies.is_empty() {
+            let lobby_ids = references.lobbies.into_iter().map(|lobby_id| lobby_id.id).collect();
+            return ApiFileError::FileInLobby(file_name, lobby_ids).to_api_error().to_response();
+        }
+        // a stored revision can still be restored, so it keeps its board from losing the file
+        let mut board_ids: Vec<String> = references.boards.into_iter().chain(references.revisions.into_iter().map(|(board_id, _)| board_id)).map(|board_id| board_id.id).collect();
+        board_ids.sort();
+        board_ids.dedup();
+        return ApiFileError::FileInUse(file_name, board_ids).to_api_error().to_response();
+    }
+
+    let deleted = storage.delete_file(&file_data).await;
+    if deleted {
+        let variants = file_data.metadata.and_then(|data| data.processing).map(|processing| processing.variants).unwrap_or_default();
+        for variant in variants {
+            if let Some(variant_data) = storage.find_file(&variant.file_name).await {
+                storage.delete_file(&variant_data).await;
+            }
+        }
+    }
+    let mut response = HttpResponse::Ok().json(ApiResponse::new(deleted));
+    set_session_token_cookie(&mut response, &settings, &user_session);
+    response
+}
+
+
+
+#[utoipa::path(
+    put,
+    path = "/api/file/rename",
+    params(
+        ("user_session_id" = Option<String>, Query, description = "User session ID"),
+        ("user_session_token" = Option<String>, Query, description = "User session token"),
+    ),
+    request_body(content = DTOFileRename, description = "Current and new name of the file", content_type = "application/json"),
+    responses(
+        // 200
+        (status = 200, description = "Renamed file with the boards and revisions that now use the new name and the ones that could not be updated"),
+
+        // Session
+        (status = 404, description = "No User Session", body = ApiError),
+        (status = 401, description = "Not a admin Session", body = ApiError),
+        (status = 403, description = "No Discord Data", body = ApiError),
+
+        //FILE
+        (status = 400, description = "Invalid name", body = ApiError),
+        (status = 404, description = "File not found", body = ApiError),
+        (status = 409, description = "File Exists", body = ApiError),
+        (status = 409, description = "File is used by a lobby that has not ended, with their ids", body = ApiError),
+        (status = 500, description = "File error", body = ApiError),
+    ),
+    security(
+        ("cookie" = ["user_session_id", "user_session_token"])
+    )
+)]
+#[put("/api/file/rename")]
+async fn rename_file(req: HttpRequest, json: web::Json<DTOFileRename>, db: web::Data<Arc<MongoServer>>, storage: web::Data<Arc<dyn MediaStorage>>, settings: web::Data<Arc<Settings>>) -> HttpResponse {
+    let (user_session, _) = match get_uploader(&req, &db).await {
+        Ok(data) => data,
+        Err(response) => return response,
+    };
+    let rename = json.into_inner();
+    let new_name = rename.new_name.trim().to_string();
+    if new_name.is_empty() || new_name == rename.file_name {
+        return ApiFileError::FileInvalid("New name must differ from the current one".to_string()).to_api_error().to_response();
+    }
+    let file_data = match storage.find_file(&rename.file_name).await {
+        Some(data) => data,
+        None => return ApiFileError::FileNotFound(rename.file_name).to_api_error().to_response(),
+    };
+    if storage.file_exists(&new_name).await {
+        return ApiFileError::FileExists.to_api_error().to_response();
+    }
+    // variants are named after their original, so they move along with it
+    let mut processing = file_data.metadata.as_ref().and_then(|data| data.processing.clone());
+    let mut variant_names = Vec::new();
+    for variant in processing.iter().flat_map(|processing| processing.variants.iter()) {
+        let suffix = variant.file_name.strip_prefix(&rename.file_name).unwrap_or(&variant.file_name);
+        let variant_name = format!("{}{}", new_name, suffix);
+        if storage.file_exists(&variant_name).await {
+            return ApiFileError::FileExists.to_api_error().to_response();
+        }
+        variant_names.push(variant_name);
+    }
+    // a running lobby keeps its boards in memory, so it would go on asking for the old name
+    let references = db.get_media_references().await.remove(&rename.file_name).unwrap_or_default();
+    if !references.lobbies.is_empty() {
+        let lobby_ids = references.lobbies.into_iter().map(|lobby_id| lobby_id.id).collect();
+        return ApiFileError::FileInLobby(rename.file_name, lobby_ids).to_api_error().to_response();
+    }
+    if !storage.rename_file(&file_data, &new_name).await {
+        return ApiFileError::FileError("Can´t rename file".to_string()).to_api_error().to_response();
+    }
+    if let Some(processing) = processing.as_mut() {
+        for (variant, variant_name) in processing.variants.iter_mut().zip(variant_names) {
+            if let Some(variant_data) = storage.find_file(&variant.file_name).await {
+                if storage.rename_file(&variant_data, &variant_name).await {
+                    variant.file_name = variant_name;
+                }
+            }
+        }
+        if !storage.set_processing(&file_data, processing).await {
+            println!("Failed to update the variants of {}", new_name);
+        }
+    }
+
+    let mut boards = Vec::new();
+    let mut failed = Vec::new();
+    for board_id in references.boards {
+        if rename_board_media(&db, &board_id, &rename.file_name, &new_name).await {
+            boards.push(board_id);
+        } else {
+            failed.push(board_id);
+        }
+    }
+    let mut revisions = Vec::new();
+    let mut failed_revisions = Vec::new();
+    for (board_id, revision) in references.revisions {
+        if db.rename_revision_media(&board_id, revision, &rename.file_name, &new_name).await {
+            revisions.push(json!({"board_id": board_id, "revision": revision}));
+        } else {
+            failed_revisions.push(json!({"board_id": board_id, "revision": revision}));
+        }
+    }
+
+    let file_data = match storage.find_file(&new_name).await.and_then(|file_data| file_data.to_dto()) {
+        Some(data) => data,
+        None => return ApiFileError::FileNotFound(new_name).to_api_error().to_response(),
+    };
+    let mut response = HttpResponse::Ok().json(json!({
+        "file": file_data,
+        "boards": boards,
+        "failed": failed,
+        "revisions": revisions,
+        "failed_revisions": failed_revisions,
+    }));
+    set_session_token_cookie(&mut response, &settings, &user_session);
+    response
+}
+
+
+
+#[utoipa::path(
+    put,
+    path = "/api/file/metadata",
+    params(
+        ("user_session_id" = Option<String>, Query, description = "User session ID"),
+        ("user_session_token" = Option<String>, Query, description = "User session token"),
+    ),
+    request_body(content = DTOFileOrganize, description = "Tags and folder of the file", content_type = "application/json"),
+    responses(
+        // 200
+        (status = 200, description = "File with the new tags and folder", body = DTOFileData),
+
+        // Session
+        (status = 404, description = "No User Session", body = ApiError),
+        (status = 401, description = "Not a admin Session", body = ApiError),
+        (status = 403, description = "No Discord Data", body = ApiError),
+
+        //FILE
+        (status = 404, description = "File not found", body = ApiError),
+        (status = 500, description = "File error", body = ApiError),
+    ),
+    security(
+        ("cookie" = ["user_session_id", "user_session_token"])
+    )
+)]
+#[put("/api/file/metadata")]
+async fn organize_file(req: HttpRequest, json: web::Json<DTOFileOrganize>, db: web::Data<Arc<MongoServer>>, storage: web::Data<Arc<dyn MediaStorage>>, settings: web::Data<Arc<Settings>>) -> HttpResponse {
+    let (user_session, _) = match get_uploader(&req, &db).await {
+        Ok(data) => data,
+        Err(response) => return response,
+    };
+    let organize = json.into_inner();
+    let file_data = match storage.find_file(&organize.file_name).await {
+        Some(data) => data,
+        None => return ApiFileError::FileNotFound(organize.file_name).to_api_error().to_response(),
+    };
+
+    let mut tags: Vec<String> = organize.tags.iter().map(|tag| tag.trim().to_string()).filter(|tag| !tag.is_empty()).collect();
+    tags.sort();
+    tags.dedup();
+    let folder = organize.folder.map(|folder| folder.trim().trim_matches('/').to_string()).filter(|folder| !folder.is_empty());
+    if !storage.organize_file(&file_data, tags, folder).await {
+        return ApiFileError::FileError("Can´t update file".to_string()).to_api_error().to_response();
+    }
+
+    let file_data = match storage.find_file(&organize.file_name).await.and_then(|file_data| file_data.to_dto()) {
+        Some(data) => data,
+        None => return ApiFileError::FileNotFound(organize.file_name).to_api_error().to_response(),
+    };
+    let mut response = HttpResponse::Ok().json(file_data);
+    set_session_token_cookie(&mut response, &settings, &user_session);
+    response
+}
+
+
+
+#[utoipa::path(
+    get,
+    path = "/api/file/unused",
+    params(
+        ("user_session_id" = Option<String>, Query, description = "User session ID"),
+        ("user_session_token" = Option<String>, Query, description = "User session token"),
+        ("page_size" = Option<u64>, Header, description = "Files per page"),
+        ("page" = Option<u64>, Header, description = "page"),
+    ),
+    responses(
+        // 200
+        (status = 200, description = "Files no stored board uses, sorted by name"),
+
+        // Session
+        (status = 404, description = "No User Session", body = ApiError),
+        (status = 401, description = "Not a admin Session", body = ApiError),
+        (status = 403, description = "No Discord Data", body = ApiError),
+
+        //FILE
+        (status = 400, description = "Page size cannot be zero", body = ApiError),
+        (status = 500, description = "File error", body = ApiError),
+    ),
+    security(
+        ("cookie" = ["user_session_id", "user_session_token"])
+    )
+)]
+#[get("/api/file/unused")]
+async fn get_unused_files(req: HttpRequest, db: web::Data<Arc<MongoServer>>, storage: web::Data<Arc<dyn MediaStorage>>, settings: web::Data<Arc<Settings>>) -> HttpResponse {
+    let (user_session, _) = match get_uploader(&req, &db).await {
+        Ok(data) => data,
+        Err(response) => return response,
+    };
+    let page = match extract_header_string(&req, "page") {
+        Ok(data) => data.parse::<u64>().unwrap_or(0),
+        Err(_) => 0,
+    };
+    let page_size = match extract_header_string(&req, "page_size") {
+        Ok(data) => data.parse::<u64>().unwrap_or(10),
+        Err(_) => 10,
+    };
+    if page_size == 0 {
+        return ApiFileError::FileInvalid("Page size cannot be zero".to_string()).to_api_error().to_response();
+    }
+
+    let used: Vec<String> = db.get_media_references().await.into_keys().collect();
+    let filter = doc! {"filename": {"$nin": used}, "metadata.variant": null};
+    let file_count = storage.count_files(filter.clone()).await;
+    let files = match storage.list_files(filter, page * page_size, page_size as i64).await {
+        Some(data) => data,
+        None => return ApiFileError::FileError("Error getting files".to_string()).to_api_error().to_response(),
+    };
+    let files: Vec<_> = files.iter().filter_map(|file_data| file_data.to_dto()).collect();
+
+    let mut response = HttpResponse::Ok().json(json!({
+        "files": files,
+        "file_count": file_count,
+        "page": page,
+        "page_size": page_size,
+    }));
+    set_session_token_cookie(&mut response, &settings, &user_session);
+    response
+}
@@ -0,0 +1,160 @@
+use std::sync::Arc;
+
+use actix_web::{get, web, HttpRequest, HttpResponse};
+use cult_common::dto::game::DTOGameResult;
+use serde_json::json;
+
+use crate::data::GameResultData;
+use crate::rest::data::{extract_header_string, get_discord_id_from_header, get_game_id_from_header, get_session, set_session_token_cookie};
+use crate::rest::error::{ApiGameError, ApiSessionError, ToApiError, ToResponse};
+use crate::services::db::MongoServer;
+use crate::settings::Settings;
+
+
+
+
+#[utoipa::path(
+    get,
+    path = "/api/games",
+    params(
+        ("user_session_id" = Option<String>, Query, description = "User session ID"),
+        ("user_session_token" = Option<String>, Query, description = "User session token"),
+        ("discord-id" = Option<String>, Header, description = "Only games this player took part in"),
+        ("page_size" = Option<u64>, Header, description = "Games per page"),
+        ("page" = Option<u64>, Header, description = "page"),
+    ),
+    responses(
+        // 200
+        (status = 200, description = "Finished games, newest first"),
+
+        // Session
+        (status = 404, description = "No User Session", body = ApiError),
+
+        //GAME
+        (status = 400, description = "Page size cannot be zero", body = ApiError),
+    ),
+    security(
+        ("cookie" = ["user_session_id", "user_session_token"])
+    )
+)]
+#[get("/api/games")]
+async fn get_game_list(req: HttpRequest, db: web::Data<Arc<MongoServer>>, settings: web::Data<Arc<Settings>>) -> HttpResponse {
+    let user_session = match get_session(&req, &db).await {
+        Some(data) => data,
+        None => return ApiSessionError::NotFound.to_api_error().to_response(),
+    };
+
+    let page = match extract_header_string(&req, "page") {
+        Ok(data) => data.parse::<u64>().unwrap_or(0),
+        Err(_) => 0,
+    };
+    let page_size = match extract_header_string(&req, "page_size") {
+        Ok(data) => data.parse::<u64>().unwrap_or(10),
+        Err(_) => 10,
+    };
+    if page_size == 0 {
+        return ApiGameError::GameInvalid("Page size cannot be zero".to_string()).to_api_error().to_response();
+    }
+
+    let discord_id = get_discord_id_from_header(&req);
+    let game_count = db.count_game_results(&discord_id).await;
+    let games: Vec<DTOGameResult> = db.get_game_results(&discord_id, page * page_size, Some(page_size as i64)).await
+        .iter()
+        .map(|game| game.to_dto())
+        .collect();
+
+    let mut response = HttpResponse::Ok().json(json!({
+        "games": games,
+        "game_count": game_count,
+        "page": page,
+        "page_size": page_size,
+    }));
+    set_session_token_cookie(&mut response, &settings, &user_session);
+    response
+}
+
+
+
+#[utoipa::path(
+    get,
+    path = "/api/games/game",
+    params(
+        ("user_session_id" = Option<String>, Query, description = "User session ID"),
+        ("user_session_token" = Option<String>, Query, description = "User session token"),
+        ("game-id" = String, Header, description = "Game ID"),
+    ),
+    responses(
+        // 200
+        (status = 200, description = "Results of the game", body = DTOGameResult),
+
+        // Session
+        (status = 404, description = "No User Session", body = ApiError),
+
+        //GAME
+        (status = 400, description = "No game id found", body = ApiError),
+        (status = 404, description = "Game not found", body = ApiError),
+    ),
+    security(
+        ("cookie" = ["user_session_id", "user_session_token"])
+    )
+)]
+#[get("/api/games/game")]
+async fn get_game_result(req: HttpRequest, db: web::Data<Arc<MongoServer>>, settings: web::Data<Arc<Settings>>) -> HttpResponse {
+    let user_session = match get_session(&req, &db).await {
+        Some(data) => data,
+        None => return ApiSessionError::NotFound.to_api_error().to_response(),
+    };
+    let game_id = match get_game_id_from_header(&req) {
+        Some(data) => data,
+        None => return ApiGameError::GameInvalid("No game id found".to_string()).to_api_error().to_response(),
+    };
+    let game = match db.find_game_result(&game_id).await {
+        Some(data) => data,
+        None => return ApiGameError::GameNotFound(game_id.id).to_api_error().to_response(),
+    };
+
+    let mut response = HttpResponse::Ok().json(game.to_dto());
+    set_session_token_cookie(&mut response, &settings, &user_session);
+    response
+}
+
+
+
+#[utoipa::path(
+    get,
+    path = "/api/games/stats",
+    params(
+        ("user_session_id" = Option<String>, Query, description = "User session ID"),
+        ("user_session_token" = Option<String>, Query, description = "User session token"),
+        ("discord-id" = Option<String>, Header, description = "Player to look up, the current user if missing"),
+    ),
+    responses(
+        // 200
+        (status = 200, description = "Lifetime stats of the player", body = DTOPlayerStats),
+
+        // Session
+        (status = 404, description = "No User Session", body = ApiError),
+        (status = 403, description = "No Discord Data", body = ApiError),
+    ),
+    security(
+        ("cookie" = ["user_session_id", "user_session_token"])
+    )
+)]
+#[get("/api/games/stats")]
+async fn get_player_stats(req: HttpRequest, db: web::Data<Arc<MongoServer>>, settings: web::Data<Arc<Settings>>) -> HttpResponse {
+    let user_session = match get_session(&req, &db).await {
+        Some(data) => data,
+        None => return ApiSessionError::NotFound.to_api_error().to_response(),
+    };
+    let discord_id = match get_discord_id_from_header(&req).or(user_session.get_discord_id()) {
+        Some(data) => data,
+        None => return ApiSessionError::NoDiscordData.to_api_error().to_response(),
+    };
+
+    let games = db.get_game_results(&Some(discord_id.clone()), 0, None).await;
+    let stats = GameResultData::player_stats(&games, &discord_id);
+
+    let mut response = HttpResponse::Ok().json(stats);
+    set_session_token_cookie(&mut response, &settings, &user_session);
+    response
+}
@@ -1,4 +1,7 @@
 pub(crate) mod   api;
 pub(crate) mod  error;
 pub(crate) mod  data;
-pub(crate) mod  file;
\ No newline at end of file
+pub(crate) mod  file;
+pub(crate) mod  board;
+pub(crate) mod  game;
+pub(crate) mod  range;
\ No newline at end of file
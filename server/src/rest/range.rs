@@ -0,0 +1,204 @@
+use std::sync::Arc;
+
+use actix_web::HttpRequest;
+use bytes::Bytes;
+use cult_common::wasm_lib::NumberScope;
+use futures::stream::{self, LocalBoxStream};
+use futures::StreamExt;
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+
+use crate::data::FileData;
+use crate::services::storage::MediaStorage;
+
+/// More ranges than this only add multipart overhead, so the full file is sent instead.
+const MAX_BYTE_RANGES: usize = 16;
+
+/// Result of evaluating the `Range` header against a file of a known length.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ByteRanges {
+    Full,
+    Partial(Vec<NumberScope>),
+    Unsatisfiable,
+}
+
+/// Parses `bytes=0-99, 200-, -50` into inclusive ranges, sorted and with overlapping ranges merged.
+/// A malformed header or one with more than `MAX_BYTE_RANGES` ranges is ignored as the RFC allows, so the full file is sent.
+pub fn parse_byte_ranges(header: &str, length: usize) -> ByteRanges {
+    let specs = match header.trim().strip_prefix("bytes=") {
+        Some(data) => data,
+        None => return ByteRanges::Full,
+    };
+    if specs.split(',').nth(MAX_BYTE_RANGES).is_some() {
+        return ByteRanges::Full;
+    }
+
+    let mut ranges = Vec::new();
+    for spec in specs.split(',') {
+        let (start, end) = match spec.trim().split_once('-') {
+            Some(data) => data,
+            None => return ByteRanges::Full,
+        };
+        let range = match (start.trim(), end.trim()) {
+            ("", "") => return ByteRanges::Full,
+            ("", suffix) => match suffix.parse::<usize>() {
+                Ok(0) => continue,
+                Ok(_) if length == 0 => continue,
+                Ok(suffix) => NumberScope::new(length.saturating_sub(suffix), length - 1),
+                Err(_) => return ByteRanges::Full,
+            },
+            (start, end) => {
+                let start = match start.parse::<usize>() {
+                    Ok(data) => data,
+                    Err(_) => return ByteRanges::Full,
+                };
+                let end = match end {
+                    "" => usize::MAX,
+                    end => match end.parse::<usize>() {
+                        Ok(data) if data >= start => data,
+                        _ => return ByteRanges::Full,
+                    },
+                };
+                if start >= length {
+                    continue;
+                }
+                NumberScope::new(start, end.min(length - 1))
+            }
+        };
+        ranges.push(range);
+    }
+    if ranges.is_empty() {
+        return ByteRanges::Unsatisfiable;
+    }
+
+    ranges.sort_by_key(|range| range.start);
+    let mut merged: Vec<NumberScope> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end.saturating_add(1) => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+    ByteRanges::Partial(merged)
+}
+
+pub fn get_byte_ranges_from_header(req: &HttpRequest, length: usize) -> ByteRanges {
+    match req.headers().get("range").and_then(|value| value.to_str().ok()) {
+        Some(header) => parse_byte_ranges(header, length),
+        None => ByteRanges::Full,
+    }
+}
+
+/// Strong ETag of a file, based on the hash it was uploaded with.
+pub fn file_etag(hash: &str) -> String {
+    format!("\"{}\"", hash)
+}
+
+/// Whether an `If-None-Match` or `If-Range` header lists the ETag of the file.
+pub fn etag_matches(req: &HttpRequest, header_name: &str, etag: &str) -> bool {
+    match req.headers().get(header_name).and_then(|value| value.to_str().ok()) {
+        Some(header) => header.split(',').map(|tag| tag.trim().trim_start_matches("W/")).any(|tag| tag == "*" || tag == etag),
+        None => false,
+    }
+}
+
+
+/// Streams the bytes of `range` from the storage as they are read, so memory stays bounded for large files.
+pub fn stream_range(storage: Arc<dyn MediaStorage>, file_data: FileData, range: NumberScope) -> LocalBoxStream<'static, Result<Bytes, actix_web::Error>> {
+    stream::once(async move {
+        match storage.open_range(&file_data, range).await {
+            Some(data) => data.map(|part| part.map_err(actix_web::error::ErrorInternalServerError)).boxed_local(),
+            None => stream::once(async { Err(actix_web::error::ErrorInternalServerError("Can´t read file")) }).boxed_local(),
+        }
+    })
+    .flatten()
+    .boxed_local()
+}
+
+
+/// Body of a `multipart/byteranges` response, the length is known up front so the response is not chunked.
+pub struct MultipartRanges {
+    pub boundary: String,
+    pub length: u64,
+    pub body: LocalBoxStream<'static, Result<Bytes, actix_web::Error>>,
+}
+
+impl MultipartRanges {
+
+    pub fn new(storage: Arc<dyn MediaStorage>, file_data: FileData, ranges: Vec<NumberScope>, content_type: &str) -> Self {
+        let boundary: String = rand::thread_rng().sample_iter(&Alphanumeric).take(32).map(char::from).collect();
+        let mut length = 0;
+        let mut parts = Vec::with_capacity(ranges.len() * 2 + 1);
+        for range in ranges {
+            let header = Bytes::from(format!("\r\n--{}\r\nContent-Type: {}\r\nContent-Range: bytes {}-{}/{}\r\n\r\n", boundary, content_type, range.start, range.end, file_data.length));
+            length += header.len() + range.end - range.start + 1;
+            parts.push(stream::once(async move { Ok(header) }).boxed_local());
+            parts.push(stream_range(storage.clone(), file_data.clone(), range));
+        }
+        let footer = Bytes::from(format!("\r\n--{}--\r\n", boundary));
+        length += footer.len();
+        parts.push(stream::once(async move { Ok(footer) }).boxed_local());
+
+        MultipartRanges {
+            boundary,
+            length: length as u64,
+            body: stream::iter(parts).flatten().boxed_local(),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn partial(ranges: &[(usize, usize)]) -> ByteRanges {
+        ByteRanges::Partial(ranges.iter().map(|(start, end)| NumberScope::new(*start, *end)).collect())
+    }
+
+    #[test]
+    fn parses_closed_open_ended_and_suffix_ranges() {
+        assert_eq!(parse_byte_ranges("bytes=0-99", 1000), partial(&[(0, 99)]));
+        assert_eq!(parse_byte_ranges("bytes=900-", 1000), partial(&[(900, 999)]));
+        assert_eq!(parse_byte_ranges("bytes=-100", 1000), partial(&[(900, 999)]));
+        assert_eq!(parse_byte_ranges(" bytes= 10 - 19 ", 1000), partial(&[(10, 19)]));
+    }
+
+    #[test]
+    fn clamps_ranges_to_the_file() {
+        assert_eq!(parse_byte_ranges("bytes=950-2000", 1000), partial(&[(950, 999)]));
+        assert_eq!(parse_byte_ranges("bytes=-5000", 1000), partial(&[(0, 999)]));
+    }
+
+    #[test]
+    fn sorts_and_merges_overlapping_and_adjacent_ranges() {
+        assert_eq!(parse_byte_ranges("bytes=500-599, 0-99, 50-149, 150-199", 1000), partial(&[(0, 199), (500, 599)]));
+        assert_eq!(parse_byte_ranges("bytes=0-10, -100, 850-", 1000), partial(&[(0, 10), (850, 999)]));
+    }
+
+    #[test]
+    fn reports_ranges_outside_the_file_as_unsatisfiable() {
+        assert_eq!(parse_byte_ranges("bytes=1000-1099", 1000), ByteRanges::Unsatisfiable);
+        assert_eq!(parse_byte_ranges("bytes=-0", 1000), ByteRanges::Unsatisfiable);
+        assert_eq!(parse_byte_ranges("bytes=-10", 0), ByteRanges::Unsatisfiable);
+        assert_eq!(parse_byte_ranges("bytes=2000-, 0-10", 1000), partial(&[(0, 10)]));
+    }
+
+    #[test]
+    fn ignores_malformed_headers() {
+        assert_eq!(parse_byte_ranges("items=0-10", 1000), ByteRanges::Full);
+        assert_eq!(parse_byte_ranges("bytes=10", 1000), ByteRanges::Full);
+        assert_eq!(parse_byte_ranges("bytes=-", 1000), ByteRanges::Full);
+        assert_eq!(parse_byte_ranges("bytes=20-10", 1000), ByteRanges::Full);
+        assert_eq!(parse_byte_ranges("bytes=a-10", 1000), ByteRanges::Full);
+        assert_eq!(parse_byte_ranges("bytes=0-10, x", 1000), ByteRanges::Full);
+    }
+
+    #[test]
+    fn sends_the_full_file_for_too_many_ranges() {
+        let ranges = |count: usize| (0..count).map(|index| format!("{}-{}", index * 10, index * 10 + 1)).collect::<Vec<String>>().join(",");
+        let allowed = parse_byte_ranges(&format!("bytes={}", ranges(MAX_BYTE_RANGES)), 1000);
+        assert!(matches!(allowed, ByteRanges::Partial(ref merged) if merged.len() == MAX_BYTE_RANGES));
+        assert_eq!(parse_byte_ranges(&format!("bytes={}", ranges(MAX_BYTE_RANGES + 1)), 1000), ByteRanges::Full);
+    }
+}
@@ -0,0 +1,296 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::hash::Hasher;
+use std::io::{Cursor, Read, Write};
+
+use cult_common::backend::JeopardyBoard;
+use cult_common::dto::board::{DTOBoardArchiveFile, DTOBoardArchiveManifest};
+use zip::write::SimpleFileOptions;
+use twox_hash::XxHash64;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::services::storage::MediaStorage;
+
+pub const BOARD_ARCHIVE_VERSION: u32 = 1;
+const MANIFEST_PATH: &str = "manifest.json";
+const BOARD_PATH: &str = "board.json";
+/// Most bytes all entries of an archive may inflate to, the same as the request payload limit.
+const MAX_CONTENT_SIZE: u64 = 100 * 1024 * 1024;
+/// Room kept per entry for the zip headers, so an exported archive stays below the payload limit.
+const ENTRY_OVERHEAD: u64 = 1024;
+
+
+#[derive(Debug)]
+pub enum BoardArchiveError {
+    Zip(zip::result::ZipError),
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    UnsupportedVersion(u32),
+    MissingEntry(String),
+    MissingMedia(String),
+    HashMismatch(String),
+    EntryTooLarge(String),
+    FileNotFound(String),
+    TooLarge(u64),
+}
+
+impl Display for BoardArchiveError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BoardArchiveError::Zip(err) => write!(f, "Invalid archive: {}", err),
+            BoardArchiveError::Io(err) => write!(f, "Can´t read archive: {}", err),
+            BoardArchiveError::Json(err) => write!(f, "Invalid archive content: {}", err),
+            BoardArchiveError::UnsupportedVersion(version) => write!(f, "Unsupported archive version {}", version),
+            BoardArchiveError::MissingEntry(path) => write!(f, "Archive entry {} is missing", path),
+            BoardArchiveError::MissingMedia(name) => write!(f, "Media {} is not part of the archive", name),
+            BoardArchiveError::HashMismatch(name) => write!(f, "Media {} does not match its hash", name),
+            BoardArchiveError::EntryTooLarge(path) => write!(f, "Archive entry {} is too large", path),
+            BoardArchiveError::FileNotFound(name) => write!(f, "Media {} can´t be loaded", name),
+            BoardArchiveError::TooLarge(size) => write!(f, "Board media has {} bytes, archives may hold at most {} bytes", size, MAX_CONTENT_SIZE),
+        }
+    }
+}
+
+impl From<zip::result::ZipError> for BoardArchiveError {
+    fn from(err: zip::result::ZipError) -> Self {
+        BoardArchiveError::Zip(err)
+    }
+}
+
+impl From<std::io::Error> for BoardArchiveError {
+    fn from(err: std::io::Error) -> Self {
+        BoardArchiveError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for BoardArchiveError {
+    fn from(err: serde_json::Error) -> Self {
+        BoardArchiveError::Json(err)
+    }
+}
+
+
+/// A board together with the media it uses, as stored in a portable zip archive.
+#[derive(Debug, Clone)]
+pub struct BoardArchive {
+    pub manifest: DTOBoardArchiveManifest,
+    pub board: JeopardyBoard,
+    pub files: HashMap<String, Vec<u8>>,
+}
+
+impl BoardArchive {
+
+    /// Collects the media of the board from the storage. The sizes are checked before anything is read,
+    /// an archive that could not be imported again is rejected with `TooLarge`.
+    pub async fn collect(board: JeopardyBoard, storage: &dyn MediaStorage) -> Result<Self, BoardArchiveError> {
+        let mut names: Vec<String> = board.media_names().into_iter().map(|(_, name)| name).collect();
+        names.sort();
+        names.dedup();
+
+        let mut manifest = DTOBoardArchiveManifest { version: BOARD_ARCHIVE_VERSION, files: Vec::new() };
+        let mut file_datas = Vec::new();
+        let mut size = 0;
+        for (index, name) in names.into_iter().enumerate() {
+            let file_data = match storage.find_file(&name).await {
+                Some(data) => data,
+                None => return Err(BoardArchiveError::FileNotFound(name)),
+            };
+            let metadata = match file_data.metadata.clone() {
+                Some(data) => data,
+                None => return Err(BoardArchiveError::FileNotFound(name)),
+            };
+            size += file_data.length as u64 + ENTRY_OVERHEAD;
+            let path = format!("media/{}", index);
+            manifest.files.push(DTOBoardArchiveFile {
+                name,
+                path: path.clone(),
+                file_type: metadata.file_type,
+                validate_hash: metadata.validate_hash,
+            });
+            file_datas.push((path, file_data));
+        }
+        size += serde_json::to_vec_pretty(&manifest)?.len() as u64 + serde_json::to_vec_pretty(&board)?.len() as u64 + 2 * ENTRY_OVERHEAD;
+        if size > MAX_CONTENT_SIZE {
+            return Err(BoardArchiveError::TooLarge(size));
+        }
+
+        let mut files = HashMap::new();
+        for (path, file_data) in file_datas {
+            let data = match storage.read_file(&file_data).await {
+                Some(data) => data,
+                None => return Err(BoardArchiveError::FileNotFound(file_data.file_name)),
+            };
+            files.insert(path, data);
+        }
+        Ok(BoardArchive { manifest, board, files })
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, BoardArchiveError> {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        let json_options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+        // media is already compressed in most cases, so it is stored as is
+        let media_options = SimpleFileOptions::default().compression_method(CompressionMethod::Stored).large_file(true);
+
+        writer.start_file(MANIFEST_PATH, json_options)?;
+        writer.write_all(&serde_json::to_vec_pretty(&self.manifest)?)?;
+        writer.start_file(BOARD_PATH, json_options)?;
+        writer.write_all(&serde_json::to_vec_pretty(&self.board)?)?;
+        for file in &self.manifest.files {
+            let data = match self.files.get(&file.path) {
+                Some(data) => data,
+                None => return Err(BoardArchiveError::MissingEntry(file.path.clone())),
+            };
+            writer.start_file(file.path.as_str(), media_options)?;
+            writer.write_all(data)?;
+        }
+        Ok(writer.finish()?.into_inner())
+    }
+
+    pub fn from_bytes(data: &[u8]) -> Result<Self, BoardArchiveError> {
+        let mut archive = ZipArchive::new(Cursor::new(data))?;
+        let mut remaining = MAX_CONTENT_SIZE;
+
+        let manifest: DTOBoardArchiveManifest = serde_json::from_slice(&read_entry(&mut archive, MANIFEST_PATH, &mut remaining)?)?;
+        if manifest.version != BOARD_ARCHIVE_VERSION {
+            return Err(BoardArchiveError::UnsupportedVersion(manifest.version));
+        }
+        let board: JeopardyBoard = serde_json::from_slice(&read_entry(&mut archive, BOARD_PATH, &mut remaining)?)?;
+
+        // the manifest hash decides which stored file the media is deduplicated against, so it has to be the real one
+        let mut files = HashMap::new();
+        for file in &manifest.files {
+            let data = read_entry(&mut archive, &file.path, &mut remaining)?;
+            let mut hasher = XxHash64::with_seed(0);
+            hasher.write(&data);
+            if file.validate_hash.get_hash() != hasher.finish().to_string() {
+                return Err(BoardArchiveError::HashMismatch(file.name.clone()));
+            }
+            files.insert(file.path.clone(), data);
+        }
+        for (_, name) in board.media_names() {
+            if !manifest.files.iter().any(|file| file.name == name) {
+                return Err(BoardArchiveError::MissingMedia(name));
+            }
+        }
+        Ok(BoardArchive { manifest, board, files })
+    }
+}
+
+/// Reads an entry within the `remaining` content size. The declared size is only a hint,
+/// so reading stops one byte past the limit to catch entries that inflate to more.
+fn read_entry(archive: &mut ZipArchive<Cursor<&[u8]>>, path: &str, remaining: &mut u64) -> Result<Vec<u8>, BoardArchiveError> {
+    let entry = match archive.by_name(path) {
+        Ok(entry) => entry,
+        Err(zip::result::ZipError::FileNotFound) => return Err(BoardArchiveError::MissingEntry(path.to_string())),
+        Err(err) => return Err(err.into()),
+    };
+    if entry.size() > *remaining {
+        return Err(BoardArchiveError::EntryTooLarge(path.to_string()));
+    }
+    let mut data = Vec::with_capacity(entry.size() as usize);
+    entry.take(*remaining + 1).read_to_end(&mut data)?;
+    if data.len() as u64 > *remaining {
+        return Err(BoardArchiveError::EntryTooLarge(path.to_string()));
+    }
+    *remaining -= data.len() as u64;
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use cult_common::wasm_lib::hashs::validate::ValidateHash;
+    use cult_common::wasm_lib::JeopardyMode;
+
+    use super::*;
+
+    fn hash(data: &[u8]) -> ValidateHash {
+        let mut hasher = XxHash64::with_seed(0);
+        hasher.write(data);
+        ValidateHash::new(hasher.finish().to_string())
+    }
+
+    fn archive() -> BoardArchive {
+        let board = JeopardyBoard::default(JeopardyMode::NORMAL);
+        let mut names: Vec<String> = board.media_names().into_iter().map(|(_, name)| name).collect();
+        names.sort();
+        names.dedup();
+        let mut manifest = DTOBoardArchiveManifest { version: BOARD_ARCHIVE_VERSION, files: Vec::new() };
+        let mut files = HashMap::new();
+        for (index, name) in names.into_iter().enumerate() {
+            let path = format!("media/{}", index);
+            let data = format!("content of {}", name).into_bytes();
+            manifest.files.push(DTOBoardArchiveFile {
+                name,
+                path: path.clone(),
+                file_type: "video/mp4".to_string(),
+                validate_hash: hash(&data),
+            });
+            files.insert(path, data);
+        }
+        BoardArchive { manifest, board, files }
+    }
+
+    #[test]
+    fn round_trip() {
+        let archive = archive();
+        let read = BoardArchive::from_bytes(&archive.to_bytes().unwrap()).unwrap();
+        assert_eq!(read.manifest, archive.manifest);
+        assert_eq!(read.files, archive.files);
+        assert_eq!(read.board.media_names(), archive.board.media_names());
+    }
+
+    #[test]
+    fn hash_mismatch() {
+        let mut archive = archive();
+        let file = archive.manifest.files.first_mut().expect("default board has media");
+        file.validate_hash = hash(b"other content");
+        let name = file.name.clone();
+        match BoardArchive::from_bytes(&archive.to_bytes().unwrap()) {
+            Err(BoardArchiveError::HashMismatch(mismatch)) => assert_eq!(mismatch, name),
+            other => panic!("expected a hash mismatch, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn missing_media() {
+        let mut archive = archive();
+        let file = archive.manifest.files.remove(0);
+        archive.files.remove(&file.path);
+        match BoardArchive::from_bytes(&archive.to_bytes().unwrap()) {
+            Err(BoardArchiveError::MissingMedia(name)) => assert_eq!(name, file.name),
+            other => panic!("expected missing media, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn unsupported_version() {
+        let mut archive = archive();
+        archive.manifest.version = BOARD_ARCHIVE_VERSION + 1;
+        assert!(matches!(BoardArchive::from_bytes(&archive.to_bytes().unwrap()), Err(BoardArchiveError::UnsupportedVersion(_))));
+    }
+
+    #[test]
+    fn oversized_entry() {
+        let archive = archive();
+        let data = archive.to_bytes().unwrap();
+        let mut zip = ZipArchive::new(Cursor::new(data.as_slice())).unwrap();
+        let path = &archive.manifest.files[0].path;
+        let size = archive.files[path].len() as u64;
+
+        let mut remaining = size - 1;
+        assert!(matches!(read_entry(&mut zip, path, &mut remaining), Err(BoardArchiveError::EntryTooLarge(_))));
+        let mut remaining = size;
+        assert_eq!(read_entry(&mut zip, path, &mut remaining).unwrap(), archive.files[path]);
+        assert_eq!(remaining, 0);
+    }
+
+    #[test]
+    fn content_limit_is_shared() {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated).large_file(true);
+        writer.start_file(MANIFEST_PATH, options).unwrap();
+        std::io::copy(&mut std::io::repeat(b' ').take(MAX_CONTENT_SIZE + 1), &mut writer).unwrap();
+        let data = writer.finish().unwrap().into_inner();
+        assert!(matches!(BoardArchive::from_bytes(&data), Err(BoardArchiveError::EntryTooLarge(_))));
+    }
+}
@@ -1,32 +1,45 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use std::time::Duration;
 
 use bson::oid::ObjectId;
+use bson::DateTime;
+use bytes::Bytes;
+use cult_common::backend::JeopardyBoard;
+use cult_common::wasm_lib::ids::board::BoardId;
 use cult_common::wasm_lib::ids::discord::DiscordID;
-use cult_common::wasm_lib::NumberScope;
+use cult_common::wasm_lib::ids::game::GameId;
+use cult_common::wasm_lib::ids::lobby::LobbyId;
+use cult_common::wasm_lib::ids::upload::UploadId;
 use futures::StreamExt;
 use mongodb::bson::doc;
-use mongodb::gridfs::GridFsBucket;
-use mongodb::options::{GridFsBucketOptions, WriteConcern};
-use mongodb::{Client, Collection, IndexModel};
+use mongodb::options::{FindOptions, IndexOptions, ReturnDocument};
+use mongodb::{Client, Collection, Cursor, Database, IndexModel};
 use cult_common::wasm_lib::ids::usersession::UserSessionId;
-use crate::data::{FileChunk, FileData};
+use crate::data::{BoardData, BoardRevisionData, GameResultData, MediaReferences, UploadChunkData, UploadSessionData};
 use crate::services::game::UserSession;
 use crate::settings::Settings;
 
 use super::authentication::Admin;
 use super::game::{DiscordData, SessionToken};
+use super::lobby::LobbySnapshot;
 
+/// Uploads without a new chunk for this long are dropped together with their chunks.
+const UPLOAD_EXPIRE_AFTER: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Lobbies nobody played in for this long are not restored anymore.
+const LOBBY_EXPIRE_AFTER: Duration = Duration::from_secs(7 * 24 * 60 * 60);
 
 #[derive(Clone, Debug)]
 pub struct UserCollection {
     pub user_sessions: Collection<UserSession>,
     pub admins: Collection<Admin>,
-    pub file_bucket: GridFsBucket,
-    pub file_bucket_files: Collection<FileData>,
-    pub file_bucket_chunks: Collection<FileChunk>,
-
+    pub lobbies: Collection<LobbySnapshot>,
+    pub boards: Collection<BoardData>,
+    pub board_revisions: Collection<BoardRevisionData>,
+    pub games: Collection<GameResultData>,
+    pub upload_sessions: Collection<UploadSessionData>,
+    pub upload_chunks: Collection<UploadChunkData>,
 }
 
 
@@ -45,6 +58,10 @@ pub struct MongoServer {
 
 impl MongoServer {
 
+    pub fn database(&self) -> Database {
+        self.mongo_client.database("CultPardy")
+    }
+
     pub async fn new(settings:&Arc<Settings>) -> Self{
         let url = settings.database.get_uri();
         println!("Connecting to MongoDB with URL: {}", url);
@@ -54,17 +71,7 @@ impl MongoServer {
     
 
 
-        
-        let wc = WriteConcern::builder().w_timeout(Duration::new(5, 0)).build();
-        let opts = GridFsBucketOptions::builder()
-            .bucket_name("FileBucket".to_string())
-            .write_concern(wc)
-            .build();
-
-
-
         let db = mongo_client.database("CultPardy");
-        let bucket = db.gridfs_bucket(opts);
 
 
 
@@ -72,9 +79,12 @@ impl MongoServer {
         let collections = UserCollection{
             user_sessions: db.collection("UserSessions"),
             admins: db.collection("Admins"),
-            file_bucket: bucket,
-            file_bucket_files: db.collection("FileBucket.files"),
-            file_bucket_chunks: db.collection("FileBucket.chunks"),
+            lobbies: db.collection("Lobbies"),
+            boards: db.collection("Boards"),
+            board_revisions: db.collection("BoardRevisions"),
+            games: db.collection("Games"),
+            upload_sessions: db.collection("UploadSessions"),
+            upload_chunks: db.collection("UploadChunks"),
         };
 
         collections.user_sessions.create_index(IndexModel::builder().keys(doc! {"user_session_id.id": 1}).build()).await.expect("Failed to create index");
@@ -86,10 +96,17 @@ impl MongoServer {
 
 
         collections.admins.create_index(IndexModel::builder().keys(doc! {"discord_id.id": 1}).build()).await.expect("Failed to create index");
-        //filename
-        collections.file_bucket_files.create_index(IndexModel::builder().keys(doc! {"filename": 1}).build()).await.expect("Failed to create index");
-        //files_id
-        collections.file_bucket_chunks.create_index(IndexModel::builder().keys(doc! {"files_id": 1}).build()).await.expect("Failed to create index");
+        collections.boards.create_index(IndexModel::builder().keys(doc! {"board_id.id": 1}).options(IndexOptions::builder().unique(true).build()).build()).await.expect("Failed to create index");
+        collections.boards.create_index(IndexModel::builder().keys(doc! {"owner.id": 1}).build()).await.expect("Failed to create index");
+        collections.board_revisions.create_index(IndexModel::builder().keys(doc! {"board_id.id": 1, "revision": 1}).options(IndexOptions::builder().unique(true).build()).build()).await.expect("Failed to create index");
+        collections.lobbies.create_index(IndexModel::builder().keys(doc! {"lobby_id.id": 1}).options(IndexOptions::builder().unique(true).build()).build()).await.expect("Failed to create index");
+        collections.lobbies.create_index(IndexModel::builder().keys(doc! {"updateDate": 1}).options(IndexOptions::builder().expire_after(LOBBY_EXPIRE_AFTER).build()).build()).await.expect("Failed to create index");
+        collections.games.create_index(IndexModel::builder().keys(doc! {"game_id.id": 1}).options(IndexOptions::builder().unique(true).build()).build()).await.expect("Failed to create index");
+        collections.games.create_index(IndexModel::builder().keys(doc! {"players.discord_user.discord_id.id": 1}).build()).await.expect("Failed to create index");
+        collections.upload_sessions.create_index(IndexModel::builder().keys(doc! {"upload_id.id": 1}).options(IndexOptions::builder().unique(true).build()).build()).await.expect("Failed to create index");
+        collections.upload_sessions.create_index(IndexModel::builder().keys(doc! {"uploader.id": 1, "file_name": 1}).build()).await.expect("Failed to create index");
+        collections.upload_chunks.create_index(IndexModel::builder().keys(doc! {"upload_id.id": 1, "index": 1}).options(IndexOptions::builder().unique(true).build()).build()).await.expect("Failed to create index");
+        collections.upload_sessions.create_index(IndexModel::builder().keys(doc! {"updateDate": 1}).build()).await.expect("Failed to create index");
 
 
 
@@ -128,34 +145,6 @@ impl MongoServer {
     }
 
 
-    pub async fn get_file_chunks_in_range(&self, id:ObjectId, range:NumberScope) -> Option<Vec<FileChunk>> {
-        let mut chunks = match self.collections.file_bucket_chunks.find(doc!{"files_id": id, "n": { "$gte": range.start as i64, "$lte": range.end as i64}}).await {
-            Ok(mut cursor) => {
-                let mut vec = Vec::new();
-                while let Some(chunk) = cursor.next().await {
-                    match chunk {
-                        Ok(chunk) => vec.push(chunk),
-                        Err(err) => {
-                            println!("Error getting chunks {:?}", err);
-                            return None;
-                        }
-                    }
-                }
-                vec
-            },
-            Err(err) => {
-                println!("Error getting chunks {:?}", err);
-                return None;
-            }
-        };
-    chunks.sort_by(|a, b| a.n.cmp(&b.n));
-    Some(chunks)
-    }
-
-
-
-
-
     pub async fn get_user_session_with_token_check(&self, user_session_id: &UserSessionId, session_token:&SessionToken) -> UserSession {
         let result = self.collections.user_sessions.find_one(doc! {"user_session_id.id": &user_session_id.id, "session_token.token": &session_token.token}).await;
         let optional_session = match result {
@@ -346,6 +335,409 @@ impl MongoServer {
 
 
 
+    pub async fn save_lobby(&self, snapshot: &LobbySnapshot) -> bool {
+        let result = self.collections.lobbies.replace_one(doc! {"lobby_id.id": &snapshot.lobby_id.id}, snapshot).upsert(true).await;
+        match result {
+            Err(err) => {
+                println!("Error saving lobby {:?}", err);
+                false
+            }
+            Ok(_) => true,
+        }
+    }
+
+    pub async fn delete_lobby(&self, lobby_id: &LobbyId) -> bool {
+        match self.collections.lobbies.delete_one(doc! {"lobby_id.id": &lobby_id.id}).await {
+            Err(err) => {
+                println!("Error deleting lobby {:?}", err);
+                false
+            }
+            Ok(_) => true,
+        }
+    }
+
+    /// Ended lobbies are left out, their results are kept in the games collection.
+    pub async fn get_lobbies(&self) -> Vec<LobbySnapshot> {
+        let result = self.collections.lobbies.find(doc! {"game_state": {"$ne": "End"}}).await;
+        match result {
+            Err(err) => {
+                println!("Error loading lobbies {:?}", err);
+                Vec::new()
+            }
+            Ok(mut data) => {
+                let mut lobbies = Vec::new();
+                while let Some(snapshot) = data.next().await {
+                    match snapshot {
+                        Ok(snapshot) => lobbies.push(snapshot),
+                        Err(err) => println!("Skipping broken lobby snapshot {:?}", err),
+                    }
+                }
+                lobbies
+            }
+        }
+    }
+
+
+
+    pub async fn insert_board(&self, board_data: &BoardData) -> bool {
+        match self.collections.boards.insert_one(board_data).await {
+            Err(err) => {
+                println!("Error inserting board {:?}", err);
+                false
+            }
+            Ok(_) => true,
+        }
+    }
+
+    pub async fn find_board(&self, board_id: &BoardId) -> Option<BoardData> {
+        let result = self.collections.boards.find_one(doc! {"board_id.id": &board_id.id}).await;
+        match result {
+            Err(err) => {
+                println!("Error finding board {:?} {:?}", board_id, err);
+                None
+            }
+            Ok(data) => data,
+        }
+    }
+
+    pub async fn find_board_revision(&self, board_id: &BoardId, revision: usize) -> Option<BoardRevisionData> {
+        let result = self.collections.board_revisions.find_one(doc! {"board_id.id": &board_id.id, "revision": revision as i64}).await;
+        match result {
+            Err(err) => {
+                println!("Error finding board revision {:?} {:?}", board_id, err);
+                None
+            }
+            Ok(data) => data,
+        }
+    }
+
+    /// Copies the current version of the board into `BoardRevisions` and stores `board` as the next revision.
+    /// Returns `None` if the board was changed by someone else in the meantime or the old version could not be kept.
+    pub async fn update_board(&self, board_data: &BoardData, board: JeopardyBoard) -> Option<BoardData> {
+        let mut updated = board_data.clone();
+        updated.revision = board_data.revision + 1;
+        updated.update_date = bson::DateTime::now();
+        updated.board = board;
+
+        // stored first so a replaced version is never lost, a copy left by a failed update holds the same board
+        let revision = board_data.to_revision();
+        let filter = doc! {"board_id.id": &board_data.board_id.id, "revision": board_data.revision as i64};
+        if let Err(err) = self.collections.board_revisions.replace_one(filter, &revision).upsert(true).await {
+            println!("Error saving board revision {:?}", err);
+            return None;
+        }
+
+        let result = self.collections.boards.replace_one(doc! {"board_id.id": &board_data.board_id.id, "revision": board_data.revision as i64}, &updated).await;
+        match result {
+            Err(err) => {
+                println!("Error updating board {:?}", err);
+                return None;
+            }
+            Ok(update) => {
+                if update.modified_count == 0 {
+                    return None;
+                }
+            }
+        }
+        Some(updated)
+    }
+
+    pub async fn count_boards(&self, owner: &DiscordID) -> u64 {
+        self.collections.boards.count_documents(doc! {"owner.id": &owner.id}).await.unwrap_or(0)
+    }
+
+    pub async fn get_boards(&self, owner: &DiscordID, skip: u64, limit: i64) -> Vec<BoardData> {
+        let find_options = FindOptions::builder()
+            .sort(doc! { "updateDate": -1 })
+            .skip(skip)
+            .limit(limit)
+            .build();
+        let result = self.collections.boards.find(doc! {"owner.id": &owner.id}).with_options(find_options).await;
+        match result {
+            Err(err) => {
+                println!("Error listing boards {:?}", err);
+                Vec::new()
+            }
+            Ok(mut data) => {
+                let mut boards = Vec::new();
+                while let Some(board) = data.next().await {
+                    match board {
+                        Ok(board) => boards.push(board),
+                        Err(err) => println!("Skipping broken board {:?}", err),
+                    }
+                }
+                boards
+            }
+        }
+    }
+
+    pub async fn get_board_revisions(&self, board_id: &BoardId) -> Vec<BoardRevisionData> {
+        let find_options = FindOptions::builder()
+            .sort(doc! { "revision": -1 })
+            .build();
+        let result = self.collections.board_revisions.find(doc! {"board_id.id": &board_id.id}).with_options(find_options).await;
+        match result {
+            Err(err) => {
+                println!("Error listing board revisions {:?}", err);
+                Vec::new()
+            }
+            Ok(mut data) => {
+                let mut revisions = Vec::new();
+                while let Some(revision) = data.next().await {
+                    match revision {
+                        Ok(revision) => revisions.push(revision),
+                        Err(err) => println!("Skipping broken board revision {:?}", err),
+                    }
+                }
+                revisions
+            }
+        }
+    }
+
+    /// Names of the media used by stored boards, their revisions and lobbies that have not ended,
+    /// together with where they are used.
+    pub async fn get_media_references(&self) -> HashMap<String, MediaReferences> {
+        let mut references: HashMap<String, MediaReferences> = HashMap::new();
+        match self.collections.boards.find(doc! {}).await {
+            Err(err) => println!("Error listing boards {:?}", err),
+            Ok(mut cursor) => {
+                while let Some(board) = cursor.next().await {
+                    match board {
+                        Ok(board_data) => for (_, name) in board_data.board.media_names() {
+                            let boards = &mut references.entry(name).or_default().boards;
+                            if !boards.contains(&board_data.board_id) {
+                                boards.push(board_data.board_id.clone());
+                            }
+                        },
+                        Err(err) => println!("Skipping broken board {:?}", err),
+                    }
+                }
+            }
+        }
+        match self.collections.board_revisions.find(doc! {}).await {
+            Err(err) => println!("Error listing board revisions {:?}", err),
+            Ok(mut cursor) => {
+                while let Some(revision) = cursor.next().await {
+                    match revision {
+                        Ok(revision) => for (_, name) in revision.board.media_names() {
+                            let revisions = &mut references.entry(name).or_default().revisions;
+                            if !revisions.iter().any(|(board_id, number)| *board_id == revision.board_id && *number == revision.revision) {
+                                revisions.push((revision.board_id.clone(), revision.revision));
+                            }
+                        },
+                        Err(err) => println!("Skipping broken board revision {:?}", err),
+                    }
+                }
+            }
+        }
+        for snapshot in self.get_lobbies().await {
+            let boards = std::iter::once(&snapshot.jeopardy_board).chain(snapshot.rounds.iter().map(|round| &round.board));
+            for board in boards {
+                for (_, name) in board.media_names() {
+                    let lobbies = &mut references.entry(name).or_default().lobbies;
+                    if !lobbies.contains(&snapshot.lobby_id) {
+                        lobbies.push(snapshot.lobby_id.clone());
+                    }
+                }
+            }
+        }
+        references
+    }
+
+    /// Points the media of a stored revision to a renamed file.
+    pub async fn rename_revision_media(&self, board_id: &BoardId, revision: usize, from: &str, to: &str) -> bool {
+        let mut revision_data = match self.find_board_revision(board_id, revision).await {
+            Some(data) => data,
+            None => return false,
+        };
+        revision_data.board.rename_media(from, to);
+        match self.collections.board_revisions.replace_one(doc! {"board_id.id": &board_id.id, "revision": revision as i64}, &revision_data).await {
+            Err(err) => {
+                println!("Error updating board revision {:?}", err);
+                false
+            }
+            Ok(update) => update.matched_count > 0,
+        }
+    }
+
+    pub async fn delete_board(&self, board_id: &BoardId) -> bool {
+        let result = self.collections.boards.delete_one(doc! {"board_id.id": &board_id.id}).await;
+        match result {
+            Err(err) => {
+                println!("Error deleting board {:?}", err);
+                false
+            }
+            Ok(delete) => {
+                if delete.deleted_count == 0 {
+                    return false;
+                }
+                if let Err(err) = self.collections.board_revisions.delete_many(doc! {"board_id.id": &board_id.id}).await {
+                    println!("Error deleting board revisions {:?}", err);
+                }
+                true
+            }
+        }
+    }
+
+
+
+    pub async fn insert_upload_session(&self, upload: &UploadSessionData) -> bool {
+        match self.collections.upload_sessions.insert_one(upload).await {
+            Err(err) => {
+                println!("Error inserting upload session {:?}", err);
+                false
+            }
+            Ok(_) => true,
+        }
+    }
+
+    pub async fn find_upload_session(&self, upload_id: &UploadId) -> Option<UploadSessionData> {
+        match self.collections.upload_sessions.find_one(doc! {"upload_id.id": &upload_id.id}).await {
+            Err(err) => {
+                println!("Error finding upload session {:?}", err);
+                None
+            }
+            Ok(data) => data,
+        }
+    }
+
+    pub async fn find_open_upload(&self, uploader: &DiscordID, file_name: &str) -> Option<UploadSessionData> {
+        match self.collections.upload_sessions.find_one(doc! {"uploader.id": &uploader.id, "file_name": file_name}).await {
+            Err(err) => {
+                println!("Error finding upload session {:?}", err);
+                None
+            }
+            Ok(data) => data,
+        }
+    }
+
+    /// Stores a chunk, replacing an earlier copy of it, and returns the upload with the chunk confirmed.
+    pub async fn store_upload_chunk(&self, upload: &UploadSessionData, index: usize, data: Bytes) -> Option<UploadSessionData> {
+        let chunk = UploadChunkData { upload_id: upload.upload_id.clone(), index, data };
+        if let Err(err) = self.collections.upload_chunks.replace_one(doc! {"upload_id.id": &upload.upload_id.id, "index": index as i64}, &chunk).upsert(true).await {
+            println!("Error storing upload chunk {:?}", err);
+            return None;
+        }
+        let update = doc! {"$addToSet": {"received": index as i64}, "$set": {"updateDate": DateTime::now()}};
+        match self.collections.upload_sessions.find_one_and_update(doc! {"upload_id.id": &upload.upload_id.id}, update).return_document(ReturnDocument::After).await {
+            Err(err) => {
+                println!("Error updating upload session {:?}", err);
+                None
+            }
+            Ok(data) => data,
+        }
+    }
+
+    /// Cursor over the received chunks of an upload, in order.
+    pub async fn get_upload_chunk_cursor(&self, upload_id: &UploadId) -> Option<Cursor<UploadChunkData>> {
+        match self.collections.upload_chunks.find(doc! {"upload_id.id": &upload_id.id}).sort(doc! {"index": 1}).await {
+            Ok(cursor) => Some(cursor),
+            Err(err) => {
+                println!("Error getting upload chunks {:?}", err);
+                None
+            }
+        }
+    }
+
+    /// Deletes uploads that got no chunk within `UPLOAD_EXPIRE_AFTER`, together with their chunks.
+    pub async fn delete_expired_uploads(&self) -> usize {
+        let expired = DateTime::from_millis(DateTime::now().timestamp_millis() - UPLOAD_EXPIRE_AFTER.as_millis() as i64);
+        let mut upload_ids = Vec::new();
+        match self.collections.upload_sessions.find(doc! {"updateDate": {"$lt": expired}}).await {
+            Err(err) => println!("Error finding expired uploads {:?}", err),
+            Ok(mut cursor) => {
+                while let Some(upload) = cursor.next().await {
+                    match upload {
+                        Ok(upload) => upload_ids.push(upload.upload_id),
+                        Err(err) => println!("Skipping broken upload session {:?}", err),
+                    }
+                }
+            }
+        }
+        let mut deleted = 0;
+        for upload_id in upload_ids {
+            if self.delete_upload_session(&upload_id).await {
+                deleted += 1;
+            }
+        }
+        deleted
+    }
+
+    pub async fn delete_upload_session(&self, upload_id: &UploadId) -> bool {
+        if let Err(err) = self.collections.upload_chunks.delete_many(doc! {"upload_id.id": &upload_id.id}).await {
+            println!("Error deleting upload chunks {:?}", err);
+        }
+        match self.collections.upload_sessions.delete_one(doc! {"upload_id.id": &upload_id.id}).await {
+            Err(err) => {
+                println!("Error deleting upload session {:?}", err);
+                false
+            }
+            Ok(delete) => delete.deleted_count > 0,
+        }
+    }
+
+
+
+    pub async fn insert_game_result(&self, game_result: &GameResultData) -> bool {
+        match self.collections.games.insert_one(game_result).await {
+            Ok(_) => true,
+            Err(err) => {
+                println!("Error saving game result {:?}", err);
+                false
+            }
+        }
+    }
+
+    pub async fn find_game_result(&self, game_id: &GameId) -> Option<GameResultData> {
+        let result = self.collections.games.find_one(doc! {"game_id.id": &game_id.id}).await;
+        match result {
+            Ok(data) => data,
+            Err(err) => {
+                println!("Error loading game result {:?}", err);
+                None
+            }
+        }
+    }
+
+    fn game_result_filter(discord_id: &Option<DiscordID>) -> bson::Document {
+        match discord_id {
+            None => doc! {},
+            Some(discord_id) => doc! {"players.discord_user.discord_id.id": &discord_id.id},
+        }
+    }
+
+    pub async fn count_game_results(&self, discord_id: &Option<DiscordID>) -> u64 {
+        self.collections.games.count_documents(Self::game_result_filter(discord_id)).await.unwrap_or(0)
+    }
+
+    /// Newest games first, only games the player took part in if a `DiscordID` is given.
+    pub async fn get_game_results(&self, discord_id: &Option<DiscordID>, skip: u64, limit: Option<i64>) -> Vec<GameResultData> {
+        let find_options = FindOptions::builder()
+            .sort(doc! { "endDate": -1 })
+            .skip(skip)
+            .limit(limit)
+            .build();
+        let result = self.collections.games.find(Self::game_result_filter(discord_id)).with_options(find_options).await;
+        match result {
+            Err(err) => {
+                println!("Error listing game results {:?}", err);
+                Vec::new()
+            }
+            Ok(mut data) => {
+                let mut games = Vec::new();
+                while let Some(game) = data.next().await {
+                    match game {
+                        Ok(game) => games.push(game),
+                        Err(err) => println!("Skipping broken game result {:?}", err),
+                    }
+                }
+                games
+            }
+        }
+    }
+
+
+
     pub async fn has_user_session(&self, user_session_id: &UserSessionId) -> bool {
          match self.find_user_session(&user_session_id).await{
             None => false,
@@ -1,18 +1,21 @@
 use std::collections::{HashMap, HashSet};
 
 use std::sync::Arc;
-use actix::{Actor, ActorFutureExt, Addr, Context, Handler, Message, MessageResult, ResponseActFuture, WrapFuture};
+use actix::{Actor, ActorFutureExt, Addr, AsyncContext, Context, Handler, Message, MessageResult, ResponseActFuture, WrapFuture};
 use chrono::{DateTime, Duration, Local, Utc};
 
-use cult_common::backend::{JeopardyBoard, LobbyCreateResponse};
-use cult_common::dto::board::DTOSession;
-use cult_common::dto::file::DTOFileMetadata;
+use cult_common::backend::{JeopardyBoard, JeopardyRound, LobbyCreateRequest, LobbyCreateResponse};
+use cult_common::dto::board::{BoardProblem, DTOBoardProblem, DTOSession};
+use cult_common::dto::file::{DTOFileMetadata, MediaProcessing, VariantKind};
 use cult_common::wasm_lib::hashs::validate::ValidateHash;
+use cult_common::wasm_lib::ids::board::BoardId;
 use cult_common::wasm_lib::ids::discord::DiscordID;
 use cult_common::wasm_lib::ids::lobby::LobbyId;
+use cult_common::wasm_lib::ids::team::TeamId;
 use cult_common::wasm_lib::ids::usersession::UserSessionId;
 use cult_common::wasm_lib::ids::websocketsession::WebsocketSessionId;
 use cult_common::wasm_lib::{DiscordUser, JeopardyMode, MediaToken};
+use mongodb::bson::oid::ObjectId;
 use mongodb::bson::{doc, Bson, Document};
 use oauth2::basic::{BasicClient, BasicTokenResponse};
 use oauth2::reqwest::async_http_client;
@@ -26,9 +29,12 @@ use crate::authentication::discord::DiscordME;
 use crate::services::authentication::RedeemAdminAccessToken;
 use crate::services::StartingServices;
 use crate::services::db::MongoServer;
+use crate::services::storage::MediaStorage;
 use serde::{Deserializer, Serializer};
-use super::lobby::{GetMediaToken, Lobby};
+use super::lobby::{GetMediaToken, Lobby, LobbySnapshot};
 
+/// How often abandoned chunked uploads are looked for.
+const UPLOAD_CLEANUP_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60 * 60);
 
 
 #[derive(Debug,Clone)]
@@ -64,7 +70,8 @@ pub struct LobbyExists {
 pub struct CreateLobby {
     pub user_session_id: UserSessionId,
     pub discord_id: DiscordID,
-    pub jeopardy_board:Option<JeopardyBoard>
+    pub request:Option<LobbyCreateRequest>,
+    pub board_id: Option<BoardId>,
 }
 
 
@@ -218,15 +225,44 @@ pub struct FileMetadata {
     pub file_type: String,
     pub validate_hash: ValidateHash,
     pub uploader: DiscordID,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub folder: Option<String>,
+    /// Set on files generated from another file, those are not listed on their own.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub variant: Option<VariantKind>,
+    /// Id of the file a variant was generated from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<ObjectId>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub processing: Option<MediaProcessing>,
 }
 
 impl FileMetadata {
 
+    pub fn new(file_type: String, validate_hash: ValidateHash, uploader: DiscordID) -> Self {
+        FileMetadata {
+            file_type,
+            validate_hash,
+            uploader,
+            tags: Vec::new(),
+            folder: None,
+            variant: None,
+            source: None,
+            processing: None,
+        }
+    }
+
     pub fn to_dto(&self) -> DTOFileMetadata {
         DTOFileMetadata {
             file_type: self.file_type.clone(),
             validate_hash: self.validate_hash.clone(),
             uploader: self.uploader.clone(),
+            tags: self.tags.clone(),
+            folder: self.folder.clone(),
+            variant: self.variant,
+            processing: self.processing.clone(),
         }
     }
     
@@ -303,7 +339,7 @@ impl DiscordData {
 
 
 impl UserSession {
-    pub fn dto(self, score:&i32, is_admin:bool) -> DTOSession {
+    pub fn dto(self, score:&i32, team_id: Option<TeamId>, is_admin:bool) -> DTOSession {
         let clone = self.clone();
         let discord_user = match clone.discord_auth {
             None => None,
@@ -317,6 +353,7 @@ impl UserSession {
             score:score.clone(),
             discord_user,
             is_admin,
+            team_id,
         }
     }
 
@@ -368,20 +405,29 @@ pub struct LobbyData {
 
 
 impl GameServer {
-    pub fn new(starting_services: Arc<StartingServices>) -> GameServer {
+    pub fn new(starting_services: Arc<StartingServices>, lobby_snapshots: Vec<LobbySnapshot>) -> GameServer {
         let name =  LobbyId::from_str("main");
+        let mut lobbies = HashMap::new();
 
-        let lobby = Lobby::new(&starting_services,&name, &UserSessionId::server(), &JeopardyBoard::default(JeopardyMode::NORMAL));
-        let addr = lobby.clone().start();
-
-        let lobby_data = LobbyData {
-            lobby_id: name.clone(),
-            addr,
-        };
+        for snapshot in lobby_snapshots {
+            let lobby_id = snapshot.lobby_id.clone();
+            let addr = Lobby::from_snapshot(&starting_services, snapshot).start();
+            lobbies.insert(lobby_id.clone(), LobbyData {
+                lobby_id,
+                addr,
+            });
+        }
 
+        if !lobbies.contains_key(&name) {
+            let lobby = Lobby::new(&starting_services,&name, &UserSessionId::server(), &[JeopardyRound::new("Jeopardy", JeopardyBoard::default(JeopardyMode::NORMAL))]);
+            let addr = lobby.clone().start();
 
-        let mut lobbies = HashMap::new();
-        lobbies.insert(name.clone(), lobby_data);
+            let lobby_data = LobbyData {
+                lobby_id: name.clone(),
+                addr,
+            };
+            lobbies.insert(name.clone(), lobby_data);
+        }
 
         println!("Game lobby's: {:?}", &lobbies.values().map(|lobby| lobby.lobby_id.clone()).collect::<Vec<_>>());
         GameServer {
@@ -410,12 +456,12 @@ impl GameServer {
 
 
 
-    fn new_lobby(&mut self, user_session_id: &UserSessionId,jeopardy_board: &JeopardyBoard) -> LobbyData {
+    fn new_lobby(&mut self, user_session_id: &UserSessionId,rounds: &[JeopardyRound]) -> LobbyData {
         let mut lobby_id: LobbyId= LobbyId::random();
         while self.lobbies.contains_key(&lobby_id) {
             lobby_id = LobbyId::random();
         }
-        let lobby = Lobby::new(&self.starting_services,&lobby_id.clone(), user_session_id, &jeopardy_board);
+        let lobby = Lobby::new(&self.starting_services,&lobby_id.clone(), user_session_id, rounds);
         let addr  = lobby.start();
 
         let lobby_data = LobbyData {
@@ -434,6 +480,17 @@ impl GameServer {
 impl Actor for GameServer {
     type Context = Context<Self>;
 
+    fn started(&mut self, ctx: &mut Self::Context) {
+        ctx.run_interval(UPLOAD_CLEANUP_INTERVAL, |server, ctx| {
+            let db = server.starting_services.mongo_server.clone();
+            ctx.spawn(async move {
+                let deleted = db.delete_expired_uploads().await;
+                if deleted > 0 {
+                    println!("Deleted {} expired uploads", deleted);
+                }
+            }.into_actor(server));
+        });
+    }
 
     fn start(self) -> Addr<Self> where Self: Actor<Context = Context<Self>> {
         Context::new().run(self)
@@ -486,21 +543,54 @@ impl Handler<LobbyExists> for GameServer {
 
 
 impl Handler<CreateLobby> for GameServer {
-    type Result = MessageResult<CreateLobby>;
+    type Result = ResponseActFuture<Self, LobbyCreateResponse>;
 
     fn handle(&mut self, msg: CreateLobby, _ctx: &mut Self::Context) -> Self::Result {
-        let board = match msg.jeopardy_board {
-            None => return MessageResult(LobbyCreateResponse::Error("No JeopardyBoard".to_string())),
-            Some(board) => board,
-        };
-        if board.categories.len() <1 {
-            return MessageResult(LobbyCreateResponse::Error("No categories".to_string()))
-        }
+        let db = self.starting_services.mongo_server.clone();
+        let storage = self.starting_services.media_storage.clone();
+        let user_session_id = msg.user_session_id.clone();
+        Box::pin(async move {
+            let rounds = match msg.board_id {
+                None => msg.request.map(LobbyCreateRequest::rounds).ok_or("No JeopardyBoard".to_string())?,
+                Some(board_id) => match db.find_board(&board_id).await {
+                    None => return Err(format!("No JeopardyBoard with id {}", board_id)),
+                    Some(board_data) if !board_data.owner.eq(&msg.discord_id) => return Err(format!("Not the owner of JeopardyBoard {}", board_id)),
+                    Some(board_data) => LobbyCreateRequest::Board(Box::new(board_data.board)).rounds(),
+                },
+            };
+            let problems = validate_rounds(&rounds, storage.as_ref()).await;
+            Ok((rounds, problems))
+        }.into_actor(self).map(move |result, game_server, _| {
+            let rounds = match result {
+                Err(error) => return LobbyCreateResponse::Error(error),
+                Ok((_, problems)) if !problems.is_empty() => return LobbyCreateResponse::Invalid(problems),
+                Ok((rounds, _)) => rounds,
+            };
+
+            let board = game_server.new_lobby(&user_session_id,&rounds);
+            LobbyCreateResponse::Created(board.lobby_id.clone())
+        }))
+    }
+}
 
 
-        let board = self.new_lobby(&msg.user_session_id,&board);
-        MessageResult(LobbyCreateResponse::Created(board.lobby_id.clone()))
+/// Checks every round and looks up the media files of the questions in the media storage.
+pub async fn validate_rounds(rounds: &[JeopardyRound], storage: &dyn MediaStorage) -> Vec<DTOBoardProblem> {
+    if rounds.is_empty() {
+        return vec![DTOBoardProblem { round: None, category: None, question: None, problem: BoardProblem::NoRounds }];
+    }
+    let mut problems = Vec::new();
+    for (index, round) in rounds.iter().enumerate() {
+        problems.extend(round.validate(index));
+        for (vector2d, name) in round.board.media_names() {
+            if !storage.file_exists(&name).await {
+                let mut problem = DTOBoardProblem::question(vector2d, BoardProblem::MissingMedia(name));
+                problem.round = Some(index);
+                problems.push(problem);
+            }
+        }
     }
+    problems
 }
 
 
@@ -1,6 +1,7 @@
 
 
 use std::process;
+use std::sync::Arc;
 use std::str::{FromStr};
 use actix::{Actor, Addr};
 use tokio::{io};
@@ -8,6 +9,9 @@ use tokio::io::{AsyncBufReadExt};
 use anyhow::{Result};
 use strum::{Display, EnumIter, IntoEnumIterator};
 use crate::services::authentication::{AuthenticationServer, NewAdminAccessToken};
+use crate::services::db::MongoServer;
+use crate::services::storage::{create_storage, migrate_storage};
+use crate::settings::{Settings, StorageBackend};
 use crate::services::input::Commands::HELP;
 
 #[derive(Debug, PartialEq, EnumIter, Display)]
@@ -15,6 +19,7 @@ enum Commands{
     STOP,
     ADMINCODE,
     PERMIT(String),
+    MIGRATE(StorageBackend, StorageBackend),
     HELP,
 }
 
@@ -26,6 +31,7 @@ impl Commands {
             Commands::STOP => "Stopping the server",
             Commands::ADMINCODE => "Generate a Admin Access Code",
             Commands::PERMIT(_) => "Give the the input Discord id admin right",
+            Commands::MIGRATE(_, _) => "Copy the media files from one storage to another: /migrate <gridfs|local> <gridfs|local>",
             Commands::HELP => "Show the helping information",
         }
 
@@ -34,7 +40,8 @@ impl Commands {
 
 
 
-    async fn run(self, game_server: &Addr<AuthenticationServer>) {
+    async fn run(self, input_server: &InputServer) {
+        let game_server = &input_server.game_server;
         match self {
             Commands::STOP =>  {
                 process::exit(0);
@@ -55,6 +62,21 @@ impl Commands {
             Commands::PERMIT(discord_id) => {
                 println!("ID:{}",discord_id);
             },
+            Commands::MIGRATE(from, to) => {
+                if from == to {
+                    println!("Source and target storage are the same");
+                    return;
+                }
+                let storage_settings = &input_server.settings.media_storage;
+                let from_storage = create_storage(&from, storage_settings, &input_server.mongo_server).await;
+                let to_storage = create_storage(&to, storage_settings, &input_server.mongo_server).await;
+                println!("Copying media from {:?} to {:?}", from, to);
+                let report = migrate_storage(from_storage.as_ref(), to_storage.as_ref()).await;
+                println!("Copied {} files, skipped {} already present", report.copied, report.skipped);
+                for file_name in report.failed {
+                    println!("Failed to copy {}", file_name);
+                }
+            },
             Commands::HELP => {
                 for command in Commands::iter() {
                     println!("/{} : {}",command.to_string(), command.to_help())
@@ -90,6 +112,11 @@ impl FromStr for Commands {
                 };
                 Ok(Commands::PERMIT(second_word.into()))
             },
+            "/MIGRATE" => {
+                let from = parts.next().ok_or(())?.parse::<StorageBackend>()?;
+                let to = parts.next().ok_or(())?.parse::<StorageBackend>()?;
+                Ok(Commands::MIGRATE(from, to))
+            },
             "/HELP" => Ok(HELP),
             _input => return Err(()),
         }
@@ -98,13 +125,17 @@ impl FromStr for Commands {
 
 #[derive(Debug)]
 pub struct InputServer {
-    game_server: Addr<AuthenticationServer>
+    game_server: Addr<AuthenticationServer>,
+    mongo_server: Arc<MongoServer>,
+    settings: Arc<Settings>,
 }
 
 impl InputServer {
-    pub fn init(game_server: Addr<AuthenticationServer>) -> Self {
+    pub fn init(game_server: Addr<AuthenticationServer>, mongo_server: Arc<MongoServer>, settings: Arc<Settings>) -> Self {
         InputServer {
             game_server,
+            mongo_server,
+            settings,
         }
     }
     //FIXME BUG NOT PRINT! WIlL ONLY BE SEE AFTER TYPING
@@ -123,7 +154,7 @@ impl InputServer {
             let command = Commands::from_str(input.as_str());
             match command {
                 Ok(command) => {
-                    command.run(&self.game_server).await;
+                    command.run(&self).await;
                 }
                 Err(_) => {
                     println!("No command found for: {}", input);
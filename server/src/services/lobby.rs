@@ -1,18 +1,21 @@
 
 use std::collections::{HashMap, HashSet};
 use std::hash::Hash;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use actix::{Actor, ActorFutureExt, Addr, AsyncContext, Context, Handler, Message, MessageResult, Recipient, ResponseActFuture, WrapFuture};
 
 use chrono::{DateTime, Local, Utc};
-use cult_common::backend::{ActionState, BuzzerState, JeopardyBoard, MediaState, Question};
-use cult_common::dto::board::DTOSession;
+use cult_common::backend::{ActionState, BoardProgress, BuzzerMode, BuzzerSettings, BuzzerState, CountdownKind, DailyDoubleState, FinalJeopardyStage, FinalJeopardyState, GameState, JeopardyBoard, JeopardyRound, LobbyTimers, Team, EstimationState, MediaState, MultipleChoiceState, Question, WrittenAnswerState};
+use cult_common::dto::board::{DTOSession, DtoCountdown, DtoJeopardyRound, DtoTeam};
+use cult_common::dto::game::{DTOBuzz, DTOQuestionResult};
+use cult_common::wasm_lib::ids::game::GameId;
 use cult_common::wasm_lib::ids::discord::DiscordID;
 use cult_common::wasm_lib::ids::lobby::LobbyId;
+use cult_common::wasm_lib::ids::team::TeamId;
 use cult_common::wasm_lib::ids::usersession::UserSessionId;
 use cult_common::wasm_lib::ids::websocketsession::WebsocketSessionId;
-use cult_common::wasm_lib::websocket_events::{ActionMediaEvent, ActionStateEvent, BoardEvent, MediaStatus, SessionEvent, VideoEvent, WebsocketEvent, WebsocketPing, WebsocketServerEvents};
+use cult_common::wasm_lib::websocket_events::{ActionMediaEvent, ActionStateEvent, BoardEvent, DailyDoubleEvent, FinalJeopardyEvent, GameEvent, GameStateEvent, MediaStatus, TeamEvent, SessionEvent, VideoEvent, WebsocketError, WebsocketEvent, WebsocketPing, WebsocketServerEvents, WrittenAnswerEvent, MultipleChoiceEvent, EstimationEvent, ImageRevealEvent};
 use cult_common::wasm_lib::{MediaToken, MediaType, QuestionType, Vector2D};
 use itertools::Itertools;
 use mongodb::bson::doc;
@@ -20,11 +23,14 @@ use ritelinked::{LinkedHashMap, LinkedHashSet};
 use serde::{Deserialize, Serialize};
 use strum::{Display, EnumIter};
 use crate::services::StartingServices;
+use crate::data::GameResultData;
 use crate::services::db::MongoServer;
 use crate::ws::session::{SendSessionMessageType, UserData};
 use super::authentication::Admin;
 use super::game::UserSession;
 
+const GAME_START_DELAY: Duration = Duration::from_secs(3);
+
 /// Send message to specific room
 #[derive(Message)]
 #[rtype(result = "()")]
@@ -48,13 +54,15 @@ pub struct WebsocketSession {
     pub addr:Recipient<SendSessionMessageType>,
     pings: Vec<i64>,
     pub last_ping: i64,
+    pub spectator: bool,
+    clock_offset: Option<i64>,
 }
 
 
 
 impl WebsocketSession {
 
-    pub fn new(websocket_session_id: WebsocketSessionId, user_session_id: UserSessionId, addr: Recipient<SendSessionMessageType>, ping: i64) -> Self {
+    pub fn new(websocket_session_id: WebsocketSessionId, user_session_id: UserSessionId, addr: Recipient<SendSessionMessageType>, ping: i64, spectator: bool) -> Self {
         let mut pings = Vec::new();
         pings.push(ping);
         WebsocketSession {
@@ -63,9 +71,20 @@ impl WebsocketSession {
             addr,
             pings,
             last_ping : ping,
+            spectator,
+            clock_offset: None,
         }
     }
 
+    /// Offset between the client and the server clock, measured with a `SyncForwardRequest`.
+    pub fn update_clock_offset(&mut self, server_time: f64, client_time: f64) {
+        self.clock_offset = Some((server_time - client_time) as i64 - self.get_ping() / 2);
+    }
+
+    pub fn to_server_time(&self, client_time: f64) -> Option<i64> {
+        self.clock_offset.map(|offset| client_time as i64 + offset)
+    }
+
 
     pub fn update_pings(&mut self, ping: i64) {
         if self.pings.len() > 5 {
@@ -94,10 +113,12 @@ impl WebsocketSession {
 }
 
 
-#[derive(Debug, Clone, Serialize, Hash, Eq, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, Hash, Eq, PartialEq)]
 pub struct UserSessionData {
     pub user_session_id: UserSessionId,
     pub score: i32,
+    #[serde(default)]
+    pub team_id: Option<TeamId>,
 }
 
 impl UserSessionData {
@@ -105,13 +126,47 @@ impl UserSessionData {
         UserSessionData {
             score: 0,
             user_session_id: user_session_id.clone(),
+            team_id: None,
         }
     }
     
 }
 
 
-//TODO ADD CUSTOM Serialize / Deserialize
+/// Stored copy of a `Lobby`, written to the `Lobbies` collection and loaded again on startup.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LobbySnapshot {
+    pub lobby_id: LobbyId,
+    pub creator: UserSessionId,
+    pub user_data: Vec<UserSessionData>,
+    pub allowed_user_session: Vec<UserSessionId>,
+    pub game_state: GameState,
+    pub jeopardy_board: JeopardyBoard,
+    pub board_progress: BoardProgress,
+    pub action_state: ActionState,
+    #[serde(default)]
+    pub board_control: Option<UserSessionId>,
+    #[serde(default)]
+    pub rounds: Vec<JeopardyRound>,
+    #[serde(default)]
+    pub round_index: usize,
+    #[serde(default)]
+    pub question_results: Vec<DTOQuestionResult>,
+    #[serde(default)]
+    pub game_start: Option<DateTime<Local>>,
+    #[serde(default = "Lobby::default_allow_spectators")]
+    pub allow_spectators: bool,
+    #[serde(default)]
+    pub teams: Vec<Team>,
+    #[serde(default)]
+    pub timers: LobbyTimers,
+    #[serde(default)]
+    pub buzzer_settings: BuzzerSettings,
+    #[serde(rename = "updateDate", default = "bson::DateTime::now")]
+    pub update_date: bson::DateTime,
+}
+
+
 #[derive(Debug, Clone)]
 pub struct Lobby {
     pub starting_services: Arc<StartingServices>,
@@ -123,6 +178,20 @@ pub struct Lobby {
     pub websocket_connections: HashMap<WebsocketSessionId,WebsocketSession>,
     pub game_state: GameState,
     pub jeopardy_board: JeopardyBoard,
+    pub board_control: Option<UserSessionId>,
+    pub rounds: Vec<JeopardyRound>,
+    pub round_index: usize,
+    pub question_results: Vec<DTOQuestionResult>,
+    pub current_buzzes: Vec<DTOBuzz>,
+    pub game_start: Option<DateTime<Local>>,
+    pub spectators: LinkedHashSet<UserSessionId>,
+    pub allow_spectators: bool,
+    pub teams: Vec<Team>,
+    pub timers: LobbyTimers,
+    pub buzzer_settings: BuzzerSettings,
+    pub buzz_lockouts: HashMap<UserSessionId, DateTime<Local>>,
+    pub countdown: Option<DtoCountdown>,
+    pub countdown_generation: u64,
 }
 
 
@@ -136,6 +205,7 @@ impl Actor for Lobby {
 
     fn started(&mut self, ctx: &mut Self::Context) {
         println!("Lobby started");
+        ctx.address().do_send(SaveLobby);
         //self.send_pings(ctx);
     }
 
@@ -145,7 +215,7 @@ impl Actor for Lobby {
 
 impl Lobby {
 
-    pub fn new(starting_services:&Arc<StartingServices>, lobby_id: &LobbyId, creator: &UserSessionId, jeopardy_board: &JeopardyBoard) -> Self {
+    pub fn new(starting_services:&Arc<StartingServices>, lobby_id: &LobbyId, creator: &UserSessionId, rounds: &[JeopardyRound]) -> Self {
         let mut allowed_user_session: LinkedHashSet<UserSessionId> = LinkedHashSet::new();
         allowed_user_session.insert(creator.clone());
         let game_state = GameState::Waiting;
@@ -158,7 +228,91 @@ impl Lobby {
             allowed_user_session,
             websocket_connections:HashMap::new(),
             game_state,
-            jeopardy_board:jeopardy_board.clone(),
+            jeopardy_board: rounds[0].start_board(),
+            board_control: None,
+            rounds: rounds.to_vec(),
+            round_index: 0,
+            question_results: Vec::new(),
+            current_buzzes: Vec::new(),
+            game_start: None,
+            spectators: LinkedHashSet::new(),
+            allow_spectators: Self::default_allow_spectators(),
+            teams: Vec::new(),
+            timers: LobbyTimers::default(),
+            buzzer_settings: BuzzerSettings::default(),
+            buzz_lockouts: HashMap::new(),
+            countdown: None,
+            countdown_generation: 0,
+        }
+    }
+
+    pub fn from_snapshot(starting_services:&Arc<StartingServices>, snapshot: LobbySnapshot) -> Self {
+        let mut jeopardy_board = snapshot.jeopardy_board;
+        jeopardy_board.apply_progress(&snapshot.board_progress);
+        if jeopardy_board.current.is_some() {
+            jeopardy_board.action_state = Arc::new(Mutex::new(snapshot.action_state));
+        }
+        let user_data = snapshot.user_data.into_iter().map(|data| (data.user_session_id.clone(), data)).collect();
+        let mut allowed_user_session: LinkedHashSet<UserSessionId> = snapshot.allowed_user_session.into_iter().collect();
+        allowed_user_session.insert(snapshot.creator.clone());
+        // the start delay timer is gone after a restart, so a starting lobby would never begin
+        let game_state = match snapshot.game_state {
+            GameState::Starting => GameState::Playing,
+            game_state => game_state,
+        };
+        println!("Restored lobby={:?} in state {}", snapshot.lobby_id.id, game_state);
+        Lobby {
+            starting_services: starting_services.clone(),
+            lobby_id: snapshot.lobby_id,
+            creator: snapshot.creator,
+            user_data,
+            connected_user_session: LinkedHashSet::new(),
+            allowed_user_session,
+            websocket_connections: HashMap::new(),
+            game_state,
+            jeopardy_board,
+            board_control: snapshot.board_control,
+            rounds: snapshot.rounds,
+            round_index: snapshot.round_index,
+            question_results: snapshot.question_results,
+            current_buzzes: Vec::new(),
+            game_start: snapshot.game_start,
+            spectators: LinkedHashSet::new(),
+            allow_spectators: snapshot.allow_spectators,
+            teams: snapshot.teams,
+            timers: snapshot.timers,
+            buzzer_settings: snapshot.buzzer_settings,
+            buzz_lockouts: HashMap::new(),
+            countdown: None,
+            countdown_generation: 0,
+        }
+    }
+
+    fn default_allow_spectators() -> bool {
+        true
+    }
+
+    pub fn snapshot(&self) -> LobbySnapshot {
+        let action_state = self.jeopardy_board.action_state.lock().expect("Failed to lock action state").clone();
+        LobbySnapshot {
+            lobby_id: self.lobby_id.clone(),
+            creator: self.creator.clone(),
+            user_data: self.user_data.values().cloned().collect(),
+            allowed_user_session: self.allowed_user_session.iter().cloned().collect(),
+            game_state: self.game_state.clone(),
+            jeopardy_board: self.jeopardy_board.clone(),
+            board_progress: self.jeopardy_board.progress(),
+            action_state,
+            board_control: self.board_control.clone(),
+            rounds: self.rounds.clone(),
+            round_index: self.round_index,
+            question_results: self.question_results.clone(),
+            game_start: self.game_start,
+            allow_spectators: self.allow_spectators,
+            teams: self.teams.clone(),
+            timers: self.timers.clone(),
+            buzzer_settings: self.buzzer_settings.clone(),
+            update_date: bson::DateTime::now(),
         }
     }
 
@@ -205,16 +359,28 @@ impl Lobby {
         0
     }
 
-    pub fn update_session_score(&mut self, user_session_id: &UserSessionId, score: i32) {
-        if let Some(_) = self.connected_user_session.get(user_session_id) {
-            if let Some(user_data) = self.user_data.get_mut(user_session_id) {
-                user_data.score += score;
-                }
-            }
+    pub fn add_session_score(&mut self, user_session_id: &UserSessionId, score: i32) -> Option<i32> {
+        let user_data = self.user_data.get_mut(user_session_id)?;
+        user_data.score += score;
+        Some(user_data.score)
     }
 
     pub fn update_game_state(&mut self, game_state: GameState) {
         self.game_state = game_state;
+        let event = WebsocketServerEvents::Game(GameEvent::StateChanged(self.game_state.clone()));
+        self.send_lobby_message(&event);
+    }
+
+    /// Board actions are only accepted while the game is running, the sender gets an error otherwise.
+    pub fn accepts_board_action(&self, websocket_session_id: &Option<WebsocketSessionId>) -> bool {
+        if self.game_state.accepts_board_actions() {
+            return true;
+        }
+        if let Some(websocket_session_id) = websocket_session_id {
+            let event = WebsocketServerEvents::Error(WebsocketError::InvalidGameState(self.game_state.clone()));
+            self.send_websocket_session_message(websocket_session_id, event);
+        }
+        false
     }
 
     pub fn get_sessions_pings(&self) -> Vec<WebsocketPing> {
@@ -230,6 +396,59 @@ impl Lobby {
     }
     
 
+    /// Locks out a buzz that comes in while a question is shown but the buzzer is still closed.
+    /// Returns true if the buzz has to be rejected.
+    pub fn early_buzz_lockout(&mut self, user_session_id: &UserSessionId, buzz_time: DateTime<Local>) -> bool {
+        if let Some(until) = self.buzz_lockouts.get(user_session_id) {
+            if *until > buzz_time {
+                return true;
+            }
+            self.buzz_lockouts.remove(user_session_id);
+        }
+        let lockout_millis = match self.buzzer_settings.early_buzz_lockout_millis {
+            None => return false,
+            Some(lockout_millis) => lockout_millis,
+        };
+        if self.jeopardy_board.current.is_none() || self.jeopardy_board.daily_double.is_some() {
+            return false;
+        }
+        if !matches!(*self.jeopardy_board.buzzer_state.lock().expect("Failed to lock buzzer_state"), BuzzerState::None) {
+            return false;
+        }
+        let until = buzz_time + chrono::Duration::milliseconds(lockout_millis as i64);
+        self.buzz_lockouts.insert(user_session_id.clone(), until);
+        let event = WebsocketServerEvents::Board(BoardEvent::BuzzerLockout(user_session_id.clone(), until.timestamp_millis()));
+        self.send_lobby_message(&event);
+        true
+    }
+
+    /// Time a buzz counts with, depending on the buzzer mode of the lobby.
+    pub fn buzz_time(&self, websocket_session_id: &Option<WebsocketSessionId>, received: DateTime<Local>, client_time: Option<f64>) -> DateTime<Local> {
+        let websocket_session = match websocket_session_id.as_ref().and_then(|id| self.websocket_connections.get(id)) {
+            None => return received,
+            Some(websocket_session) => websocket_session,
+        };
+        let tolerance = self.buzzer_settings.tolerance_millis as i64;
+        let compensation = (websocket_session.get_ping() / 2).clamp(0, tolerance);
+        let compensated = received - chrono::Duration::milliseconds(compensation);
+        match self.buzzer_settings.mode {
+            BuzzerMode::ServerTime => received,
+            BuzzerMode::PingCompensated => compensated,
+            BuzzerMode::ClientTimestamp => {
+                let claimed = match client_time.and_then(|time| websocket_session.to_server_time(time)) {
+                    None => return compensated,
+                    Some(claimed) => claimed,
+                };
+                let received_millis = received.timestamp_millis();
+                if claimed > received_millis || (claimed - compensated.timestamp_millis()).abs() > tolerance {
+                    return compensated;
+                }
+                received - chrono::Duration::milliseconds(received_millis - claimed)
+            }
+        }
+    }
+
+
     pub fn get_session_ping(&self, user_session_id: &UserSessionId) -> i64 {
         let mut ping = 0;
         let mut count = 0;
@@ -275,6 +494,23 @@ impl Lobby {
         let qeuestion = self.jeopardy_board.get_mut_question(vector2d).cloned();
         if let Some(value) = qeuestion.clone() {
             self.jeopardy_board.current = Some(vector2d);
+            self.current_buzzes.clear();
+            self.jeopardy_board.daily_double = match value.daily_double && !value.open {
+                true => Some(self.new_daily_double(self.board_control.clone())),
+                false => None,
+            };
+            self.jeopardy_board.written_answers = match value.question_type == QuestionType::Written && !value.open {
+                true => Some(WrittenAnswerState::new(self.answering_players())),
+                false => None,
+            };
+            self.jeopardy_board.multiple_choice = match value.question_type.get_multiple_choice().is_some() && !value.open {
+                true => Some(MultipleChoiceState::new(Local::now().timestamp_millis())),
+                false => None,
+            };
+            self.jeopardy_board.estimation = match value.question_type.get_estimation().is_some() && !value.open {
+                true => Some(EstimationState::default()),
+                false => None,
+            };
             let mut state = self.jeopardy_board.action_state.lock().expect("Failed to lock action state");
             let action: ActionState = value.question_type.get_default_actionstate(websocket_session_id);
             state.update(action)
@@ -284,19 +520,586 @@ impl Lobby {
 
 
 
+    /// Keeps the winner and buzzes of the current question for the game results.
+    fn record_question_result(&mut self, won_user_id: Option<UserSessionId>) {
+        let vector2d = match self.jeopardy_board.current {
+            None => return,
+            Some(vector2d) => vector2d,
+        };
+        let question = match self.jeopardy_board.get_question(vector2d) {
+            None => return,
+            Some(question) => question,
+        };
+        let category = self.jeopardy_board.categories.get(vector2d.x).map(|category| category.title.clone()).unwrap_or_default();
+        let value = self.jeopardy_board.daily_double.as_ref().and_then(|daily_double| daily_double.wager).unwrap_or(question.value);
+        self.question_results.push(DTOQuestionResult {
+            round: self.round_index,
+            category,
+            vector2d,
+            value,
+            won_user_id,
+            buzzes: std::mem::take(&mut self.current_buzzes),
+        });
+    }
+
     pub fn current_question_won(&mut self, grant_score_user_session_id: &UserSessionId) {
+        self.record_question_result(Some(grant_score_user_session_id.clone()));
         if let Some(value) = self.jeopardy_board.get_value_and_remove_current(&grant_score_user_session_id){
-            self.update_session_score(&grant_score_user_session_id, value);
+            // a player who lost the connection keeps the points, they are restored on reconnect
+            self.add_session_score(grant_score_user_session_id, value);
+            self.board_control = Some(grant_score_user_session_id.clone());
         }
         
     }
 
 
+    pub fn current_round(&self) -> Option<DtoJeopardyRound> {
+        let round = self.rounds.get(self.round_index)?;
+        Some(round.dto(self.round_index, self.rounds.len()))
+    }
+
+    /// Scores in `user_data` are kept, only the board is replaced.
+    /// A question, Daily Double or Final Jeopardy still in play has to be finished first.
+    pub fn next_round(&mut self) -> bool {
+        let final_jeopardy_running = self.jeopardy_board.final_jeopardy_state.as_ref().is_some_and(|state| state.stage != FinalJeopardyStage::Finished);
+        if self.jeopardy_board.current.is_some() || self.jeopardy_board.daily_double.is_some() || final_jeopardy_running {
+            return false;
+        }
+        let round = match self.rounds.get(self.round_index + 1) {
+            None => return false,
+            Some(round) => round,
+        };
+        self.jeopardy_board = round.start_board();
+        self.round_index += 1;
+        true
+    }
+
+    /// Subtracts the current question's value and hands the question to the next buzzed player.
+    pub fn current_question_wrong(&mut self, wrong_user_session_id: &UserSessionId) -> Option<i32> {
+        if self.jeopardy_board.daily_double.is_some() {
+            return None;
+        }
+        let question = self.jeopardy_board.get_question(self.jeopardy_board.current?)?;
+        let score = self.add_session_score(wrong_user_session_id, -question.value)?;
+
+        let mut buzzer_state = self.jeopardy_board.buzzer_state.lock().expect("Failed to lock buzzer_state");
+        if let BuzzerState::BuzzerdClosed(ref mut order) = *buzzer_state {
+            order.retain(|user_session_id| !user_session_id.eq(wrong_user_session_id));
+            let event = match order.is_empty() {
+                true => {
+                    *buzzer_state = BuzzerState::None;
+                    WebsocketServerEvents::Board(BoardEvent::BuzzeringReset)
+                }
+                false => WebsocketServerEvents::Board(BoardEvent::BuzzeringClosed(order.clone())),
+            };
+            drop(buzzer_state);
+            self.send_lobby_message(&event);
+        }
+        Some(score)
+    }
+
+
+    fn new_daily_double(&self, user_session_id: Option<UserSessionId>) -> DailyDoubleState {
+        let score = match &user_session_id {
+            None => 0,
+            Some(user_session_id) => self.get_session_score(user_session_id),
+        };
+        DailyDoubleState::new(user_session_id, score, self.jeopardy_board.max_value())
+    }
+
+    pub fn set_daily_double_player(&mut self, user_session_id: &UserSessionId) -> bool {
+        match &self.jeopardy_board.daily_double {
+            Some(daily_double) if daily_double.wager.is_none() => {},
+            _ => return false,
+        }
+        self.jeopardy_board.daily_double = Some(self.new_daily_double(Some(user_session_id.clone())));
+        true
+    }
+
+    /// Only the selected player or an editor may place the wager.
+    pub fn submit_daily_double_wager(&mut self, user_session_id: &UserSessionId, websocket_session_id: &WebsocketSessionId, wager: i32, allowed: bool) -> bool {
+        let daily_double = match self.jeopardy_board.daily_double.as_mut() {
+            Some(daily_double) if daily_double.wager.is_none() && daily_double.user_session_id.is_some() => daily_double,
+            _ => return false,
+        };
+        if !allowed && daily_double.user_session_id.as_ref() != Some(user_session_id) {
+            return false;
+        }
+        if !daily_double.is_valid_wager(wager) {
+            let event = WebsocketServerEvents::Error(WebsocketError::InvalidWager(daily_double.max_wager));
+            self.send_websocket_session_message(websocket_session_id, event);
+            return false;
+        }
+        daily_double.wager = Some(wager);
+        true
+    }
+
+    pub fn judge_daily_double(&mut self, correct: bool) -> bool {
+        let (user_session_id, wager) = match &self.jeopardy_board.daily_double {
+            Some(DailyDoubleState { user_session_id: Some(user_session_id), wager: Some(wager), .. }) => (user_session_id.clone(), *wager),
+            _ => return false,
+        };
+        if correct {
+            self.current_question_won(&user_session_id);
+            return true;
+        }
+        self.record_question_result(None);
+        self.jeopardy_board.close_current();
+        if let Ok(mut state) = self.jeopardy_board.action_state.lock() {
+            state.update(ActionState::None);
+        }
+        self.add_session_score(&user_session_id, -wager);
+        true
+    }
+
+
+    /// Connected players without the creator and spectators.
+    fn answering_players(&self) -> Vec<UserSessionId> {
+        self.connected_user_session.iter()
+            .filter(|user_session_id| !self.is_creator(user_session_id) && !self.is_spectator(user_session_id))
+            .cloned()
+            .collect()
+    }
+
+    pub fn submit_written_answer(&mut self, user_session_id: &UserSessionId, answer: String) -> bool {
+        let state = match self.jeopardy_board.written_answers.as_mut() {
+            Some(state) if !state.closed => state,
+            _ => return false,
+        };
+        match state.get_mut_entry(user_session_id) {
+            None => false,
+            Some(entry) => {
+                entry.answer = Some(answer.trim().to_string());
+                true
+            }
+        }
+    }
+
+    pub fn close_written_answers(&mut self) -> bool {
+        let question = match self.jeopardy_board.get_current() {
+            None => return false,
+            Some(question) => question,
+        };
+        match self.jeopardy_board.written_answers.as_mut() {
+            Some(state) if !state.closed => {
+                state.close(&question);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Awards the correct answers of the confirmed grades, players without a grade get the suggested one.
+    /// The first correct player wins the question and gets board control.
+    pub fn confirm_written_answers(&mut self, grades: &[(UserSessionId, bool)]) -> bool {
+        let state = match &self.jeopardy_board.written_answers {
+            Some(state) if state.closed => state.clone(),
+            _ => return false,
+        };
+        let (vector2d, value) = match self.jeopardy_board.current.zip(self.jeopardy_board.get_current()) {
+            None => return false,
+            Some((vector2d, question)) => (vector2d, question.value),
+        };
+        let mut won_user_id: Option<UserSessionId> = None;
+        for entry in &state.entries {
+            let correct = grades.iter()
+                .find(|(user_session_id, _)| user_session_id.eq(&entry.user_session_id))
+                .map(|(_, correct)| *correct)
+                .or(entry.suggested);
+            if correct != Some(true) {
+                continue;
+            }
+            won_user_id.get_or_insert_with(|| entry.user_session_id.clone());
+            if let Some(score) = self.add_session_score(&entry.user_session_id, value) {
+                self.send_session_score(&entry.user_session_id, score);
+            }
+        }
+        self.record_question_result(won_user_id.clone());
+        if let Some(question) = self.jeopardy_board.get_mut_question(vector2d) {
+            question.won_user_id = won_user_id.clone();
+        }
+        self.jeopardy_board.close_current();
+        if let Ok(mut state) = self.jeopardy_board.action_state.lock() {
+            state.update(ActionState::None);
+        }
+        if won_user_id.is_some() {
+            self.board_control = won_user_id;
+        }
+        true
+    }
+
+    pub fn lock_in_multiple_choice(&mut self, user_session_id: &UserSessionId, choice: usize) -> bool {
+        if self.is_creator(user_session_id) || self.is_spectator(user_session_id) {
+            return false;
+        }
+        let options = match self.jeopardy_board.get_current().as_ref().and_then(|question| question.question_type.get_multiple_choice()) {
+            None => return false,
+            Some(multiple_choice) => multiple_choice.options.len(),
+        };
+        match self.jeopardy_board.multiple_choice.as_mut() {
+            None => false,
+            Some(state) => state.lock_in(user_session_id, choice, options, Local::now().timestamp_millis()),
+        }
+    }
+
+    /// Adds the awarded points, the first awarded player wins the question and gets board control.
+    /// The question stays current so the results can be shown until the host goes back.
+    fn apply_awards(&mut self, vector2d: Vector2D, awards: &[(UserSessionId, i32)]) {
+        for (user_session_id, points) in awards {
+            if let Some(score) = self.add_session_score(user_session_id, *points) {
+                self.send_session_score(user_session_id, score);
+            }
+        }
+        let won_user_id = awards.first().map(|(user_session_id, _)| user_session_id.clone());
+        self.record_question_result(won_user_id.clone());
+        if let Some(question) = self.jeopardy_board.get_mut_question(vector2d) {
+            question.open = true;
+            question.won_user_id = won_user_id.clone();
+        }
+        if won_user_id.is_some() {
+            self.board_control = won_user_id;
+        }
+    }
+
+    /// Awards everyone who picked a correct option, sorted so the fastest correct player wins.
+    pub fn reveal_multiple_choice(&mut self) -> bool {
+        let (vector2d, question) = match self.jeopardy_board.current.zip(self.jeopardy_board.get_current()) {
+            None => return false,
+            Some(current) => current,
+        };
+        let multiple_choice = match question.question_type.get_multiple_choice() {
+            None => return false,
+            Some(multiple_choice) => multiple_choice,
+        };
+        let awards = match self.jeopardy_board.multiple_choice.as_mut() {
+            Some(state) if !state.revealed => state.reveal(multiple_choice, question.value, Local::now().timestamp_millis()),
+            _ => return false,
+        };
+        self.apply_awards(vector2d, &awards);
+        true
+    }
+
+    pub fn submit_estimation_guess(&mut self, user_session_id: &UserSessionId, guess: i64) -> bool {
+        if self.is_creator(user_session_id) || self.is_spectator(user_session_id) {
+            return false;
+        }
+        match self.jeopardy_board.estimation.as_mut() {
+            None => false,
+            Some(state) => state.submit_guess(user_session_id, guess),
+        }
+    }
+
+    /// Awards the closest guesses, sorted so the closest player wins.
+    pub fn reveal_estimation(&mut self) -> bool {
+        let (vector2d, question) = match self.jeopardy_board.current.zip(self.jeopardy_board.get_current()) {
+            None => return false,
+            Some(current) => current,
+        };
+        let estimation = match question.question_type.get_estimation() {
+            None => return false,
+            Some(estimation) => estimation,
+        };
+        let awards = match self.jeopardy_board.estimation.as_mut() {
+            Some(state) if !state.revealed => state.reveal(estimation, question.value),
+            _ => return false,
+        };
+        self.apply_awards(vector2d, &awards);
+        true
+    }
+
+    pub fn start_final_jeopardy(&mut self) -> bool {
+        if self.jeopardy_board.final_jeopardy.is_none() || self.jeopardy_board.final_jeopardy_state.is_some() {
+            return false;
+        }
+        let players: Vec<UserSessionId> = self.connected_user_session.iter()
+            .filter(|user_session_id| !self.is_creator(user_session_id))
+            .sorted_by_key(|user_session_id| self.get_user_session_data(user_session_id).score)
+            .cloned()
+            .collect();
+        self.jeopardy_board.current = None;
+        if let Ok(mut state) = self.jeopardy_board.action_state.lock() {
+            state.update(ActionState::None);
+        }
+        self.jeopardy_board.final_jeopardy_state = Some(FinalJeopardyState::new(players));
+        true
+    }
+
+    pub fn submit_final_wager(&mut self, user_session_id: &UserSessionId, websocket_session_id: &WebsocketSessionId, wager: i32) -> bool {
+        let max_wager = self.get_user_session_data(user_session_id).score.max(0);
+        if wager < 0 || wager > max_wager {
+            let event = WebsocketServerEvents::Error(WebsocketError::InvalidWager(max_wager));
+            self.send_websocket_session_message(websocket_session_id, event);
+            return false;
+        }
+        let state = match self.jeopardy_board.final_jeopardy_state.as_mut() {
+            Some(state) if state.stage == FinalJeopardyStage::Wagering => state,
+            _ => return false,
+        };
+        match state.get_mut_entry(user_session_id) {
+            None => false,
+            Some(entry) => {
+                entry.wager = Some(wager);
+                true
+            }
+        }
+    }
+
+    pub fn open_final_question(&mut self) -> bool {
+        let state = match self.jeopardy_board.final_jeopardy_state.as_mut() {
+            Some(state) if state.stage == FinalJeopardyStage::Wagering => state,
+            _ => return false,
+        };
+        for entry in state.entries.iter_mut() {
+            if entry.wager.is_none() {
+                entry.wager = Some(0);
+            }
+        }
+        state.stage = FinalJeopardyStage::Answering;
+        true
+    }
+
+    pub fn submit_final_answer(&mut self, user_session_id: &UserSessionId, answer: String) -> bool {
+        let state = match self.jeopardy_board.final_jeopardy_state.as_mut() {
+            Some(state) if state.stage == FinalJeopardyStage::Answering => state,
+            _ => return false,
+        };
+        match state.get_mut_entry(user_session_id) {
+            None => false,
+            Some(entry) => {
+                entry.answer = Some(answer.trim().to_string());
+                true
+            }
+        }
+    }
+
+    pub fn reveal_next_final_answer(&mut self) -> bool {
+        let state = match self.jeopardy_board.final_jeopardy_state.as_mut() {
+            Some(state) if state.stage == FinalJeopardyStage::Answering || state.stage == FinalJeopardyStage::Revealing => state,
+            _ => return false,
+        };
+        if state.entries.iter().any(|entry| entry.revealed && entry.correct.is_none()) {
+            return false;
+        }
+        state.stage = FinalJeopardyStage::Revealing;
+        if let Some(entry) = state.next_unrevealed() {
+            entry.revealed = true;
+        }
+        true
+    }
+
+    pub fn judge_final_answer(&mut self, user_session_id: &UserSessionId, correct: bool) -> bool {
+        let state = match self.jeopardy_board.final_jeopardy_state.as_mut() {
+            Some(state) if state.stage == FinalJeopardyStage::Revealing => state,
+            _ => return false,
+        };
+        let entry = match state.get_mut_entry(user_session_id) {
+            Some(entry) if entry.revealed && entry.correct.is_none() => entry,
+            _ => return false,
+        };
+        entry.correct = Some(correct);
+        let wager = entry.wager.unwrap_or(0);
+        if state.all_judged() {
+            state.stage = FinalJeopardyStage::Finished;
+        }
+
+        let score = if correct { wager } else { -wager };
+        if let Some(score) = self.add_session_score(user_session_id, score) {
+            self.send_session_score(user_session_id, score);
+        }
+        true
+    }
+
+
+    /// Starts a countdown and replaces the running one, expired countdowns of older generations are ignored.
+    pub fn start_countdown(&mut self, ctx: &mut Context<Self>, kind: CountdownKind, user_session_id: Option<UserSessionId>, secs: u64) {
+        self.countdown_generation += 1;
+        let generation = self.countdown_generation;
+        let countdown = DtoCountdown {
+            kind,
+            user_session_id: user_session_id.clone(),
+            deadline: (Local::now() + chrono::Duration::seconds(secs as i64)).timestamp_millis(),
+        };
+        self.countdown = Some(countdown.clone());
+        self.send_lobby_message(&WebsocketServerEvents::Board(BoardEvent::Countdown(Some(countdown))));
+        ctx.run_later(Duration::from_secs(secs), move |lobby, ctx| {
+            if lobby.countdown_generation == generation {
+                lobby.countdown_expired(ctx, kind, user_session_id);
+            }
+        });
+    }
+
+    pub fn cancel_countdown(&mut self) {
+        self.countdown_generation += 1;
+        if self.countdown.take().is_some() {
+            self.send_lobby_message(&WebsocketServerEvents::Board(BoardEvent::Countdown(None)));
+        }
+    }
+
+    fn countdown_expired(&mut self, ctx: &mut Context<Self>, kind: CountdownKind, user_session_id: Option<UserSessionId>) {
+        self.cancel_countdown();
+        match kind {
+            CountdownKind::Buzzer => {
+                let mut buzzer_state = self.jeopardy_board.buzzer_state.lock().expect("Failed to lock buzzer_state");
+                let nobody_buzzed = matches!(*buzzer_state, BuzzerState::BuzzerOpen(ref state_map) if state_map.is_empty());
+                if !nobody_buzzed {
+                    return;
+                }
+                *buzzer_state = BuzzerState::None;
+                drop(buzzer_state);
+                self.send_lobby_message(&WebsocketServerEvents::Board(BoardEvent::BuzzeringReset));
+                self.question_timed_out(ctx);
+            }
+            CountdownKind::Answer => {
+                let user_session_id = match user_session_id {
+                    None => return,
+                    Some(user_session_id) => user_session_id,
+                };
+                let answering = match &*self.jeopardy_board.buzzer_state.lock().expect("Failed to lock buzzer_state") {
+                    BuzzerState::BuzzerdClosed(order) => order.first().cloned(),
+                    _ => None,
+                };
+                if answering.as_ref() != Some(&user_session_id) {
+                    return;
+                }
+                if let Some(score) = self.current_question_wrong(&user_session_id) {
+                    self.send_session_score(&user_session_id, score);
+                    ctx.address().do_send(SaveLobby);
+                }
+                self.after_wrong_answer(ctx);
+            }
+        }
+    }
+
+    /// Gives the next buzzed player the answer timer, or times the question out if nobody is left.
+    pub fn after_wrong_answer(&mut self, ctx: &mut Context<Self>) {
+        let buzzer_closed = matches!(*self.jeopardy_board.buzzer_state.lock().expect("Failed to lock buzzer_state"), BuzzerState::BuzzerdClosed(_));
+        match buzzer_closed {
+            true => self.start_answer_timer(ctx),
+            false => self.question_timed_out(ctx),
+        }
+    }
+
+    pub fn start_answer_timer(&mut self, ctx: &mut Context<Self>) {
+        let secs = match self.timers.answer_time_secs {
+            None => return,
+            Some(secs) => secs,
+        };
+        let answering = match &*self.jeopardy_board.buzzer_state.lock().expect("Failed to lock buzzer_state") {
+            BuzzerState::BuzzerdClosed(order) => order.first().cloned(),
+            _ => None,
+        };
+        if let Some(user_session_id) = answering {
+            self.start_countdown(ctx, CountdownKind::Answer, Some(user_session_id), secs);
+        }
+    }
+
+    /// With auto reveal the question is closed without a winner, which shows its answer.
+    fn question_timed_out(&mut self, ctx: &mut Context<Self>) {
+        if !self.timers.auto_reveal || self.jeopardy_board.current.is_none() {
+            return;
+        }
+        self.record_question_result(None);
+        self.jeopardy_board.close_current();
+        if let Ok(mut state) = self.jeopardy_board.action_state.lock() {
+            state.update(ActionState::None);
+        }
+        let dto_board = self.jeopardy_board.dto(self.creator.clone());
+        self.send_lobby_message(&WebsocketServerEvents::Board(BoardEvent::CurrentBoard(dto_board)));
+        ctx.address().do_send(SaveLobby);
+    }
+
+
+    pub fn get_team_id(&self, user_session_id: &UserSessionId) -> Option<TeamId> {
+        self.user_data.get(user_session_id).and_then(|data| data.team_id.clone())
+    }
+
+    pub fn teams_dto(&self) -> Vec<DtoTeam> {
+        self.teams.iter().map(|team| {
+            let members: Vec<&UserSessionData> = self.user_data.values()
+                .filter(|data| data.team_id.as_ref() == Some(&team.team_id))
+                .collect();
+            DtoTeam {
+                team_id: team.team_id.clone(),
+                name: team.name.clone(),
+                members: members.iter().map(|data| data.user_session_id.clone()).collect(),
+                score: members.iter().map(|data| data.score).sum(),
+            }
+        }).collect()
+    }
+
+    pub fn send_teams(&self) {
+        if self.teams.is_empty() {
+            return;
+        }
+        self.send_lobby_message(&WebsocketServerEvents::Board(BoardEvent::CurrentTeams(self.teams_dto())));
+    }
+
+    /// Broadcasts a new score together with the team totals it changes.
+    pub fn send_session_score(&self, user_session_id: &UserSessionId, score: i32) {
+        let event = WebsocketServerEvents::Board(BoardEvent::UpdateSessionScore(user_session_id.clone(), score));
+        self.send_lobby_message(&event);
+        if self.get_team_id(user_session_id).is_some() {
+            self.send_teams();
+        }
+    }
+
+    pub fn create_team(&mut self, name: &str) -> bool {
+        let team = Team::new(name);
+        if team.name.is_empty() || self.teams.iter().any(|existing| existing.name.eq_ignore_ascii_case(&team.name)) {
+            return false;
+        }
+        self.teams.push(team);
+        true
+    }
+
+    pub fn remove_team(&mut self, team_id: &TeamId) -> bool {
+        let count = self.teams.len();
+        self.teams.retain(|team| !team.team_id.eq(team_id));
+        if count == self.teams.len() {
+            return false;
+        }
+        for data in self.user_data.values_mut().filter(|data| data.team_id.as_ref() == Some(team_id)) {
+            data.team_id = None;
+        }
+        true
+    }
+
+    pub fn set_session_team(&mut self, user_session_id: &UserSessionId, team_id: Option<TeamId>) -> bool {
+        if let Some(team_id) = &team_id {
+            if !self.teams.iter().any(|team| team.team_id.eq(team_id)) {
+                return false;
+            }
+        }
+        match self.user_data.get_mut(user_session_id) {
+            None => false,
+            Some(data) => {
+                data.team_id = team_id;
+                true
+            }
+        }
+    }
+
+
     pub fn has_session_websockets(&self, user_session_id: &UserSessionId) -> bool {
-        self.websocket_connections.values().any(|websocket_session| websocket_session.user_session_id.eq(&user_session_id)
+        self.websocket_connections.values().any(|websocket_session| !websocket_session.spectator && websocket_session.user_session_id.eq(user_session_id)
         )
     }
 
+    /// Spectators get every lobby message but don't take part in the game.
+    pub fn is_spectator(&self, user_session_id: &UserSessionId) -> bool {
+        self.spectators.contains(user_session_id) && !self.connected_user_session.contains(user_session_id)
+    }
+
+    pub fn has_spectator_websockets(&self, user_session_id: &UserSessionId) -> bool {
+        self.websocket_connections.values().any(|websocket_session| websocket_session.spectator && websocket_session.user_session_id.eq(user_session_id))
+    }
+
+    pub fn disconnect_spectators(&mut self) {
+        for websocket_session in self.websocket_connections.values().filter(|websocket_session| websocket_session.spectator) {
+            websocket_session.addr.do_send(SendSessionMessageType::SelfDisconnect);
+        }
+    }
+
     pub fn is_new_session(&self, user_session_id: &UserSessionId) -> bool {
         !self.connected_user_session.contains(user_session_id)
     }
@@ -304,12 +1107,12 @@ impl Lobby {
 
 
     pub fn is_multiple_session(&self, user_session_id: &UserSessionId) -> bool {
-        self.websocket_connections.values().any(|websocket_session| websocket_session.user_session_id.eq(&user_session_id))
+        self.has_session_websockets(user_session_id)
     }
 
     pub fn add_new_websocket(&mut self, websocket_connect: &WebsocketConnect ) -> WebsocketSessionId {
         let websocket_session_id = WebsocketSessionId::random();
-        self.websocket_connections.insert(websocket_session_id.clone(), WebsocketSession::new(websocket_session_id.clone(), websocket_connect.user_session_id.clone(), websocket_connect.addr.clone(), websocket_connect.ping));
+        self.websocket_connections.insert(websocket_session_id.clone(), WebsocketSession::new(websocket_session_id.clone(), websocket_connect.user_session_id.clone(), websocket_connect.addr.clone(), websocket_connect.ping, websocket_connect.spectator));
         websocket_session_id
     }
 
@@ -353,7 +1156,7 @@ impl Lobby {
                 None => false,
                 Some(discord_id) => admin.iter().any(|admin| admin.discord_id.eq(&discord_id)),
             };
-            let dto = user_session.dto(&self.get_session_score(user_session_id), is_admin);
+            let dto = user_session.dto(&self.get_session_score(user_session_id), self.get_team_id(user_session_id), is_admin);
             sessions.push(dto);
         }
         sessions
@@ -391,7 +1194,7 @@ impl Lobby {
 
     pub async fn send_someone_joined(&self, user_session:&UserSession, user_score: i32){
         let is_admin = self.is_admin(&user_session).await;
-        let dto =  user_session.clone().dto(&user_score, is_admin);
+        let dto =  user_session.clone().dto(&user_score, self.get_team_id(&user_session.user_session_id), is_admin);
         let event = WebsocketServerEvents::Session(SessionEvent::SessionJoined(dto));
         self.send_lobby_message(&event);
     }
@@ -434,25 +1237,6 @@ impl Lobby {
 
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq, Display)]
-pub enum GameState{
-    Waiting,
-    Starting,
-    Playing,
-    End
-}
-
-impl GameState{
-
-    pub fn open(&self) -> bool {
-        match self {
-            GameState::Waiting => true,
-            GameState::Starting => false,
-            GameState::Playing => false,
-            GameState::End => true,
-        }
-    }
-}
 pub struct LobbyClick {
     pub user_data:UserData,
     pub vector_2d:Vector2D,
@@ -491,17 +1275,27 @@ impl Handler<LobbyClick> for Lobby {
         Box::pin(
             async move {
                 is_editor(&user_session_id, &creator, db).await
-            }.into_actor(self).map(move |allowed, lobby, _|  {
+            }.into_actor(self).map(move |allowed, lobby, ctx|  {
+            if !lobby.accepts_board_action(&msg.user_data.websocket_session_id) {
+                return;
+            }
             if allowed.clone() {
                 let ws_id: WebsocketSessionId = match msg.user_data.websocket_session_id.clone() {
                     None => return,
                     Some(id) => id,
                 };
                 let vec = msg.vector_2d.clone();
-                if let Some(question) = lobby.set_current_question(vec,  &ws_id) {
+                if lobby.set_current_question(vec,  &ws_id).is_some() {
+                    lobby.cancel_countdown();
                     let action_state = lobby.jeopardy_board.action_state.lock().expect("Failed to lock action state").clone();
-                    let event = WebsocketServerEvents::Board(BoardEvent::CurrentQuestion(question.dto(true, vec.clone()), action_state));
-                    lobby.send_lobby_message(&event);
+                    if let Some(question) = lobby.jeopardy_board.current_dto() {
+                        let event = WebsocketServerEvents::Board(BoardEvent::CurrentQuestion(question, action_state));
+                        lobby.send_lobby_message(&event);
+                    }
+                    if let Some(daily_double) = lobby.jeopardy_board.daily_double.clone() {
+                        lobby.send_lobby_message(&WebsocketServerEvents::Board(BoardEvent::DailyDouble(daily_double)));
+                    }
+                    ctx.address().do_send(SaveLobby);
                 }
             }
         }))
@@ -519,42 +1313,408 @@ pub struct LobbyBackClick {
 impl Handler<LobbyBackClick> for Lobby {
     type Result = ResponseActFuture<Self, ()>;
 
-    fn handle(&mut self, msg: LobbyBackClick, _: &mut Self::Context) -> Self::Result {
+    fn handle(&mut self, msg: LobbyBackClick, _: &mut Self::Context) -> Self::Result {
+        let user_session_id = msg.user_data.user_session_id.clone();
+        let creator = self.creator.clone();
+        let db = self.starting_services.mongo_server.clone();
+
+        Box::pin(
+            async move {
+                is_editor(&user_session_id, &creator, db).await
+        }.into_actor(self).map(move |allowed, lobby, ctx|  {
+            if !lobby.accepts_board_action(&msg.user_data.websocket_session_id) {
+                return;
+            }
+            if allowed.clone() {
+                lobby.cancel_countdown();
+                lobby.jeopardy_board.current = None;
+                lobby.jeopardy_board.daily_double = None;
+                lobby.jeopardy_board.written_answers = None;
+                lobby.jeopardy_board.multiple_choice = None;
+                lobby.jeopardy_board.estimation = None;
+                if let Ok(mut state) = lobby.jeopardy_board.action_state.lock(){
+                    state.update(ActionState::None);
+                }
+                let board = lobby.jeopardy_board.clone();
+                println!("Back Clicked {:#?}", board.action_state);
+                let event = WebsocketServerEvents::Board(BoardEvent::CurrentBoard(board.dto(lobby.creator.clone())));
+                lobby.send_lobby_message(&event);
+                ctx.address().do_send(SaveLobby);
+            }
+        }))
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct AddLobbySessionScore {
+    pub user_data: UserData,
+    pub grant_score_user_session_id: UserSessionId,
+    pub vector2d: Vector2D,
+}
+
+
+impl Handler<AddLobbySessionScore> for Lobby {
+    type Result = ResponseActFuture<Self, ()>;
+
+    fn handle(&mut self, msg: AddLobbySessionScore, _: &mut Self::Context) -> Self::Result {
+        let user_session_id = msg.user_data.user_session_id.clone();
+        let creator = self.creator.clone();
+        let db = self.starting_services.mongo_server.clone();
+        Box::pin(
+            async move {
+                is_editor(&user_session_id, &creator, db).await
+        }.into_actor(self).map(move |allowed, lobby,ctx|  {
+            if !lobby.accepts_board_action(&msg.user_data.websocket_session_id) {
+                return;
+            }
+            if allowed.clone() {
+                if let Some(daily_double) = &lobby.jeopardy_board.daily_double {
+                    // only the Daily Double player can win the wager
+                    if daily_double.wager.is_none() || daily_double.user_session_id.as_ref() != Some(&msg.grant_score_user_session_id) {
+                        return;
+                    }
+                    lobby.cancel_countdown();
+                    lobby.judge_daily_double(true);
+                } else {
+                    lobby.cancel_countdown();
+                    lobby.current_question_won(&msg.grant_score_user_session_id);
+                }
+                let dto_board = lobby.jeopardy_board.dto(lobby.creator.clone());
+                let event = WebsocketServerEvents::Board(BoardEvent::CurrentBoard(dto_board));
+                lobby.send_lobby_message(&event);
+                //FIXME: Send the user session id
+                ctx.address().do_send(SendCurrentDTOSessions{});
+                ctx.address().do_send(SaveLobby);
+            }
+        }))
+    }
+}
+
+
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct LobbyWrongAnswer {
+    pub user_data: UserData,
+    pub wrong_user_session_id: UserSessionId,
+    pub vector2d: Vector2D,
+}
+
+
+impl Handler<LobbyWrongAnswer> for Lobby {
+    type Result = ResponseActFuture<Self, ()>;
+
+    fn handle(&mut self, msg: LobbyWrongAnswer, _: &mut Self::Context) -> Self::Result {
+        let user_session_id = msg.user_data.user_session_id.clone();
+        let creator = self.creator.clone();
+        let db = self.starting_services.mongo_server.clone();
+        Box::pin(
+            async move {
+                is_editor(&user_session_id, &creator, db).await
+        }.into_actor(self).map(move |allowed, lobby,ctx|  {
+            if !lobby.accepts_board_action(&msg.user_data.websocket_session_id) {
+                return;
+            }
+            if !allowed || lobby.jeopardy_board.current != Some(msg.vector2d) {
+                return;
+            }
+            if let Some(score) = lobby.current_question_wrong(&msg.wrong_user_session_id) {
+                lobby.send_session_score(&msg.wrong_user_session_id, score);
+                lobby.cancel_countdown();
+                lobby.after_wrong_answer(ctx);
+                ctx.address().do_send(SaveLobby);
+            }
+        }))
+    }
+}
+
+
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct ReciveGameStateEvent {
+    pub user_data: UserData,
+    pub event: GameStateEvent,
+}
+
+
+impl Handler<ReciveGameStateEvent> for Lobby {
+    type Result = ResponseActFuture<Self, ()>;
+
+    fn handle(&mut self, msg: ReciveGameStateEvent, _: &mut Self::Context) -> Self::Result {
+        let user_session_id = msg.user_data.user_session_id.clone();
+        let creator = self.creator.clone();
+        let db = self.starting_services.mongo_server.clone();
+        Box::pin(
+            async move {
+                is_editor(&user_session_id, &creator, db).await
+        }.into_actor(self).map(move |allowed, lobby,ctx|  {
+            if !allowed {
+                return;
+            }
+            let game_state = match lobby.game_state.transition(&msg.event) {
+                Some(game_state) => game_state,
+                None => {
+                    if let Some(websocket_session_id) = &msg.user_data.websocket_session_id {
+                        let event = WebsocketServerEvents::Error(WebsocketError::InvalidGameState(lobby.game_state.clone()));
+                        lobby.send_websocket_session_message(websocket_session_id, event);
+                    }
+                    return;
+                }
+            };
+            // a started game only lets its players back in, so everyone taking part is allowed now
+            if lobby.game_state.open() && !game_state.open() {
+                let players: Vec<UserSessionId> = lobby.connected_user_session.iter().cloned().collect();
+                lobby.allowed_user_session.extend(players);
+            }
+            lobby.update_game_state(game_state.clone());
+            match game_state {
+                GameState::Starting => {
+                    lobby.game_start = Some(Local::now());
+                    ctx.run_later(GAME_START_DELAY, |lobby, ctx| {
+                        if lobby.game_state == GameState::Starting {
+                            lobby.update_game_state(GameState::Playing);
+                            ctx.address().do_send(SaveLobby);
+                        }
+                    });
+                }
+                GameState::End => ctx.address().do_send(FinishGame),
+                _ => {}
+            }
+            ctx.address().do_send(SaveLobby);
+        }))
+    }
+}
+
+
+/// Sends the final standings and stores the results of the game.
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct FinishGame;
+
+
+impl Handler<FinishGame> for Lobby {
+    type Result = ResponseActFuture<Self, ()>;
+
+    fn handle(&mut self, _: FinishGame, _: &mut Self::Context) -> Self::Result {
+        let db: Arc<MongoServer> = self.starting_services.mongo_server.clone();
+        let user_session_data = self.user_data.clone();
+        let user_session_id = self.user_data.keys()
+            .filter(|user_session_id| !self.is_creator(user_session_id))
+            .cloned()
+            .collect::<Vec<UserSessionId>>();
+        let title = match self.rounds.first() {
+            None => self.jeopardy_board.title.clone(),
+            Some(round) => round.board.title.clone(),
+        };
+        let lobby_id = self.lobby_id.clone();
+        let questions = self.question_results.clone();
+        let start_date = self.game_start.unwrap_or(self.jeopardy_board.create);
+
+        Box::pin(
+            async move {
+                let sessions = get_sessions(&db, &user_session_id).await;
+                let mut dto_sessions = get_dto_sessions(&db, sessions, user_session_data).await;
+                dto_sessions.sort_by_key(|dto_session| std::cmp::Reverse(dto_session.score));
+                let game_result = GameResultData {
+                    game_id: GameId::random(),
+                    lobby_id,
+                    title,
+                    players: dto_sessions.clone(),
+                    questions,
+                    start_date: bson::DateTime::from_chrono(start_date),
+                    end_date: bson::DateTime::now(),
+                };
+                if !db.insert_game_result(&game_result).await {
+                    println!("Failed to save results of lobby={:?}", game_result.lobby_id.id);
+                }
+                WebsocketServerEvents::Game(GameEvent::FinalStandings(dto_sessions))
+        }.into_actor(self).map(move |event, lobby, _|  {
+            lobby.send_lobby_message(&event);
+        }))
+    }
+}
+
+
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct ReciveTeamEvent {
+    pub user_data: UserData,
+    pub event: TeamEvent,
+}
+
+
+impl Handler<ReciveTeamEvent> for Lobby {
+    type Result = ResponseActFuture<Self, ()>;
+
+    fn handle(&mut self, msg: ReciveTeamEvent, _: &mut Self::Context) -> Self::Result {
+        let user_session_id = msg.user_data.user_session_id.clone();
+        let creator = self.creator.clone();
+        let db = self.starting_services.mongo_server.clone();
+        Box::pin(
+            async move {
+                is_editor(&user_session_id, &creator, db).await
+        }.into_actor(self).map(move |allowed, lobby,ctx|  {
+            // Players pick their own team only before the game starts.
+            let self_service = lobby.game_state == GameState::Waiting && !lobby.is_spectator(&msg.user_data.user_session_id);
+            let changed = match msg.event {
+                TeamEvent::Join(team_id) if self_service => lobby.set_session_team(&msg.user_data.user_session_id, Some(team_id)),
+                TeamEvent::Leave if self_service => lobby.set_session_team(&msg.user_data.user_session_id, None),
+                _ if !allowed => false,
+                TeamEvent::Create(name) => lobby.create_team(&name),
+                TeamEvent::Remove(team_id) => lobby.remove_team(&team_id),
+                TeamEvent::Assign(user_session_id, team_id) => lobby.set_session_team(&user_session_id, team_id),
+                TeamEvent::Join(_) | TeamEvent::Leave => false,
+            };
+            if changed {
+                lobby.send_lobby_message(&WebsocketServerEvents::Board(BoardEvent::CurrentTeams(lobby.teams_dto())));
+                ctx.address().do_send(SendCurrentDTOSessions{});
+                ctx.address().do_send(SaveLobby);
+            }
+        }))
+    }
+}
+
+
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct LobbyUpdateTimers {
+    pub user_data: UserData,
+    pub timers: LobbyTimers,
+}
+
+
+impl Handler<LobbyUpdateTimers> for Lobby {
+    type Result = ResponseActFuture<Self, ()>;
+
+    fn handle(&mut self, msg: LobbyUpdateTimers, _: &mut Self::Context) -> Self::Result {
+        let user_session_id = msg.user_data.user_session_id.clone();
+        let creator = self.creator.clone();
+        let db = self.starting_services.mongo_server.clone();
+        Box::pin(
+            async move {
+                is_editor(&user_session_id, &creator, db).await
+        }.into_actor(self).map(move |allowed, lobby,ctx|  {
+            if !allowed {
+                return;
+            }
+            lobby.timers = msg.timers;
+            lobby.send_lobby_message(&WebsocketServerEvents::Board(BoardEvent::Timers(lobby.timers.clone())));
+            ctx.address().do_send(SaveLobby);
+        }))
+    }
+}
+
+
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct LobbyUpdateBuzzerSettings {
+    pub user_data: UserData,
+    pub buzzer_settings: BuzzerSettings,
+}
+
+
+impl Handler<LobbyUpdateBuzzerSettings> for Lobby {
+    type Result = ResponseActFuture<Self, ()>;
+
+    fn handle(&mut self, msg: LobbyUpdateBuzzerSettings, _: &mut Self::Context) -> Self::Result {
+        let user_session_id = msg.user_data.user_session_id.clone();
+        let creator = self.creator.clone();
+        let db = self.starting_services.mongo_server.clone();
+        Box::pin(
+            async move {
+                is_editor(&user_session_id, &creator, db).await
+        }.into_actor(self).map(move |allowed, lobby,ctx|  {
+            if !allowed {
+                return;
+            }
+            lobby.buzzer_settings = msg.buzzer_settings;
+            lobby.send_lobby_message(&WebsocketServerEvents::Board(BoardEvent::BuzzerSettings(lobby.buzzer_settings.clone())));
+            ctx.address().do_send(SaveLobby);
+        }))
+    }
+}
+
+
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct LobbyAllowSpectators {
+    pub user_data: UserData,
+    pub allow: bool,
+}
+
+
+impl Handler<LobbyAllowSpectators> for Lobby {
+    type Result = ResponseActFuture<Self, ()>;
+
+    fn handle(&mut self, msg: LobbyAllowSpectators, _: &mut Self::Context) -> Self::Result {
+        let user_session_id = msg.user_data.user_session_id.clone();
+        let creator = self.creator.clone();
+        let db = self.starting_services.mongo_server.clone();
+        Box::pin(
+            async move {
+                is_editor(&user_session_id, &creator, db).await
+        }.into_actor(self).map(move |allowed, lobby,ctx|  {
+            if !allowed {
+                return;
+            }
+            lobby.allow_spectators = msg.allow;
+            if !msg.allow {
+                lobby.disconnect_spectators();
+            }
+            ctx.address().do_send(SaveLobby);
+        }))
+    }
+}
+
+
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct LobbyNextRound {
+    pub user_data: UserData,
+}
+
+
+impl Handler<LobbyNextRound> for Lobby {
+    type Result = ResponseActFuture<Self, ()>;
+
+    fn handle(&mut self, msg: LobbyNextRound, _: &mut Self::Context) -> Self::Result {
         let user_session_id = msg.user_data.user_session_id.clone();
         let creator = self.creator.clone();
         let db = self.starting_services.mongo_server.clone();
-
         Box::pin(
             async move {
                 is_editor(&user_session_id, &creator, db).await
-        }.into_actor(self).map(move |allowed, lobby, _|  {
-            if allowed.clone() {
-                lobby.jeopardy_board.current = None;
-                if let Ok(mut state) = lobby.jeopardy_board.action_state.lock(){
-                    state.update(ActionState::None);
-                }
-                let board = lobby.jeopardy_board.clone();
-                println!("Back Clicked {:#?}", board.action_state);
-                let event = WebsocketServerEvents::Board(BoardEvent::CurrentBoard(board.dto(lobby.creator.clone())));
-                lobby.send_lobby_message(&event);
+        }.into_actor(self).map(move |allowed, lobby,ctx|  {
+            if !lobby.accepts_board_action(&msg.user_data.websocket_session_id) {
+                return;
+            }
+            if !allowed || !lobby.next_round() {
+                return;
+            }
+            let dto_board = lobby.jeopardy_board.dto(lobby.creator.clone());
+            lobby.send_lobby_message(&WebsocketServerEvents::Board(BoardEvent::CurrentBoard(dto_board)));
+            if let Some(round) = lobby.current_round() {
+                lobby.send_lobby_message(&WebsocketServerEvents::Board(BoardEvent::CurrentRound(round)));
             }
+            ctx.address().do_send(SaveLobby);
         }))
     }
 }
 
+
 #[derive(Message)]
 #[rtype(result = "()")]
-pub struct AddLobbySessionScore {
+pub struct AdjustLobbySessionScore {
     pub user_data: UserData,
-    pub grant_score_user_session_id: UserSessionId,
-    pub vector2d: Vector2D,
+    pub adjust_user_session_id: UserSessionId,
+    pub score: i32,
 }
 
 
-impl Handler<AddLobbySessionScore> for Lobby {
+impl Handler<AdjustLobbySessionScore> for Lobby {
     type Result = ResponseActFuture<Self, ()>;
 
-    fn handle(&mut self, msg: AddLobbySessionScore, _: &mut Self::Context) -> Self::Result {
+    fn handle(&mut self, msg: AdjustLobbySessionScore, _: &mut Self::Context) -> Self::Result {
         let user_session_id = msg.user_data.user_session_id.clone();
         let creator = self.creator.clone();
         let db = self.starting_services.mongo_server.clone();
@@ -562,13 +1722,12 @@ impl Handler<AddLobbySessionScore> for Lobby {
             async move {
                 is_editor(&user_session_id, &creator, db).await
         }.into_actor(self).map(move |allowed, lobby,ctx|  {
-            if allowed.clone() {
-                lobby.current_question_won(&msg.grant_score_user_session_id);
-                let dto_board = lobby.jeopardy_board.dto(lobby.creator.clone());
-                let event = WebsocketServerEvents::Board(BoardEvent::CurrentBoard(dto_board));
-                lobby.send_lobby_message(&event);
-                //FIXME: Send the user session id
-                ctx.address().do_send(SendCurrentDTOSessions{});
+            if !allowed {
+                return;
+            }
+            if let Some(score) = lobby.add_session_score(&msg.adjust_user_session_id, msg.score) {
+                lobby.send_session_score(&msg.adjust_user_session_id, score);
+                ctx.address().do_send(SaveLobby);
             }
         }))
     }
@@ -597,6 +1756,7 @@ impl Handler<SendCurrentDTOSessions> for Lobby {
                 WebsocketServerEvents::Session(SessionEvent::CurrentSessions(dto_sessions))
         }.into_actor(self).map(move |event, lobby, _|  {
             lobby.send_lobby_message(&event);
+            lobby.send_teams();
         }))
     }
 }
@@ -750,6 +1910,300 @@ impl Handler<ReciveVideoEvent> for Lobby {
 }
 
 
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct ReciveImageRevealEvent{
+    pub user_session_id: UserSessionId,
+    pub event: ImageRevealEvent,
+}
+
+impl Handler<ReciveImageRevealEvent> for Lobby {
+    type Result = ResponseActFuture<Self, ()>;
+
+    fn handle(&mut self, msg: ReciveImageRevealEvent, _: &mut Self::Context) -> Self::Result {
+        let user_session_id = msg.user_session_id.clone();
+        let creator = self.creator.clone();
+        let db = self.starting_services.mongo_server.clone();
+
+        Box::pin(
+            async move {
+                is_editor(&user_session_id, &creator, db).await
+        }.into_actor(self).map(move |allowed, lobby: &mut Lobby, _|  {
+            if !allowed {
+                return;
+            }
+            let mut state = lobby.jeopardy_board.action_state.lock().expect("Failed to lock action state");
+            let image_reveal = match state.get_mut_image_reveal() {
+                None => return,
+                Some(image_reveal) => image_reveal,
+            };
+            let step = match msg.event {
+                ImageRevealEvent::Next => image_reveal.step + 1,
+                ImageRevealEvent::Previous => image_reveal.step.saturating_sub(1),
+                ImageRevealEvent::RevealAll => image_reveal.steps,
+            };
+            if !image_reveal.set_step(step) {
+                return;
+            }
+            let event = WebsocketServerEvents::ActionState(ActionStateEvent::ImageReveal(image_reveal.clone()));
+            drop(state);
+            lobby.send_lobby_message(&event);
+        }))
+    }
+}
+
+
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct ReciveDailyDoubleEvent{
+    pub user_session_id: UserSessionId,
+    pub websocket_session_id: WebsocketSessionId,
+    pub event: DailyDoubleEvent,
+}
+
+impl Handler<ReciveDailyDoubleEvent> for Lobby {
+    type Result = ResponseActFuture<Self, ()>;
+
+    fn handle(&mut self, msg: ReciveDailyDoubleEvent, _: &mut Self::Context) -> Self::Result {
+        let user_session_id = msg.user_session_id.clone();
+        let creator = self.creator.clone();
+        let db = self.starting_services.mongo_server.clone();
+
+        Box::pin(
+            async move {
+                is_editor(&user_session_id, &creator, db).await
+        }.into_actor(self).map(move |allowed, lobby: &mut Lobby, ctx|  {
+            if !lobby.accepts_board_action(&Some(msg.websocket_session_id.clone())) {
+                return;
+            }
+            match msg.event {
+                DailyDoubleEvent::SubmitWager(wager) => {
+                    if !lobby.submit_daily_double_wager(&msg.user_session_id, &msg.websocket_session_id, wager, allowed) {
+                        return;
+                    }
+                    let action_state = lobby.jeopardy_board.action_state.lock().expect("Failed to lock action state").clone();
+                    if let Some(question) = lobby.jeopardy_board.current_dto() {
+                        lobby.send_lobby_message(&WebsocketServerEvents::Board(BoardEvent::CurrentQuestion(question, action_state)));
+                    }
+                    if let Some(daily_double) = lobby.jeopardy_board.daily_double.clone() {
+                        lobby.send_lobby_message(&WebsocketServerEvents::Board(BoardEvent::DailyDouble(daily_double)));
+                    }
+                }
+                _ if !allowed => return,
+                DailyDoubleEvent::SetPlayer(user_session_id) => {
+                    if !lobby.set_daily_double_player(&user_session_id) {
+                        return;
+                    }
+                    if let Some(daily_double) = lobby.jeopardy_board.daily_double.clone() {
+                        lobby.send_lobby_message(&WebsocketServerEvents::Board(BoardEvent::DailyDouble(daily_double)));
+                    }
+                }
+                DailyDoubleEvent::Judge(correct) => {
+                    if !lobby.judge_daily_double(correct) {
+                        return;
+                    }
+                    let dto_board = lobby.jeopardy_board.dto(lobby.creator.clone());
+                    lobby.send_lobby_message(&WebsocketServerEvents::Board(BoardEvent::CurrentBoard(dto_board)));
+                    ctx.address().do_send(SendCurrentDTOSessions{});
+                }
+            }
+            ctx.address().do_send(SaveLobby);
+        }))
+    }
+}
+
+
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct ReciveFinalJeopardyEvent{
+    pub user_session_id: UserSessionId,
+    pub websocket_session_id: WebsocketSessionId,
+    pub event: FinalJeopardyEvent,
+}
+
+impl Handler<ReciveFinalJeopardyEvent> for Lobby {
+    type Result = ResponseActFuture<Self, ()>;
+
+    fn handle(&mut self, msg: ReciveFinalJeopardyEvent, _: &mut Self::Context) -> Self::Result {
+        let user_session_id = msg.user_session_id.clone();
+        let creator = self.creator.clone();
+        let db = self.starting_services.mongo_server.clone();
+
+        Box::pin(
+            async move {
+                is_editor(&user_session_id, &creator, db).await
+        }.into_actor(self).map(move |allowed, lobby: &mut Lobby, ctx|  {
+            if !lobby.accepts_board_action(&Some(msg.websocket_session_id.clone())) {
+                return;
+            }
+            let changed = match msg.event {
+                FinalJeopardyEvent::SubmitWager(wager) => lobby.submit_final_wager(&msg.user_session_id, &msg.websocket_session_id, wager),
+                FinalJeopardyEvent::SubmitAnswer(answer) => lobby.submit_final_answer(&msg.user_session_id, answer),
+                _ if !allowed => false,
+                FinalJeopardyEvent::Start => {
+                    let started = lobby.start_final_jeopardy();
+                    if started {
+                        let event = WebsocketServerEvents::Board(BoardEvent::CurrentBoard(lobby.jeopardy_board.dto(lobby.creator.clone())));
+                        lobby.send_lobby_message(&event);
+                    }
+                    started
+                }
+                FinalJeopardyEvent::OpenQuestion => lobby.open_final_question(),
+                FinalJeopardyEvent::RevealNext => lobby.reveal_next_final_answer(),
+                FinalJeopardyEvent::Judge(user_session_id, correct) => lobby.judge_final_answer(&user_session_id, correct),
+            };
+            if changed {
+                if let Some(dto) = lobby.jeopardy_board.final_jeopardy_dto() {
+                    lobby.send_lobby_message(&WebsocketServerEvents::Board(BoardEvent::FinalJeopardy(dto)));
+                }
+                ctx.address().do_send(SaveLobby);
+            }
+        }))
+    }
+}
+
+
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct ReciveWrittenAnswerEvent{
+    pub user_session_id: UserSessionId,
+    pub websocket_session_id: WebsocketSessionId,
+    pub event: WrittenAnswerEvent,
+}
+
+impl Handler<ReciveWrittenAnswerEvent> for Lobby {
+    type Result = ResponseActFuture<Self, ()>;
+
+    fn handle(&mut self, msg: ReciveWrittenAnswerEvent, _: &mut Self::Context) -> Self::Result {
+        let user_session_id = msg.user_session_id.clone();
+        let creator = self.creator.clone();
+        let db = self.starting_services.mongo_server.clone();
+
+        Box::pin(
+            async move {
+                is_editor(&user_session_id, &creator, db).await
+        }.into_actor(self).map(move |allowed, lobby: &mut Lobby, ctx|  {
+            if !lobby.accepts_board_action(&Some(msg.websocket_session_id.clone())) {
+                return;
+            }
+            let changed = match msg.event {
+                WrittenAnswerEvent::Submit(answer) => lobby.submit_written_answer(&msg.user_session_id, answer),
+                _ if !allowed => false,
+                WrittenAnswerEvent::Close => lobby.close_written_answers(),
+                WrittenAnswerEvent::Confirm(grades) => {
+                    let confirmed = lobby.confirm_written_answers(&grades);
+                    if confirmed {
+                        let event = WebsocketServerEvents::Board(BoardEvent::CurrentBoard(lobby.jeopardy_board.dto(lobby.creator.clone())));
+                        lobby.send_lobby_message(&event);
+                    }
+                    confirmed
+                }
+            };
+            if changed {
+                if let Some(written_answers) = &lobby.jeopardy_board.written_answers {
+                    lobby.send_lobby_message(&WebsocketServerEvents::Board(BoardEvent::WrittenAnswers(written_answers.dto())));
+                }
+                ctx.address().do_send(SaveLobby);
+            }
+        }))
+    }
+}
+
+
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct ReciveMultipleChoiceEvent{
+    pub user_session_id: UserSessionId,
+    pub websocket_session_id: WebsocketSessionId,
+    pub event: MultipleChoiceEvent,
+}
+
+impl Handler<ReciveMultipleChoiceEvent> for Lobby {
+    type Result = ResponseActFuture<Self, ()>;
+
+    fn handle(&mut self, msg: ReciveMultipleChoiceEvent, _: &mut Self::Context) -> Self::Result {
+        let user_session_id = msg.user_session_id.clone();
+        let creator = self.creator.clone();
+        let db = self.starting_services.mongo_server.clone();
+
+        Box::pin(
+            async move {
+                is_editor(&user_session_id, &creator, db).await
+        }.into_actor(self).map(move |allowed, lobby: &mut Lobby, ctx|  {
+            if !lobby.accepts_board_action(&Some(msg.websocket_session_id.clone())) {
+                return;
+            }
+            let changed = match msg.event {
+                MultipleChoiceEvent::LockIn(choice) => lobby.lock_in_multiple_choice(&msg.user_session_id, choice),
+                MultipleChoiceEvent::Reveal if allowed => {
+                    let revealed = lobby.reveal_multiple_choice();
+                    if revealed {
+                        lobby.cancel_countdown();
+                        let event = WebsocketServerEvents::Board(BoardEvent::CurrentBoard(lobby.jeopardy_board.dto(lobby.creator.clone())));
+                        lobby.send_lobby_message(&event);
+                    }
+                    revealed
+                }
+                MultipleChoiceEvent::Reveal => false,
+            };
+            if changed {
+                if let Some(multiple_choice) = lobby.jeopardy_board.multiple_choice_dto() {
+                    lobby.send_lobby_message(&WebsocketServerEvents::Board(BoardEvent::MultipleChoice(multiple_choice)));
+                }
+                ctx.address().do_send(SaveLobby);
+            }
+        }))
+    }
+}
+
+
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct ReciveEstimationEvent{
+    pub user_session_id: UserSessionId,
+    pub websocket_session_id: WebsocketSessionId,
+    pub event: EstimationEvent,
+}
+
+impl Handler<ReciveEstimationEvent> for Lobby {
+    type Result = ResponseActFuture<Self, ()>;
+
+    fn handle(&mut self, msg: ReciveEstimationEvent, _: &mut Self::Context) -> Self::Result {
+        let user_session_id = msg.user_session_id.clone();
+        let creator = self.creator.clone();
+        let db = self.starting_services.mongo_server.clone();
+
+        Box::pin(
+            async move {
+                is_editor(&user_session_id, &creator, db).await
+        }.into_actor(self).map(move |allowed, lobby: &mut Lobby, ctx|  {
+            if !lobby.accepts_board_action(&Some(msg.websocket_session_id.clone())) {
+                return;
+            }
+            let changed = match msg.event {
+                EstimationEvent::SubmitGuess(guess) => lobby.submit_estimation_guess(&msg.user_session_id, guess),
+                EstimationEvent::Reveal if allowed => {
+                    let revealed = lobby.reveal_estimation();
+                    if revealed {
+                        lobby.cancel_countdown();
+                        let event = WebsocketServerEvents::Board(BoardEvent::CurrentBoard(lobby.jeopardy_board.dto(lobby.creator.clone())));
+                        lobby.send_lobby_message(&event);
+                    }
+                    revealed
+                }
+                EstimationEvent::Reveal => false,
+            };
+            if changed {
+                if let Some(estimation) = lobby.jeopardy_board.estimation_dto() {
+                    lobby.send_lobby_message(&WebsocketServerEvents::Board(BoardEvent::Estimation(estimation)));
+                }
+                ctx.address().do_send(SaveLobby);
+            }
+        }))
+    }
+}
+
+
 #[derive(Message)]
 #[rtype(result = "()")]
 pub struct SyncBackwardRequest{
@@ -777,7 +2231,11 @@ impl Handler<SyncForwardRequest> for Lobby {
     type Result = ();
 
     fn handle(&mut self, msg: SyncForwardRequest, _: &mut Self::Context) -> Self::Result {
-        let current_time =  Local::now().timestamp_millis() as f64 - msg.current_time;
+        let server_time = Local::now().timestamp_millis() as f64;
+        if let Some(websocket_session) = self.websocket_connections.get_mut(&msg.websocket_session_id) {
+            websocket_session.update_clock_offset(server_time, msg.current_time);
+        }
+        let current_time = server_time - msg.current_time;
         let event = WebsocketServerEvents::ActionState(ActionStateEvent::SyncForward(current_time));
         self.send_websocket_session_message(&msg.websocket_session_id, event)
     }
@@ -793,6 +2251,7 @@ pub struct WebsocketConnect {
     pub user_session_id: UserSessionId,
     pub addr: Recipient<SendSessionMessageType>,
     pub ping: i64,
+    pub spectator: bool,
 }
 
 
@@ -813,6 +2272,17 @@ struct WebsocketConnectFuture{
 impl Handler<WebsocketConnect> for Lobby {
     type Result =    Option<WebsocketSessionId>;
     fn handle(&mut self, msg: WebsocketConnect, ctx: &mut Context<Self>) -> Self::Result {
+        if msg.spectator {
+            if !self.allow_spectators {
+                println!("Spectators are not allowed in the lobby={:?}.", &self.lobby_id.id);
+                return None;
+            }
+            let websocket_session_id = self.add_new_websocket(&msg);
+            self.spectators.insert(msg.user_session_id.clone());
+            ctx.address().do_send(SendWSCurrentDTOBoard{websocket_session_id: websocket_session_id.clone()});
+            ctx.address().do_send(SendCurrentDTOSessions{});
+            return Some(websocket_session_id);
+        }
 
         let has_websockets: bool = self.has_session_websockets(&msg.user_session_id);
         let websockets = self.get_session_websockets(&msg.user_session_id);
@@ -833,6 +2303,7 @@ impl Handler<WebsocketConnect> for Lobby {
         let is_new_session = self.is_new_session(&msg.user_session_id);
         if is_new_session {
             self.add_new_session(&msg.user_session_id, &user_session_data);
+            ctx.address().do_send(SaveLobby);
         } else {
             self.reconnect_session(&msg.user_session_id);
         }
@@ -867,6 +2338,23 @@ impl Handler<SendWSCurrentDTOBoard> for Lobby {
         let dto_board = self.jeopardy_board.dto(self.creator.clone());
         let event = WebsocketServerEvents::Board(BoardEvent::CurrentBoard(dto_board.clone()));
         self.send_websocket_session_message(&msg.websocket_session_id, event);
+        if let Some(round) = self.current_round() {
+            self.send_websocket_session_message(&msg.websocket_session_id, WebsocketServerEvents::Board(BoardEvent::CurrentRound(round)));
+        }
+        let event = WebsocketServerEvents::Game(GameEvent::StateChanged(self.game_state.clone()));
+        self.send_websocket_session_message(&msg.websocket_session_id, event);
+        if !self.teams.is_empty() {
+            let event = WebsocketServerEvents::Board(BoardEvent::CurrentTeams(self.teams_dto()));
+            self.send_websocket_session_message(&msg.websocket_session_id, event);
+        }
+        let event = WebsocketServerEvents::Board(BoardEvent::Timers(self.timers.clone()));
+        self.send_websocket_session_message(&msg.websocket_session_id, event);
+        let event = WebsocketServerEvents::Board(BoardEvent::BuzzerSettings(self.buzzer_settings.clone()));
+        self.send_websocket_session_message(&msg.websocket_session_id, event);
+        if let Some(countdown) = self.countdown.clone() {
+            let event = WebsocketServerEvents::Board(BoardEvent::Countdown(Some(countdown)));
+            self.send_websocket_session_message(&msg.websocket_session_id, event);
+        }
             
     }
 }
@@ -887,13 +2375,23 @@ pub struct WebsocketDisconnect {
 impl Handler<WebsocketDisconnect> for Lobby {
     type Result = ();
 
-    fn handle(&mut self, msg: WebsocketDisconnect, _: &mut Context<Self>) {
+    fn handle(&mut self, msg: WebsocketDisconnect, _ctx: &mut Context<Self>) {
         let websocket_session_id =  match msg.user_data.websocket_session_id {
             None => return,
             Some(websocket_session_id) => websocket_session_id
         };
 
-        self.websocket_connections.remove(&websocket_session_id);
+        let websocket_session = match self.websocket_connections.remove(&websocket_session_id) {
+            None => return,
+            Some(websocket_session) => websocket_session,
+        };
+        if websocket_session.spectator {
+            if !self.has_spectator_websockets(&msg.user_data.user_session_id) {
+                self.spectators.remove(&msg.user_data.user_session_id);
+            }
+            println!("Spectator {:?} has left the lobby={:?}.", msg.user_data.user_session_id.id, &self.lobby_id.id);
+            return;
+        }
 
         let multi_sessions = self.is_multiple_session(&msg.user_data.user_session_id);
         if multi_sessions {
@@ -903,15 +2401,7 @@ impl Handler<WebsocketDisconnect> for Lobby {
         if !multi_sessions {
             let user_session = msg.user_data.user_session_id.clone();
             self.connected_user_session.remove(&user_session);
-
-            // ! NEED TO BE REMOVED AFTER GAME CAN SWITCH TO OTHER STATES
-
-            if !self.game_state.open() {
-                println!("Session {:?} has been removed from the lobby={:?}.", msg.user_data.user_session_id.id, &self.lobby_id.id);
-                self.allowed_user_session.remove(&user_session);
-            } else {
-                println!("Session {:?} has been disconnected from the lobby={:?}.", msg.user_data.user_session_id.id, &self.lobby_id.id);
-            }
+            println!("Session {:?} has been disconnected from the lobby={:?}.", msg.user_data.user_session_id.id, &self.lobby_id.id);
         }
         self.send_lobby_message( &WebsocketServerEvents::Websocket(WebsocketEvent::WebsocketDisconnected(websocket_session_id.clone())));
         if !multi_sessions {
@@ -928,6 +2418,7 @@ impl Handler<WebsocketDisconnect> for Lobby {
 #[rtype(result = "bool")]
 pub struct CanJoinLobby {
     pub user_session_id:UserSessionId,
+    pub spectator: bool,
 }
 
 
@@ -940,6 +2431,9 @@ impl Handler<CanJoinLobby> for Lobby {
         if self.game_state.open() {
             return true
         }
+        if msg.spectator {
+            return self.allow_spectators;
+        }
         self.allowed_user_session.contains(&msg.user_session_id)
     }
 }
@@ -969,13 +2463,11 @@ impl Handler<GetMediaToken> for Lobby {
             Err(_) => return MessageResult(None), 
         };
 
-        let media_player = match state.get_media_player() {
-            Some(mp) => mp,
-            None => return MessageResult(None),
-        };
+        // Images and other media without a synchronised player always show the first media.
+        let current_media = state.get_media_player().map(|mp| mp.current_media).unwrap_or(0);
 
         if let QuestionType::Media(medias) = &question.question_type {
-            if let Some(media) = medias.get(media_player.current_media) {
+            if let Some(media) = medias.get(current_media) {
                 return MessageResult(media.media_token.clone());
             }
         }
@@ -1090,34 +2582,55 @@ impl Handler<BeforeMedia> for Lobby {
 #[rtype(result = "()")]
 pub struct BuzzerClicked{
     pub user_session_id: UserSessionId,
+    pub websocket_session_id: Option<WebsocketSessionId>,
     pub current_time:  DateTime<Local>,
+    pub client_time: Option<f64>,
 }
 impl Handler<BuzzerClicked> for Lobby {
     type Result = ();
 
     fn handle(&mut self, msg: BuzzerClicked,ctx: &mut Self::Context) -> Self::Result {
+        if !self.game_state.accepts_board_actions() || self.is_spectator(&msg.user_session_id) {
+            return;
+        }
+        if self.early_buzz_lockout(&msg.user_session_id, msg.current_time) {
+            println!("User {:?} is locked out of buzzing", msg.user_session_id.id);
+            return;
+        }
+        let buzz_time = self.buzz_time(&msg.websocket_session_id, msg.current_time, msg.client_time);
         let mut buzzer_state = self.jeopardy_board.buzzer_state.lock().expect("Failed to lock buzzer_state");
+        let mut first_buzz = false;
 
         if let BuzzerState::BuzzerOpen(ref mut state_map) = *buzzer_state {
             if state_map.contains_key(&msg.user_session_id) {
                 println!("User {:?} has already buzzed", msg.user_session_id.id);
                 return;
             }
-            let grace_period = chrono::Duration::seconds(2);
-            let grace_period2 = Duration::from_secs(2);
+            if let Some(team_id) = self.user_data.get(&msg.user_session_id).and_then(|data| data.team_id.as_ref()) {
+                let team_buzzed = state_map.keys().any(|user_session_id| {
+                    self.user_data.get(user_session_id).and_then(|data| data.team_id.as_ref()) == Some(team_id)
+                });
+                if team_buzzed {
+                    println!("Team of user {:?} has already buzzed", msg.user_session_id.id);
+                    return;
+                }
+            }
+            let grace_period = chrono::Duration::milliseconds(self.timers.buzz_grace_millis as i64);
+            let grace_period2 = Duration::from_millis(self.timers.buzz_grace_millis);
 
             if let Some(&first_buzz_time) = state_map.values().next() {
-                if msg.current_time.signed_duration_since(first_buzz_time) > grace_period {
+                if buzz_time.signed_duration_since(first_buzz_time) > grace_period {
                     println!(
                         "User {:?} has buzzed too late by {:?}",
                         msg.user_session_id.id,
-                        msg.current_time.signed_duration_since(first_buzz_time)
+                        buzz_time.signed_duration_since(first_buzz_time)
                     );
                     return;
                 }
             }
             let clone_user_session_id = msg.user_session_id.clone();
             if state_map.len() == 0 {
+                first_buzz = true;
                 ctx.run_later(grace_period2, move |act, t| {
                     let buzzer_state = act.jeopardy_board.buzzer_state.lock().expect("Failed to lock buzzer_state");
                     if let BuzzerState::BuzzerOpen(_) = *buzzer_state {
@@ -1129,12 +2642,16 @@ impl Handler<BuzzerClicked> for Lobby {
 
 
 
-            state_map.insert(msg.user_session_id.clone(), msg.current_time);
+            state_map.insert(msg.user_session_id.clone(), buzz_time);
             println!(
                 "User {:?} buzzed in at time {:?}",
-                msg.user_session_id.id, msg.current_time
+                msg.user_session_id.id, buzz_time
             );
         }
+        drop(buzzer_state);
+        if first_buzz {
+            self.cancel_countdown();
+        }
     }
 }
 
@@ -1148,19 +2665,31 @@ impl Handler<BuzzeringStarting> for Lobby {
     type Result = ();
 
     fn handle(&mut self, msg: BuzzeringStarting,ctx: &mut Self::Context) -> Self::Result {
+        if !self.game_state.accepts_board_actions() {
+            return;
+        }
         let mut buzzer_state = self.jeopardy_board.buzzer_state.lock().expect("Failed to lock buzzer_state");
 
-        match *buzzer_state {
+        let opened = match *buzzer_state {
             BuzzerState::BuzzerOpen(_) => {
                 println!("Buzzer is already open");
+                false
             }
             BuzzerState::BuzzerdClosed(_) | BuzzerState::None => {
                 *buzzer_state = BuzzerState::BuzzerOpen(HashMap::new());
-                let event = WebsocketServerEvents::Board(BoardEvent::BuzzeringStarting);
-                self.send_lobby_message(&event);
-                println!("Buzzer has been opened");
+                true
             } 
 
+        };
+        drop(buzzer_state);
+        if opened {
+            let event = WebsocketServerEvents::Board(BoardEvent::BuzzeringStarting);
+            self.send_lobby_message(&event);
+            println!("Buzzer has been opened");
+            self.cancel_countdown();
+            if let Some(secs) = self.timers.buzz_window_secs {
+                self.start_countdown(ctx, CountdownKind::Buzzer, None, secs);
+            }
         }
 
     }
@@ -1175,17 +2704,25 @@ pub struct BuzzeringStopped {
 impl Handler<BuzzeringStopped> for Lobby {
     type Result = ();
 
-    fn handle(&mut self, msg: BuzzeringStopped, _: &mut Self::Context) -> Self::Result {
+    fn handle(&mut self, msg: BuzzeringStopped, ctx: &mut Self::Context) -> Self::Result {
         let mut buzzer_state = self.jeopardy_board.buzzer_state.lock().expect("Failed to lock buzzer_state");
         if let BuzzerState::BuzzerOpen(ref mut state_map) = *buzzer_state {
             if state_map.len() == 0 {
                 return;
             }
             let sorted_vec: Vec<UserSessionId> = state_map.iter().sorted_by(|a, b| a.1.cmp(b.1)).map(|(k, _)| k.clone()).collect();
+            if let Some(first_buzz) = state_map.values().min().cloned() {
+                self.current_buzzes.extend(sorted_vec.iter().map(|user_session_id| DTOBuzz {
+                    user_session_id: user_session_id.clone(),
+                    delay: state_map[user_session_id].signed_duration_since(first_buzz).num_milliseconds(),
+                }));
+            }
             *buzzer_state = BuzzerState::BuzzerdClosed(sorted_vec.clone());
             let event = WebsocketServerEvents::Board(BoardEvent::BuzzeringClosed(sorted_vec));
             self.send_lobby_message(&event);
             println!("Buzzer has been closed");
+            drop(buzzer_state);
+            self.start_answer_timer(ctx);
         }
     }
 }
@@ -1202,6 +2739,8 @@ impl Handler<BuzzerReset> for Lobby {
     fn handle(&mut self, msg: BuzzerReset, _: &mut Self::Context) -> Self::Result {
         let mut buzzer_state = self.jeopardy_board.buzzer_state.lock().expect("Failed to lock buzzer_state");
         *buzzer_state = BuzzerState::None;
+        drop(buzzer_state);
+        self.cancel_countdown();
         println!("Buzzer has been reset");
     }
 }
@@ -1214,6 +2753,36 @@ impl Handler<BuzzerReset> for Lobby {
 
 
 
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct SaveLobby;
+
+
+impl Handler<SaveLobby> for Lobby {
+    type Result = ResponseActFuture<Self, ()>;
+
+    fn handle(&mut self, _: SaveLobby, _: &mut Self::Context) -> Self::Result {
+        let db: Arc<MongoServer> = self.starting_services.mongo_server.clone();
+        let snapshot = self.snapshot();
+        Box::pin(
+            async move {
+                // an ended game is not restored, its results are stored by `FinishGame`
+                if snapshot.game_state == GameState::End {
+                    if !db.delete_lobby(&snapshot.lobby_id).await {
+                        println!("Failed to delete lobby={:?}", snapshot.lobby_id.id);
+                    }
+                    return;
+                }
+                if !db.save_lobby(&snapshot).await {
+                    println!("Failed to save lobby={:?}", snapshot.lobby_id.id);
+                }
+        }.into_actor(self))
+    }
+}
+
+
+
+
 pub async fn get_session(db: &Arc<MongoServer>, user_session_id: &UserSessionId) -> Option<UserSession> {
     db.find_user_session(&user_session_id).await
 }
@@ -1235,7 +2804,7 @@ pub async fn get_dto_sesion(db: &Arc<MongoServer>, user_session: &UserSession, d
         None => false,
         Some(discord_id) => db.is_admin(&discord_id).await,
     };
-    user_session.clone().dto(&data.score, is_admin)
+    user_session.clone().dto(&data.score, data.team_id.clone(), is_admin)
 }
 
 
@@ -1248,7 +2817,7 @@ pub async fn get_dto_sessions(db: &Arc<MongoServer>, user_sessions:Vec<UserSessi
             Some(discord_id) => admin.iter().any(|admin| admin.discord_id.eq(&discord_id)),
         };
         let data = data.get(&user_session.user_session_id).expect("User session not found");
-        let dto = user_session.dto(&data.score, is_admin);
+        let dto = user_session.dto(&data.score, data.team_id.clone(), is_admin);
         sessions.push(dto);
     }
     sessions
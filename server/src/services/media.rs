@@ -0,0 +1,393 @@
+use std::collections::VecDeque;
+use std::hash::Hasher;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+
+use actix::{Actor, ActorFutureExt, AsyncContext, Context, Handler, Message, WrapFuture};
+use bson::oid::ObjectId;
+use cult_common::dto::file::{MediaInfo, MediaProcessing, MediaVariant, VariantKind};
+use cult_common::wasm_lib::hashs::validate::ValidateHash;
+use cult_common::wasm_lib::NumberScope;
+use flate2::write::{DeflateDecoder, DeflateEncoder};
+use flate2::Compression;
+use futures::StreamExt;
+use mongodb::bson::doc;
+use serde_json::Value;
+use tokio::fs::File;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::process::Command;
+use twox_hash::XxHash64;
+
+use crate::data::FileData;
+use crate::services::game::FileMetadata;
+use crate::services::storage::MediaStorage;
+use crate::settings::{MediaProcessingSettings, Settings};
+
+/// Longest a single ffmpeg or ffprobe run may take before the job gives up.
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+const COPY_SIZE: usize = 256 * 1024;
+
+
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct ProcessMedia {
+    pub file_name: String,
+}
+
+
+/// Probes uploaded media and stores the variants made from it. Jobs run one after another,
+/// so a burst of uploads never starts more than one ffmpeg at a time.
+#[derive(Debug)]
+pub struct MediaProcessor {
+    storage: Arc<dyn MediaStorage>,
+    settings: Arc<Settings>,
+    queue: VecDeque<String>,
+    busy: bool,
+    available: bool,
+}
+
+impl MediaProcessor {
+
+    pub async fn new(storage: Arc<dyn MediaStorage>, settings: Arc<Settings>) -> Self {
+        let available = settings.media_processing.enabled && tools_available(&settings.media_processing).await;
+        MediaProcessor {
+            storage,
+            settings,
+            queue: VecDeque::new(),
+            busy: false,
+            available,
+        }
+    }
+
+    fn enqueue(&mut self, file_name: String) {
+        if !self.queue.contains(&file_name) {
+            self.queue.push_back(file_name);
+        }
+    }
+
+    fn next_job(&mut self, ctx: &mut Context<Self>) {
+        if self.busy {
+            return;
+        }
+        let file_name = match self.queue.pop_front() {
+            Some(data) => data,
+            None => return,
+        };
+        self.busy = true;
+        let storage = self.storage.clone();
+        let settings = self.settings.clone();
+        ctx.spawn(
+            async move {
+                process_media(storage.as_ref(), &settings.media_processing, &file_name).await
+            }
+            .into_actor(self)
+            .map(|_, act, ctx| {
+                act.busy = false;
+                act.next_job(ctx);
+            })
+        );
+    }
+}
+
+
+impl Actor for MediaProcessor {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        if !self.available {
+            return;
+        }
+        // files uploaded while the server was down or before processing existed
+        let storage = self.storage.clone();
+        ctx.spawn(
+            async move {
+                storage.list_files(doc! {"metadata.processing": null, "metadata.variant": null}, 0, 0).await.unwrap_or_default()
+            }
+            .into_actor(self)
+            .map(|files, act, ctx| {
+                for file_data in files {
+                    act.enqueue(file_data.file_name);
+                }
+                act.next_job(ctx);
+            })
+        );
+    }
+}
+
+impl Handler<ProcessMedia> for MediaProcessor {
+    type Result = ();
+
+    fn handle(&mut self, msg: ProcessMedia, ctx: &mut Self::Context) -> Self::Result {
+        if !self.available {
+            return;
+        }
+        self.enqueue(msg.file_name);
+        self.next_job(ctx);
+    }
+}
+
+
+async fn tools_available(settings: &MediaProcessingSettings) -> bool {
+    for tool in [&settings.ffmpeg, &settings.ffprobe] {
+        match Command::new(tool).arg("-version").stdout(Stdio::null()).stderr(Stdio::null()).status().await {
+            Ok(status) if status.success() => {}
+            _ => {
+                println!("{} is not available, uploaded media is not processed", tool);
+                return false;
+            }
+        }
+    }
+    true
+}
+
+async fn process_media(storage: &dyn MediaStorage, settings: &MediaProcessingSettings, file_name: &str) {
+    let file_data = match storage.find_file(file_name).await {
+        Some(data) => data,
+        None => return,
+    };
+    let metadata = match &file_data.metadata {
+        Some(data) if data.variant.is_none() => data.clone(),
+        _ => return,
+    };
+    println!("Processing media {}", file_name);
+
+    let work_dir = std::env::temp_dir().join("cult-media").join(ObjectId::new().to_hex());
+    let processing = match create_variants(storage, settings, &file_data, &metadata, &work_dir).await {
+        Ok(data) => data,
+        Err(err) => MediaProcessing { error: Some(err), ..MediaProcessing::default() },
+    };
+    if let Err(err) = tokio::fs::remove_dir_all(&work_dir).await {
+        println!("Error removing {:?} {:?}", work_dir, err);
+    }
+    if let Some(err) = &processing.error {
+        println!("Error processing media {} {}", file_name, err);
+    }
+    storage.set_processing(&file_data, &processing).await;
+}
+
+
+/// A file ffmpeg makes from the upload, `input_args` go before the input so seeking stays fast.
+struct VariantJob {
+    kind: VariantKind,
+    extension: &'static str,
+    file_type: &'static str,
+    input_args: Vec<String>,
+    output_args: Vec<String>,
+}
+
+fn plan_variants(settings: &MediaProcessingSettings, file_type: &str, info: &MediaInfo) -> Vec<VariantJob> {
+    let is_video = file_type.starts_with("video/");
+    let is_audio = file_type.starts_with("audio/");
+    let seek = match info.duration_ms {
+        // a frame a bit into the video, the first one is often black
+        Some(duration) if is_video => vec!["-ss".to_string(), format!("{:.3}", (duration / 10).min(10_000) as f64 / 1000.0)],
+        _ => Vec::new(),
+    };
+
+    let mut jobs = Vec::new();
+    // images, videos and audio with cover art all have a picture stream
+    if info.width.is_some() {
+        jobs.push(VariantJob {
+            kind: VariantKind::Thumbnail,
+            extension: "jpg",
+            file_type: "image/jpeg",
+            input_args: seek.clone(),
+            output_args: vec!["-frames:v".into(), "1".into(), "-vf".into(), format!("scale='min({},iw)':-2", settings.thumbnail_width), "-q:v".into(), "4".into()],
+        });
+    }
+    if is_video && info.width.is_some() {
+        jobs.push(VariantJob {
+            kind: VariantKind::Poster,
+            extension: "jpg",
+            file_type: "image/jpeg",
+            input_args: seek,
+            output_args: vec!["-frames:v".into(), "1".into(), "-q:v".into(), "2".into()],
+        });
+    }
+    if !settings.transcode {
+        return jobs;
+    }
+
+    let audio_playable = matches!(info.audio_codec.as_deref(), None | Some("aac") | Some("mp3"));
+    if is_video && info.video_codec.is_some() {
+        let playable = info.video_codec.as_deref() == Some("h264") && audio_playable && info.format.as_deref().is_some_and(|format| format.contains("mp4"));
+        if !playable {
+            jobs.push(VariantJob {
+                kind: VariantKind::Web,
+                extension: "mp4",
+                file_type: "video/mp4",
+                input_args: Vec::new(),
+                output_args: [
+                    "-map", "0:v:0", "-map", "0:a:0?",
+                    "-c:v", "libx264", "-preset", "veryfast", "-crf", "23", "-pix_fmt", "yuv420p",
+                    "-vf", "scale=trunc(iw/2)*2:trunc(ih/2)*2",
+                    "-c:a", "aac", "-b:a", "160k", "-movflags", "+faststart",
+                ].iter().map(|arg| arg.to_string()).collect(),
+            });
+        }
+    } else if is_audio && info.audio_codec.is_some() && !audio_playable {
+        jobs.push(VariantJob {
+            kind: VariantKind::Web,
+            extension: "m4a",
+            file_type: "audio/mp4",
+            input_args: Vec::new(),
+            output_args: ["-map", "0:a:0", "-c:a", "aac", "-b:a", "192k", "-movflags", "+faststart"].iter().map(|arg| arg.to_string()).collect(),
+        });
+    }
+    jobs
+}
+
+/// Probes the file and stores its variants. A variant that fails is reported in `error`, the others are still kept.
+async fn create_variants(storage: &dyn MediaStorage, settings: &MediaProcessingSettings, file_data: &FileData, metadata: &FileMetadata, work_dir: &Path) -> Result<MediaProcessing, String> {
+    tokio::fs::create_dir_all(work_dir).await.map_err(|err| err.to_string())?;
+    let input = work_dir.join("input");
+    copy_to_path(storage, file_data, &input).await?;
+
+    let mut command = Command::new(&settings.ffprobe);
+    command.args(["-v", "error", "-print_format", "json", "-show_format", "-show_streams"]).arg(&input);
+    let probe: Value = serde_json::from_slice(&run(command).await?).map_err(|err| format!("Invalid ffprobe output {}", err))?;
+    let info = parse_probe(&probe);
+
+    let mut processing = MediaProcessing { info: Some(info.clone()), ..MediaProcessing::default() };
+    for job in plan_variants(settings, &metadata.file_type, &info) {
+        let output = work_dir.join(format!("{}.{}", job.kind.name(), job.extension));
+        let mut command = Command::new(&settings.ffmpeg);
+        command.args(["-v", "error", "-y"])
+            .args(&job.input_args)
+            .arg("-i").arg(&input)
+            .args(&job.output_args)
+            .arg(&output);
+        let variant = match run(command).await {
+            Ok(_) => store_variant(storage, file_data, metadata, &job, &output).await,
+            Err(err) => Err(err),
+        };
+        match variant {
+            Ok(variant) => processing.variants.push(variant),
+            Err(err) => processing.error = Some(format!("{} {}", job.kind.name(), err)),
+        }
+    }
+    Ok(processing)
+}
+
+fn parse_probe(probe: &Value) -> MediaInfo {
+    let streams = probe["streams"].as_array().cloned().unwrap_or_default();
+    let video = streams.iter().find(|stream| stream["codec_type"] == "video");
+    let audio = streams.iter().find(|stream| stream["codec_type"] == "audio");
+    MediaInfo {
+        duration_ms: probe["format"]["duration"].as_str()
+            .and_then(|duration| duration.parse::<f64>().ok())
+            .filter(|duration| duration.is_finite() && *duration > 0.0)
+            .map(|duration| (duration * 1000.0).round() as u64),
+        width: video.and_then(|stream| stream["width"].as_u64()).map(|width| width as u32),
+        height: video.and_then(|stream| stream["height"].as_u64()).map(|height| height as u32),
+        format: probe["format"]["format_name"].as_str().map(str::to_string),
+        video_codec: video.and_then(|stream| stream["codec_name"].as_str()).map(str::to_string),
+        audio_codec: audio.and_then(|stream| stream["codec_name"].as_str()).map(str::to_string),
+    }
+}
+
+/// Runs the command and returns its stdout, stderr becomes the error when it fails.
+async fn run(mut command: Command) -> Result<Vec<u8>, String> {
+    let program = command.as_std().get_program().to_string_lossy().to_string();
+    command.stdin(Stdio::null()).kill_on_drop(true);
+    let output = match tokio::time::timeout(COMMAND_TIMEOUT, command.output()).await {
+        Err(_) => return Err(format!("{} timed out", program)),
+        Ok(Err(err)) => return Err(format!("{} can´t run {}", program, err)),
+        Ok(Ok(output)) => output,
+    };
+    if !output.status.success() {
+        return Err(format!("{} failed {}", program, String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(output.stdout)
+}
+
+/// ffmpeg needs a seekable input, so the file is copied out of the storage first.
+/// Media is stored raw deflated as the frontend uploads it, so it is inflated on the way.
+async fn copy_to_path(storage: &dyn MediaStorage, file_data: &FileData, path: &PathBuf) -> Result<(), String> {
+    let mut file = File::create(path).await.map_err(|err| err.to_string())?;
+    let mut decoder = DeflateDecoder::new(Vec::new());
+    if file_data.length > 0 {
+        let mut stream = match storage.open_range(file_data, NumberScope::new(0, file_data.length - 1)).await {
+            Some(data) => data,
+            None => return Err("Can´t read file".to_string()),
+        };
+        while let Some(part) = stream.next().await {
+            let part = part.map_err(|err| err.to_string())?;
+            decoder.write_all(&part).map_err(|err| format!("Can´t inflate file {}", err))?;
+            file.write_all(decoder.get_ref()).await.map_err(|err| err.to_string())?;
+            decoder.get_mut().clear();
+        }
+    }
+    let rest = decoder.finish().map_err(|err| format!("Can´t inflate file {}", err))?;
+    file.write_all(&rest).await.map_err(|err| err.to_string())?;
+    file.flush().await.map_err(|err| err.to_string())
+}
+
+async fn store_variant(storage: &dyn MediaStorage, original: &FileData, metadata: &FileMetadata, job: &VariantJob, path: &Path) -> Result<MediaVariant, String> {
+    let file_name = format!("{}.{}.{}", original.file_name, job.kind.name(), job.extension);
+    if let Some(existing) = storage.find_file(&file_name).await {
+        // left over from an earlier run for the same file, a file that was renamed away keeps its own variants
+        if existing.metadata.as_ref().is_some_and(|data| data.variant == Some(job.kind) && data.source.is_some() && data.source == original.id) {
+            storage.delete_file(&existing).await;
+        } else {
+            return Err(format!("{} already exists", file_name));
+        }
+    }
+
+    let mut file = File::open(path).await.map_err(|err| err.to_string())?;
+    let mut writer = match storage.open_writer(&file_name).await {
+        Some(writer) => writer,
+        None => return Err(format!("Can´t store {}", file_name)),
+    };
+    // stored deflated like uploaded media, so clients inflate every download the same way
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+    let mut hasher = XxHash64::with_seed(0);
+    let mut buf = vec![0; COPY_SIZE];
+    loop {
+        let read = match file.read(&mut buf).await {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(err) => {
+                writer.abort().await;
+                return Err(err.to_string());
+            }
+        };
+        if let Err(err) = encoder.write_all(&buf[..read]) {
+            writer.abort().await;
+            return Err(err.to_string());
+        }
+        hasher.write(encoder.get_ref());
+        if let Err(err) = writer.write(encoder.get_ref()).await {
+            writer.abort().await;
+            return Err(err.to_string());
+        }
+        encoder.get_mut().clear();
+    }
+    let rest = match encoder.finish() {
+        Ok(data) => data,
+        Err(err) => {
+            writer.abort().await;
+            return Err(err.to_string());
+        }
+    };
+    hasher.write(&rest);
+    if let Err(err) = writer.write(&rest).await {
+        writer.abort().await;
+        return Err(err.to_string());
+    }
+
+    let mut variant_metadata = FileMetadata::new(job.file_type.to_string(), ValidateHash::new(hasher.finish().to_string()), metadata.uploader.clone());
+    variant_metadata.variant = Some(job.kind);
+    variant_metadata.source = original.id;
+    if !writer.finish(variant_metadata).await {
+        return Err(format!("Can´t store {}", file_name));
+    }
+    Ok(MediaVariant {
+        kind: job.kind,
+        file_name,
+        file_type: job.file_type.to_string(),
+    })
+}
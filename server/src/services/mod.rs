@@ -10,6 +10,8 @@ use crate::authentication::discord::{GrantDiscordAuth, LoginDiscordAuth};
 use crate::services::authentication::AuthenticationServer;
 use crate::services::db::MongoServer;
 use crate::services::game::GameServer;
+use crate::services::media::MediaProcessor;
+use crate::services::storage::{create_storage, MediaStorage};
 use crate::settings::Settings;
 
 pub(crate) mod game;
@@ -17,6 +19,9 @@ pub(crate) mod input;
 pub(crate) mod authentication;
 pub(crate) mod db;
 pub(crate) mod lobby;
+pub(crate) mod archive;
+pub(crate) mod storage;
+pub(crate) mod media;
 
 
 #[derive(Clone, Debug)]
@@ -25,6 +30,7 @@ pub(crate) struct StartingServices{
     pub grant_client : Arc<GrantDiscordAuth>,
     pub login_client :Arc<LoginDiscordAuth>,
     pub mongo_server : Arc<MongoServer>,
+    pub media_storage : Arc<dyn MediaStorage>,
 }
 
 #[derive(Clone, Debug)]
@@ -34,6 +40,8 @@ pub(crate) struct Services {
     pub grant_client : Arc<GrantDiscordAuth>,
     pub login_client :Arc<LoginDiscordAuth>,
     pub mongo_server : Arc<MongoServer>,
+    pub media_storage : Arc<dyn MediaStorage>,
+    pub media_processor: Addr<MediaProcessor>,
 }
 
 
@@ -41,6 +49,10 @@ impl Services {
     pub async fn init(settings:&Arc<Settings>) -> Self {
 
         let mongo_server = Arc::new(MongoServer::new(settings).await);
+        let media_storage = create_storage(&settings.media_storage.backend, &settings.media_storage, &mongo_server).await;
+        println!("Storing media with the {:?} backend", media_storage.backend());
+        let media_processor = MediaProcessor::new(media_storage.clone(), settings.clone()).await.start();
+        let lobby_snapshots = mongo_server.get_lobbies().await;
         let login_client = Arc::new(LoginDiscordAuth::init(settings));
         
         let auth_server = AuthenticationServer::new(mongo_server.clone()).start();
@@ -53,13 +65,16 @@ impl Services {
             grant_client: discord_auth.clone(),
             login_client: login_client.clone(),
             mongo_server: mongo_server.clone()  ,
+            media_storage: media_storage.clone(),
         });
         Services {
             authentication_server: auth_server,
-            game_server: GameServer::new(services).start(),
+            game_server: GameServer::new(services, lobby_snapshots).start(),
             grant_client: discord_auth,
             login_client,
             mongo_server,
+            media_storage,
+            media_processor,
         }
     }
 
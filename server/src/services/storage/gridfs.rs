@@ -0,0 +1,159 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use cult_common::wasm_lib::NumberScope;
+use futures::stream::BoxStream;
+use futures::{AsyncWriteExt, StreamExt};
+use mongodb::bson::doc;
+use mongodb::gridfs::{GridFsBucket, GridFsUploadStream};
+use mongodb::options::{GridFsBucketOptions, WriteConcern};
+use mongodb::{Collection, IndexModel};
+
+use crate::data::{FileChunk, FileData};
+use crate::services::db::MongoServer;
+use crate::services::game::FileMetadata;
+use crate::settings::StorageBackend;
+
+use super::{MediaStorage, MediaWriter};
+
+
+/// Media kept in the `FileBucket` GridFS bucket of the database.
+#[derive(Debug, Clone)]
+pub struct GridFsStorage {
+    pub bucket: GridFsBucket,
+    pub files: Collection<FileData>,
+    pub chunks: Collection<FileChunk>,
+}
+
+impl GridFsStorage {
+
+    pub async fn new(db: &MongoServer) -> Self {
+        let wc = WriteConcern::builder().w_timeout(Duration::new(5, 0)).build();
+        let opts = GridFsBucketOptions::builder()
+            .bucket_name("FileBucket".to_string())
+            .write_concern(wc)
+            .build();
+        let database = db.database();
+        let storage = GridFsStorage {
+            bucket: database.gridfs_bucket(opts),
+            files: database.collection("FileBucket.files"),
+            chunks: database.collection("FileBucket.chunks"),
+        };
+        //filename
+        storage.files.create_index(IndexModel::builder().keys(doc! {"filename": 1}).build()).await.expect("Failed to create index");
+        //files_id
+        storage.chunks.create_index(IndexModel::builder().keys(doc! {"files_id": 1}).build()).await.expect("Failed to create index");
+        storage
+    }
+}
+
+fn slice_chunk(chunk: &FileChunk, chunk_size: usize, range: &NumberScope) -> Option<Bytes> {
+    if chunk.data.is_empty() {
+        return None;
+    }
+    let chunk_start = chunk.n * chunk_size;
+    let chunk_range = NumberScope::new(chunk_start, chunk_start + chunk.data.len() - 1);
+    range.intersection(&chunk_range).map(|part| chunk.data.slice(part.start - chunk_start..=part.end - chunk_start))
+}
+
+#[async_trait]
+impl MediaStorage for GridFsStorage {
+
+    fn backend(&self) -> StorageBackend {
+        StorageBackend::GridFs
+    }
+
+    fn files(&self) -> &Collection<FileData> {
+        &self.files
+    }
+
+    async fn open_writer(&self, file_name: &str) -> Option<Box<dyn MediaWriter>> {
+        match self.bucket.open_upload_stream(file_name).await {
+            Ok(stream) => Some(Box::new(GridFsWriter { stream, bucket: self.bucket.clone(), files: self.files.clone(), file_name: file_name.to_string() })),
+            Err(err) => {
+                println!("Error opening upload stream {} {:?}", file_name, err);
+                None
+            }
+        }
+    }
+
+    async fn open_range(&self, file_data: &FileData, range: NumberScope) -> Option<BoxStream<'static, std::io::Result<Bytes>>> {
+        let id = file_data.id?;
+        let chunk_size = file_data.chunk_size;
+        let chunk_range = file_data.get_chunk_range(range.clone());
+        let cursor = match self.chunks.find(doc! {"files_id": id, "n": { "$gte": chunk_range.start as i64, "$lte": chunk_range.end as i64}}).sort(doc! {"n": 1}).await {
+            Ok(cursor) => cursor,
+            Err(err) => {
+                println!("Error getting chunks {:?}", err);
+                return None;
+            }
+        };
+        let stream = cursor.filter_map(move |chunk| {
+            let part = match chunk {
+                Ok(chunk) => slice_chunk(&chunk, chunk_size, &range).map(Ok),
+                Err(err) => Some(Err(std::io::Error::other(err))),
+            };
+            async move { part }
+        });
+        Some(stream.boxed())
+    }
+
+    async fn delete_file(&self, file_data: &FileData) -> bool {
+        let id = match file_data.id {
+            Some(id) => id,
+            None => return false,
+        };
+        match self.bucket.delete(id.into()).await {
+            Err(err) => {
+                println!("Error deleting file {} {:?}", file_data.file_name, err);
+                false
+            }
+            Ok(_) => true,
+        }
+    }
+}
+
+
+struct GridFsWriter {
+    stream: GridFsUploadStream,
+    bucket: GridFsBucket,
+    files: Collection<FileData>,
+    file_name: String,
+}
+
+#[async_trait]
+impl MediaWriter for GridFsWriter {
+
+    async fn write(&mut self, data: &[u8]) -> std::io::Result<()> {
+        self.stream.write_all(data).await
+    }
+
+    async fn finish(mut self: Box<Self>, metadata: FileMetadata) -> bool {
+        let id = self.stream.id().clone();
+        if let Err(err) = self.stream.close().await {
+            println!("Error closing upload stream {} {:?}", self.file_name, err);
+            return false;
+        }
+        let stored = match self.files.update_one(doc! {"_id": id.clone()}, doc! {"$set": {"metadata": metadata}}).await {
+            Err(err) => {
+                println!("Error updating file metadata {} {:?}", self.file_name, err);
+                false
+            }
+            Ok(update) => update.matched_count > 0,
+        };
+        // a file without metadata would block the name, so it is removed again
+        if !stored {
+            if let Err(err) = self.bucket.delete(id).await {
+                println!("Error deleting file without metadata {} {:?}", self.file_name, err);
+            }
+        }
+        stored
+    }
+
+    async fn abort(mut self: Box<Self>) {
+        if let Err(err) = self.stream.abort().await {
+            println!("Error aborting upload stream {} {:?}", self.file_name, err);
+        }
+    }
+}
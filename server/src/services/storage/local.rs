@@ -0,0 +1,190 @@
+use std::io::SeekFrom;
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use bson::oid::ObjectId;
+use bson::DateTime;
+use bytes::Bytes;
+use cult_common::wasm_lib::NumberScope;
+use futures::stream::{self, BoxStream};
+use futures::StreamExt;
+use mongodb::bson::doc;
+use mongodb::options::IndexOptions;
+use mongodb::{Collection, IndexModel};
+use tokio::fs::File;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+
+use crate::data::FileData;
+use crate::services::db::MongoServer;
+use crate::services::game::FileMetadata;
+use crate::settings::StorageBackend;
+
+use super::{MediaStorage, MediaWriter};
+
+/// Size of the pieces a file is streamed in, also stored as the chunk size of the file.
+const READ_SIZE: usize = 256 * 1024;
+
+
+/// Media kept as plain files in a directory, for example one mounted from a NAS.
+/// Files are named by their id, the file index lives in the `LocalFiles` collection.
+#[derive(Debug, Clone)]
+pub struct LocalStorage {
+    pub root: PathBuf,
+    pub files: Collection<FileData>,
+}
+
+impl LocalStorage {
+
+    pub async fn new(path: &str, db: &MongoServer) -> Self {
+        let root = PathBuf::from(path);
+        tokio::fs::create_dir_all(&root).await.expect("Can´t create media directory");
+        let storage = LocalStorage {
+            root,
+            files: db.database().collection("LocalFiles"),
+        };
+        storage.files.create_index(IndexModel::builder().keys(doc! {"filename": 1}).options(IndexOptions::builder().unique(true).build()).build()).await.expect("Failed to create index");
+        storage.files.create_index(IndexModel::builder().keys(doc! {"metadata.validate_hash.hash": 1}).build()).await.expect("Failed to create index");
+        storage
+    }
+
+    fn path_of(&self, id: &ObjectId) -> PathBuf {
+        self.root.join(id.to_hex())
+    }
+}
+
+#[async_trait]
+impl MediaStorage for LocalStorage {
+
+    fn backend(&self) -> StorageBackend {
+        StorageBackend::Local
+    }
+
+    fn files(&self) -> &Collection<FileData> {
+        &self.files
+    }
+
+    async fn open_writer(&self, file_name: &str) -> Option<Box<dyn MediaWriter>> {
+        let id = ObjectId::new();
+        let path = self.path_of(&id);
+        let part_path = path.with_extension("part");
+        match File::create(&part_path).await {
+            Ok(file) => Some(Box::new(LocalWriter {
+                id,
+                file,
+                path,
+                part_path,
+                length: 0,
+                file_name: file_name.to_string(),
+                files: self.files.clone(),
+            })),
+            Err(err) => {
+                println!("Error creating file {:?} {:?}", part_path, err);
+                None
+            }
+        }
+    }
+
+    async fn open_range(&self, file_data: &FileData, range: NumberScope) -> Option<BoxStream<'static, std::io::Result<Bytes>>> {
+        let path = self.path_of(&file_data.id?);
+        let mut file = match File::open(&path).await {
+            Ok(file) => file,
+            Err(err) => {
+                println!("Error opening file {:?} {:?}", path, err);
+                return None;
+            }
+        };
+        if let Err(err) = file.seek(SeekFrom::Start(range.start as u64)).await {
+            println!("Error seeking file {:?} {:?}", path, err);
+            return None;
+        }
+        let remaining = range.end - range.start + 1;
+        let stream = stream::try_unfold((file, remaining), |(mut file, remaining)| async move {
+            if remaining == 0 {
+                return Ok(None);
+            }
+            let mut buf = vec![0; remaining.min(READ_SIZE)];
+            let read = file.read(&mut buf).await?;
+            if read == 0 {
+                return Err(std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "File is shorter than its index entry"));
+            }
+            buf.truncate(read);
+            Ok(Some((Bytes::from(buf), (file, remaining - read))))
+        });
+        Some(stream.boxed())
+    }
+
+    async fn delete_file(&self, file_data: &FileData) -> bool {
+        let id = match file_data.id {
+            Some(id) => id,
+            None => return false,
+        };
+        // the index entry goes first, so the file is never listed without its content
+        match self.files.delete_one(doc! {"_id": id}).await {
+            Err(err) => {
+                println!("Error deleting file index {} {:?}", file_data.file_name, err);
+                return false;
+            }
+            Ok(delete) if delete.deleted_count == 0 => return false,
+            Ok(_) => {}
+        }
+        if let Err(err) = tokio::fs::remove_file(self.path_of(&id)).await {
+            println!("Error deleting file {:?} {:?}", self.path_of(&id), err);
+        }
+        true
+    }
+}
+
+
+struct LocalWriter {
+    id: ObjectId,
+    file: File,
+    path: PathBuf,
+    part_path: PathBuf,
+    length: usize,
+    file_name: String,
+    files: Collection<FileData>,
+}
+
+#[async_trait]
+impl MediaWriter for LocalWriter {
+
+    async fn write(&mut self, data: &[u8]) -> std::io::Result<()> {
+        self.file.write_all(data).await?;
+        self.length += data.len();
+        Ok(())
+    }
+
+    async fn finish(mut self: Box<Self>, metadata: FileMetadata) -> bool {
+        if let Err(err) = self.file.sync_all().await {
+            println!("Error writing file {:?} {:?}", self.part_path, err);
+            self.abort().await;
+            return false;
+        }
+        if let Err(err) = tokio::fs::rename(&self.part_path, &self.path).await {
+            println!("Error moving file {:?} {:?}", self.part_path, err);
+            self.abort().await;
+            return false;
+        }
+        let file_data = FileData {
+            id: Some(self.id),
+            length: self.length,
+            chunk_size: READ_SIZE,
+            upload_date: DateTime::now(),
+            file_name: self.file_name.clone(),
+            metadata: Some(metadata),
+        };
+        // the unique index on the name rejects a file that was stored in the meantime
+        if let Err(err) = self.files.insert_one(&file_data).await {
+            println!("Error storing file index {} {:?}", self.file_name, err);
+            let _ = tokio::fs::remove_file(&self.path).await;
+            return false;
+        }
+        true
+    }
+
+    async fn abort(self: Box<Self>) {
+        if let Err(err) = tokio::fs::remove_file(&self.part_path).await {
+            println!("Error removing file {:?} {:?}", self.part_path, err);
+        }
+    }
+}
@@ -0,0 +1,337 @@
+use std::fmt::Debug;
+use std::hash::Hasher;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use cult_common::dto::file::MediaProcessing;
+use cult_common::wasm_lib::hashs::filedata::FileDataHash;
+use cult_common::wasm_lib::hashs::validate::ValidateHash;
+use cult_common::wasm_lib::NumberScope;
+use futures::stream::BoxStream;
+use futures::StreamExt;
+use mongodb::bson::{doc, Document};
+use mongodb::options::FindOptions;
+use mongodb::Collection;
+use twox_hash::XxHash64;
+
+use crate::data::{FileData, UploadSessionData};
+use crate::services::db::MongoServer;
+use crate::services::game::FileMetadata;
+use crate::settings::{MediaStorageSettings, StorageBackend};
+
+pub(crate) mod gridfs;
+pub(crate) mod local;
+
+use gridfs::GridFsStorage;
+use local::LocalStorage;
+
+
+/// Receives the content of a new file piece by piece, the file only becomes visible once it is finished.
+#[async_trait]
+pub trait MediaWriter: Send {
+    async fn write(&mut self, data: &[u8]) -> std::io::Result<()>;
+
+    /// Stores the file together with its metadata, false if that failed and nothing was stored.
+    async fn finish(self: Box<Self>, metadata: FileMetadata) -> bool;
+
+    async fn abort(self: Box<Self>);
+}
+
+/// Place the media files are kept in. The file index is a `FileData` collection for every backend,
+/// so looking files up works the same and only reading and writing the content differs.
+#[async_trait]
+pub trait MediaStorage: Send + Sync + Debug {
+    fn backend(&self) -> StorageBackend;
+
+    fn files(&self) -> &Collection<FileData>;
+
+    async fn open_writer(&self, file_name: &str) -> Option<Box<dyn MediaWriter>>;
+
+    /// Streams the inclusive byte range of the file without loading the whole file.
+    async fn open_range(&self, file_data: &FileData, range: NumberScope) -> Option<BoxStream<'static, std::io::Result<Bytes>>>;
+
+    /// Removes the content and the index entry of the file.
+    async fn delete_file(&self, file_data: &FileData) -> bool;
+
+    async fn file_exists(&self, file_name: &str) -> bool {
+        match self.files().count_documents(doc! {"filename": file_name}).await {
+            Err(err) => {
+                println!("Error finding file {} {:?}", file_name, err);
+                false
+            }
+            Ok(count) => count > 0,
+        }
+    }
+
+    async fn find_file(&self, file_name: &str) -> Option<FileData> {
+        match self.files().find_one(doc! {"filename": file_name}).await {
+            Err(err) => {
+                println!("Error finding file {} {:?}", file_name, err);
+                None
+            }
+            Ok(data) => data,
+        }
+    }
+
+    async fn find_file_by_hash(&self, validate_hash: &ValidateHash) -> Option<FileData> {
+        match self.files().find_one(doc! {"metadata.validate_hash.hash": validate_hash.get_hash()}).await {
+            Err(err) => {
+                println!("Error finding file with hash {:?} {:?}", validate_hash, err);
+                None
+            }
+            Ok(data) => data,
+        }
+    }
+
+    async fn count_files(&self, filter: Document) -> u64 {
+        match self.files().count_documents(filter).await {
+            Err(err) => {
+                println!("Error counting files {:?}", err);
+                0
+            }
+            Ok(count) => count,
+        }
+    }
+
+    /// Files matching the filter, sorted by name.
+    async fn list_files(&self, filter: Document, skip: u64, limit: i64) -> Option<Vec<FileData>> {
+        let find_options = FindOptions::builder()
+            .sort(doc! { "filename": 1 })
+            .skip(skip)
+            .limit(limit)
+            .build();
+        let mut cursor = match self.files().find(filter).with_options(find_options).await {
+            Ok(cursor) => cursor,
+            Err(err) => {
+                println!("Error listing files {:?}", err);
+                return None;
+            }
+        };
+        let mut files = Vec::new();
+        while let Some(file_data) = cursor.next().await {
+            match file_data {
+                Ok(file_data) => files.push(file_data),
+                Err(err) => {
+                    println!("Error listing files {:?}", err);
+                    return None;
+                }
+            }
+        }
+        Some(files)
+    }
+
+    /// Only the index entry changes, the content is stored by id in every backend.
+    async fn rename_file(&self, file_data: &FileData, new_name: &str) -> bool {
+        let id = match file_data.id {
+            Some(id) => id,
+            None => return false,
+        };
+        match self.files().update_one(doc! {"_id": id}, doc! {"$set": {"filename": new_name}}).await {
+            Err(err) => {
+                println!("Error renaming file {} to {} {:?}", file_data.file_name, new_name, err);
+                false
+            }
+            Ok(update) => update.matched_count > 0,
+        }
+    }
+
+    async fn organize_file(&self, file_data: &FileData, tags: Vec<String>, folder: Option<String>) -> bool {
+        let id = match file_data.id {
+            Some(id) => id,
+            None => return false,
+        };
+        match self.files().update_one(doc! {"_id": id}, doc! {"$set": {"metadata.tags": tags, "metadata.folder": folder}}).await {
+            Err(err) => {
+                println!("Error organizing file {} {:?}", file_data.file_name, err);
+                false
+            }
+            Ok(update) => update.matched_count > 0,
+        }
+    }
+
+    async fn set_processing(&self, file_data: &FileData, processing: &MediaProcessing) -> bool {
+        let id = match file_data.id {
+            Some(id) => id,
+            None => return false,
+        };
+        let processing = match bson::to_bson(processing) {
+            Ok(data) => data,
+            Err(err) => {
+                println!("Error converting processing result of {} {:?}", file_data.file_name, err);
+                return false;
+            }
+        };
+        match self.files().update_one(doc! {"_id": id}, doc! {"$set": {"metadata.processing": processing}}).await {
+            Err(err) => {
+                println!("Error storing processing result of {} {:?}", file_data.file_name, err);
+                false
+            }
+            Ok(update) => update.matched_count > 0,
+        }
+    }
+
+    async fn read_file(&self, file_data: &FileData) -> Option<Vec<u8>> {
+        let mut data = Vec::with_capacity(file_data.length);
+        if file_data.length == 0 {
+            return Some(data);
+        }
+        let mut stream = self.open_range(file_data, NumberScope::new(0, file_data.length - 1)).await?;
+        while let Some(part) = stream.next().await {
+            match part {
+                Ok(part) => data.extend_from_slice(&part),
+                Err(err) => {
+                    println!("Error reading file {} {:?}", file_data.file_name, err);
+                    return None;
+                }
+            }
+        }
+        Some(data)
+    }
+
+    async fn store_file(&self, file_name: &str, data: &[u8], metadata: FileMetadata) -> bool {
+        let mut writer = match self.open_writer(file_name).await {
+            Some(writer) => writer,
+            None => return false,
+        };
+        if let Err(err) = writer.write(data).await {
+            println!("Error writing file {} {:?}", file_name, err);
+            writer.abort().await;
+            return false;
+        }
+        writer.finish(metadata).await
+    }
+}
+
+
+pub async fn create_storage(backend: &StorageBackend, settings: &MediaStorageSettings, db: &MongoServer) -> Arc<dyn MediaStorage> {
+    match backend {
+        StorageBackend::GridFs => Arc::new(GridFsStorage::new(db).await),
+        StorageBackend::Local => Arc::new(LocalStorage::new(&settings.path, db).await),
+    }
+}
+
+
+/// Writes the chunks of a complete upload in order into the storage and returns the hash computed over them.
+/// The file is only kept when the hash matches the one the upload was started with.
+pub async fn assemble_upload(storage: &dyn MediaStorage, db: &MongoServer, upload: &UploadSessionData) -> Option<FileDataHash> {
+    let mut cursor = db.get_upload_chunk_cursor(&upload.upload_id).await?;
+    let mut writer = storage.open_writer(&upload.file_name).await?;
+
+    let mut hasher = XxHash64::with_seed(0);
+    let mut expected = 0;
+    while let Some(chunk) = cursor.next().await {
+        let chunk = match chunk {
+            Ok(chunk) if chunk.index == expected => chunk,
+            result => {
+                println!("Upload chunk {} of {} is missing {:?}", expected, upload.file_name, result.err());
+                writer.abort().await;
+                return None;
+            }
+        };
+        hasher.write(&chunk.data);
+        if let Err(err) = writer.write(&chunk.data).await {
+            println!("Error writing file {} {:?}", upload.file_name, err);
+            writer.abort().await;
+            return None;
+        }
+        expected += 1;
+    }
+    if expected != upload.chunk_count() {
+        println!("Upload {} has {} of {} chunks", upload.file_name, expected, upload.chunk_count());
+        writer.abort().await;
+        return None;
+    }
+
+    let hash = FileDataHash::new(hasher.finish().to_string());
+    if !upload.validate_hash.validate_file_chunk(&hash) {
+        writer.abort().await;
+        return Some(hash);
+    }
+    if !writer.finish(upload.to_metadata()).await {
+        return None;
+    }
+    Some(hash)
+}
+
+
+#[derive(Debug, Default)]
+pub struct MigrationReport {
+    pub copied: usize,
+    pub skipped: usize,
+    pub failed: Vec<String>,
+}
+
+/// Copies every file that is missing in `to`, keeping its metadata. The content is hashed while copying
+/// and a file whose hash does not match its metadata is not stored.
+pub async fn migrate_storage(from: &dyn MediaStorage, to: &dyn MediaStorage) -> MigrationReport {
+    const PAGE_SIZE: i64 = 100;
+    let mut report = MigrationReport::default();
+    let file_count = from.count_files(doc! {}).await;
+    let mut skip = 0;
+    while skip < file_count {
+        let files = match from.list_files(doc! {}, skip, PAGE_SIZE).await {
+            Some(files) => files,
+            None => break,
+        };
+        skip += PAGE_SIZE as u64;
+        for file_data in files {
+            if to.file_exists(&file_data.file_name).await {
+                report.skipped += 1;
+                continue;
+            }
+            if copy_file(from, to, &file_data).await {
+                report.copied += 1;
+            } else {
+                report.failed.push(file_data.file_name);
+            }
+        }
+    }
+    report
+}
+
+async fn copy_file(from: &dyn MediaStorage, to: &dyn MediaStorage, file_data: &FileData) -> bool {
+    let metadata = match &file_data.metadata {
+        Some(data) => data.clone(),
+        None => return false,
+    };
+    let mut writer = match to.open_writer(&file_data.file_name).await {
+        Some(writer) => writer,
+        None => return false,
+    };
+
+    let mut hasher = XxHash64::with_seed(0);
+    if file_data.length > 0 {
+        let mut stream = match from.open_range(file_data, NumberScope::new(0, file_data.length - 1)).await {
+            Some(stream) => stream,
+            None => {
+                writer.abort().await;
+                return false;
+            }
+        };
+        while let Some(part) = stream.next().await {
+            let part = match part {
+                Ok(part) => part,
+                Err(err) => {
+                    println!("Error reading file {} {:?}", file_data.file_name, err);
+                    writer.abort().await;
+                    return false;
+                }
+            };
+            hasher.write(&part);
+            if let Err(err) = writer.write(&part).await {
+                println!("Error writing file {} {:?}", file_data.file_name, err);
+                writer.abort().await;
+                return false;
+            }
+        }
+    }
+
+    let hash = FileDataHash::new(hasher.finish().to_string());
+    if !metadata.validate_hash.validate_file_chunk(&hash) {
+        println!("Hash of {} does not match, expected {} got {}", file_data.file_name, metadata.validate_hash.get_hash(), hash.get_hash());
+        writer.abort().await;
+        return false;
+    }
+    writer.finish(metadata).await
+}
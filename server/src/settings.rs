@@ -1,3 +1,5 @@
+use std::str::FromStr;
+
 use config::{Config, ConfigError, File};
 use serde::Deserialize;
 
@@ -71,6 +73,105 @@ impl BackendSettings {
 
 
 
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackend {
+    #[default]
+    GridFs,
+    Local,
+}
+
+impl FromStr for StorageBackend {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<Self, ()> {
+        match input.to_lowercase().as_str() {
+            "gridfs" => Ok(StorageBackend::GridFs),
+            "local" => Ok(StorageBackend::Local),
+            _ => Err(()),
+        }
+    }
+}
+
+
+/// Where media files are kept, `path` is the directory used by the local backend.
+#[derive(Debug, Deserialize)]
+#[allow(unused)]
+pub struct MediaStorageSettings {
+    #[serde(default)]
+    pub backend: StorageBackend,
+    #[serde(default = "MediaStorageSettings::default_path")]
+    pub path: String,
+}
+
+impl MediaStorageSettings {
+    fn default_path() -> String {
+        "media".to_string()
+    }
+}
+
+impl Default for MediaStorageSettings {
+    fn default() -> Self {
+        MediaStorageSettings {
+            backend: StorageBackend::default(),
+            path: MediaStorageSettings::default_path(),
+        }
+    }
+}
+
+
+
+
+/// Thumbnails and probing run with the local ffmpeg after an upload, `transcode` also stores a variant
+/// browsers can play for video and audio in other codecs.
+#[derive(Debug, Deserialize)]
+#[allow(unused)]
+pub struct MediaProcessingSettings {
+    #[serde(default = "MediaProcessingSettings::default_enabled")]
+    pub enabled: bool,
+    #[serde(default = "MediaProcessingSettings::default_ffmpeg")]
+    pub ffmpeg: String,
+    #[serde(default = "MediaProcessingSettings::default_ffprobe")]
+    pub ffprobe: String,
+    #[serde(default)]
+    pub transcode: bool,
+    #[serde(default = "MediaProcessingSettings::default_thumbnail_width")]
+    pub thumbnail_width: u32,
+}
+
+impl MediaProcessingSettings {
+    fn default_enabled() -> bool {
+        true
+    }
+
+    fn default_ffmpeg() -> String {
+        "ffmpeg".to_string()
+    }
+
+    fn default_ffprobe() -> String {
+        "ffprobe".to_string()
+    }
+
+    fn default_thumbnail_width() -> u32 {
+        320
+    }
+}
+
+impl Default for MediaProcessingSettings {
+    fn default() -> Self {
+        MediaProcessingSettings {
+            enabled: MediaProcessingSettings::default_enabled(),
+            ffmpeg: MediaProcessingSettings::default_ffmpeg(),
+            ffprobe: MediaProcessingSettings::default_ffprobe(),
+            transcode: false,
+            thumbnail_width: MediaProcessingSettings::default_thumbnail_width(),
+        }
+    }
+}
+
+
+
+
 #[derive(Debug, Deserialize)]
 #[allow(unused)]
 pub struct Settings {
@@ -78,6 +179,10 @@ pub struct Settings {
     pub database: Database,
     pub discord_auth: DiscordAuth,
     pub backend_settings: BackendSettings,
+    #[serde(default)]
+    pub media_storage: MediaStorageSettings,
+    #[serde(default)]
+    pub media_processing: MediaProcessingSettings,
 }
 
 
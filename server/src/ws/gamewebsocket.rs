@@ -4,7 +4,7 @@ use actix::{Addr};
 use actix_web::{HttpRequest, HttpResponse, web};
 use actix_web_actors::ws;
 use serde_json::json;
-use crate::rest::data::{find_session, get_internal_server_error_json, get_lobby_id_from_value};
+use crate::rest::data::{find_session, get_internal_server_error_json, get_lobby_id_from_value, get_spectator_from_value};
 use crate::services::db::MongoServer;
 use crate::services::game::{self, GameServer};
 use crate::ws::session::WsSession;
@@ -34,7 +34,8 @@ pub async fn start_ws(req: HttpRequest, stream: web::Payload, db: web::Data<Arc<
     };
 
     
-    ws::start(WsSession::default(&user.user_session_id, &lobby_id, &game, &loddy_addr), &req, stream)
+    let spectator = get_spectator_from_value(&req);
+    ws::start(WsSession::default(&user.user_session_id, &lobby_id, spectator, &game, &loddy_addr), &req, stream)
 
 }
 
@@ -7,7 +7,7 @@ use mongodb::change_stream::event;
 use serde::{Deserialize, Serialize};
 
 use crate::services::game::{self};
-use crate::services::lobby::{AddLobbySessionScore, BuzzerClicked, BuzzerReset, BuzzeringStarting, Lobby, LobbyBackClick, LobbyClick, ReciveVideoEvent, SyncBackwardRequest, SyncForwardRequest, UpdateWebsocketPing, WebsocketConnect, WebsocketDisconnect};
+use crate::services::lobby::{AddLobbySessionScore, AdjustLobbySessionScore, BuzzerClicked, BuzzerReset, BuzzeringStarting, Lobby, LobbyAllowSpectators, LobbyBackClick, LobbyClick, LobbyNextRound, LobbyWrongAnswer, ReciveDailyDoubleEvent, ReciveFinalJeopardyEvent, ReciveGameStateEvent, ReciveTeamEvent, ReciveWrittenAnswerEvent, ReciveMultipleChoiceEvent, ReciveEstimationEvent, ReciveImageRevealEvent, LobbyUpdateBuzzerSettings, LobbyUpdateTimers, ReciveVideoEvent, SyncBackwardRequest, SyncForwardRequest, UpdateWebsocketPing, WebsocketConnect, WebsocketDisconnect};
 use actix_web::web;
 use actix_web_actors::ws;
 use chrono::{DateTime, Local};
@@ -47,23 +47,25 @@ pub struct UserData {
     pub user_session_id: UserSessionId,
     pub lobby_id: LobbyId,
     pub ping: i64,
+    pub spectator: bool,
 }
 
 impl UserData {
-    fn default(user_session_id: UserSessionId, lobby: LobbyId) -> Self {
+    fn default(user_session_id: UserSessionId, lobby: LobbyId, spectator: bool) -> Self {
         UserData {
             websocket_session_id: None,
             user_session_id,
             lobby_id: lobby,
             ping: 0,
+            spectator,
         }
     }
 }
 
 impl WsSession {
-        pub fn default(user_session_id: &UserSessionId,lobby_id: &LobbyId, srv: &web::Data<Addr<game::GameServer>>, lobby: &Addr<Lobby>) -> Self {
+        pub fn default(user_session_id: &UserSessionId,lobby_id: &LobbyId, spectator: bool, srv: &web::Data<Addr<game::GameServer>>, lobby: &Addr<Lobby>) -> Self {
             WsSession {
-                player: UserData::default(user_session_id.clone(), lobby_id.clone()),
+                player: UserData::default(user_session_id.clone(), lobby_id.clone(), spectator),
                 hb: Instant::now(),
                 game_server_addr: srv.get_ref().clone(),
                 lobby_addr: lobby.clone(),
@@ -110,6 +112,7 @@ impl WsSession {
             user_session_id: self.player.user_session_id.clone(),
             addr: ctx.address().recipient(),
             ping: self.player.ping,
+            spectator: self.player.spectator,
         })
         .into_actor(self)
         .then(|res, act, ctx| {
@@ -214,6 +217,10 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for WsSession {
                     //TODO: make deflate alogithm de-/activatable again for development
                     if let Ok(bytes) = decompress(&data) {
                         match serde_json::from_slice::<WebsocketSessionEvent>(&bytes) {
+                            // spectators may only keep their media playback in sync
+                            Ok(event) if self.player.spectator && !matches!(event, WebsocketSessionEvent::MediaEvent(MediaEvent::SyncForwardRequest(_) | MediaEvent::SyncBackwardRequest)) => {
+                                println!("Ignoring event from spectator {:?}", self.player.user_session_id.id);
+                            }
                             Ok(event) => {
                                 match event.clone() {
                                     WebsocketSessionEvent::ChooseQuestion(vector2d) => {
@@ -234,6 +241,55 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for WsSession {
                                             vector2d
                                         });
                                     }
+                                    WebsocketSessionEvent::WrongUserSessionAnswer(wrong_user_session_id, vector2d) => {
+                                        self.lobby_addr.do_send(LobbyWrongAnswer{
+                                            user_data: self.player.clone(),
+                                            wrong_user_session_id,
+                                            vector2d,
+                                        });
+                                    }
+                                    WebsocketSessionEvent::AdjustUserSessionScore(adjust_user_session_id, score) => {
+                                        self.lobby_addr.do_send(AdjustLobbySessionScore{
+                                            user_data: self.player.clone(),
+                                            adjust_user_session_id,
+                                            score,
+                                        });
+                                    }
+                                    WebsocketSessionEvent::GameStateEvent(event) => {
+                                        self.lobby_addr.do_send(ReciveGameStateEvent{
+                                            user_data: self.player.clone(),
+                                            event,
+                                        });
+                                    }
+                                    WebsocketSessionEvent::TeamEvent(event) => {
+                                        self.lobby_addr.do_send(ReciveTeamEvent{
+                                            user_data: self.player.clone(),
+                                            event,
+                                        });
+                                    }
+                                    WebsocketSessionEvent::UpdateBuzzerSettings(buzzer_settings) => {
+                                        self.lobby_addr.do_send(LobbyUpdateBuzzerSettings{
+                                            user_data: self.player.clone(),
+                                            buzzer_settings,
+                                        });
+                                    }
+                                    WebsocketSessionEvent::UpdateTimers(timers) => {
+                                        self.lobby_addr.do_send(LobbyUpdateTimers{
+                                            user_data: self.player.clone(),
+                                            timers,
+                                        });
+                                    }
+                                    WebsocketSessionEvent::AllowSpectators(allow) => {
+                                        self.lobby_addr.do_send(LobbyAllowSpectators{
+                                            user_data: self.player.clone(),
+                                            allow,
+                                        });
+                                    }
+                                    WebsocketSessionEvent::NextRound => {
+                                        self.lobby_addr.do_send(LobbyNextRound{
+                                            user_data: self.player.clone(),
+                                        });
+                                    }
                                     WebsocketSessionEvent::MediaEvent(media_event) => {
                                         match media_event {
                                             MediaEvent::VideoEvent(event) => {
@@ -247,6 +303,12 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for WsSession {
                                                     event
                                                 })
                                             },
+                                            MediaEvent::ImageRevealEvent(event) => {
+                                                self.lobby_addr.do_send(ReciveImageRevealEvent{
+                                                    user_session_id: self.player.user_session_id.clone(),
+                                                    event
+                                                })
+                                            },
                                             MediaEvent::SyncBackwardRequest => {
                                                 let id = match self.player.websocket_session_id.clone() {
                                                     Some(data) => data,
@@ -273,7 +335,17 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for WsSession {
                                             BuzzorEvent::BuzzorClick => {
                                                 self.lobby_addr.do_send(BuzzerClicked{
                                                     user_session_id: self.player.user_session_id.clone(),
+                                                    websocket_session_id: self.player.websocket_session_id.clone(),
+                                                    current_time: Local::now(),
+                                                    client_time: None,
+                                                });
+                                            }
+                                            BuzzorEvent::BuzzorClickAt(client_time) => {
+                                                self.lobby_addr.do_send(BuzzerClicked{
+                                                    user_session_id: self.player.user_session_id.clone(),
+                                                    websocket_session_id: self.player.websocket_session_id.clone(),
                                                     current_time: Local::now(),
+                                                    client_time: Some(client_time),
                                                 });
                                             }
                                             BuzzorEvent::BuzzorStarting => {
@@ -284,7 +356,9 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for WsSession {
                                             BuzzorEvent::BuzzorStop => {
                                                 self.lobby_addr.do_send(BuzzerClicked{
                                                     user_session_id: self.player.user_session_id.clone(),
+                                                    websocket_session_id: self.player.websocket_session_id.clone(),
                                                     current_time: Local::now(),
+                                                    client_time: None,
                                                 });
                                             },
                                             BuzzorEvent::BuzzorReset => {
@@ -295,6 +369,61 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for WsSession {
                                         }
                                
                                     }
+                                    WebsocketSessionEvent::FinalJeopardyEvent(final_event) => {
+                                        let id = match self.player.websocket_session_id.clone() {
+                                            Some(data) => data,
+                                            None => return,
+                                        };
+                                        self.lobby_addr.do_send(ReciveFinalJeopardyEvent{
+                                            user_session_id: self.player.user_session_id.clone(),
+                                            websocket_session_id: id,
+                                            event: final_event,
+                                        });
+                                    }
+                                    WebsocketSessionEvent::DailyDoubleEvent(daily_double_event) => {
+                                        let id = match self.player.websocket_session_id.clone() {
+                                            Some(data) => data,
+                                            None => return,
+                                        };
+                                        self.lobby_addr.do_send(ReciveDailyDoubleEvent{
+                                            user_session_id: self.player.user_session_id.clone(),
+                                            websocket_session_id: id,
+                                            event: daily_double_event,
+                                        });
+                                    }
+                                    WebsocketSessionEvent::WrittenAnswerEvent(written_event) => {
+                                        let id = match self.player.websocket_session_id.clone() {
+                                            Some(data) => data,
+                                            None => return,
+                                        };
+                                        self.lobby_addr.do_send(ReciveWrittenAnswerEvent{
+                                            user_session_id: self.player.user_session_id.clone(),
+                                            websocket_session_id: id,
+                                            event: written_event,
+                                        });
+                                    }
+                                    WebsocketSessionEvent::MultipleChoiceEvent(multiple_choice_event) => {
+                                        let id = match self.player.websocket_session_id.clone() {
+                                            Some(data) => data,
+                                            None => return,
+                                        };
+                                        self.lobby_addr.do_send(ReciveMultipleChoiceEvent{
+                                            user_session_id: self.player.user_session_id.clone(),
+                                            websocket_session_id: id,
+                                            event: multiple_choice_event,
+                                        });
+                                    }
+                                    WebsocketSessionEvent::EstimationEvent(estimation_event) => {
+                                        let id = match self.player.websocket_session_id.clone() {
+                                            Some(data) => data,
+                                            None => return,
+                                        };
+                                        self.lobby_addr.do_send(ReciveEstimationEvent{
+                                            user_session_id: self.player.user_session_id.clone(),
+                                            websocket_session_id: id,
+                                            event: estimation_event,
+                                        });
+                                    }
                                 
                                 }
                             }